@@ -0,0 +1,4 @@
+-- The databases of the services which can store their entities in Postgres,
+-- with `db_backend = "postgres"`. Each has its own migrations.
+CREATE DATABASE user_db;
+CREATE DATABASE tenant_db;
@@ -19,6 +19,8 @@ vault secrets enable -version=2 -path=auth-service-secrets-kv kv
 echo "Adding auth-service secrets..."
 vault kv put auth-service-secrets-kv/dev/mongo user_name=test_user password=test_password
 vault kv put auth-service-secrets-kv/dev/redis password=test_password
+# The access token signing key must be the same for every service.
+vault kv put auth-service-secrets-kv/dev/access_token signing_key=test_access_token_signing_key
 
 echo "Initializing user-service vault..."
 vault secrets enable -version=2 -path=user-service-secrets-kv kv
@@ -29,6 +31,8 @@ vault secrets enable -version=2 -path=user-service-secrets-kv kv
 echo "Adding user-service secrets..."
 vault kv put user-service-secrets-kv/dev/mongo user_name=test_user password=test_password
 vault kv put user-service-secrets-kv/dev/redis password=test_password
+vault kv put user-service-secrets-kv/dev/postgres user_name=test_user password=test_password
+vault kv put user-service-secrets-kv/dev/access_token signing_key=test_access_token_signing_key
 
 echo "Initializing tenant-service vault..."
 vault secrets enable -version=2 -path=tenant-service-secrets-kv kv
@@ -39,11 +43,22 @@ vault secrets enable -version=2 -path=tenant-service-secrets-kv kv
 echo "Adding tenant-service secrets..."
 vault kv put tenant-service-secrets-kv/dev/mongo user_name=test_user password=test_password
 vault kv put tenant-service-secrets-kv/dev/redis password=test_password
+vault kv put tenant-service-secrets-kv/dev/postgres user_name=test_user password=test_password
+vault kv put tenant-service-secrets-kv/dev/access_token signing_key=test_access_token_signing_key
 
 echo "Initializing notification-service vault..."
 vault secrets enable -version=2 -path=notification-service-secrets-kv kv
 echo "Adding notification-service secrets..."
+vault kv put notification-service-secrets-kv/dev/mongo user_name=test_user password=test_password
 vault kv put notification-service-secrets-kv/dev/redis password=test_password
 vault kv put notification-service-secrets-kv/dev/smtp user_name=test_user password=test_password
+vault kv put notification-service-secrets-kv/dev/unsubscribe signing_key=test_signing_key
+vault kv put notification-service-secrets-kv/dev/access_token signing_key=test_access_token_signing_key
+
+echo "Initializing audit-service vault..."
+vault secrets enable -version=2 -path=audit-service-secrets-kv kv
+echo "Adding audit-service secrets..."
+vault kv put audit-service-secrets-kv/dev/mongo user_name=test_user password=test_password
+vault kv put audit-service-secrets-kv/dev/access_token signing_key=test_access_token_signing_key
 
 echo "Done adding secrets to vault server."
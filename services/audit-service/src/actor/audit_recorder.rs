@@ -0,0 +1,43 @@
+use crate::{
+    context::AppContext,
+    model::domain::audit_entry::AuditEntry,
+    repository::audit_entry_repository,
+};
+use actix::{Actor, Context, Handler};
+use common::model::event::{v1::Event, EventMessage};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+/// Appends the audit records emitted by the services to the audit log.
+pub struct AuditRecorder {
+    pub context: Arc<AppContext>,
+}
+
+impl Actor for AuditRecorder {
+    type Context = Context<Self>;
+}
+
+// Define handler for `Event` message
+impl Handler<Event> for AuditRecorder {
+    type Result = Result<(), std::io::Error>;
+
+    fn handle(&mut self, event: Event, _: &mut Context<Self>) -> Self::Result {
+        let record = match event {
+            Event::AuditRecorded(EventMessage { payload, .. }) => payload,
+            event => {
+                warn!("Skipping event not recording an action: {:?}", event);
+                return Ok(());
+            }
+        };
+        let context = Arc::clone(&self.context);
+
+        actix::spawn(async move {
+            let entry = AuditEntry::from(record);
+            if let Err(err) = audit_entry_repository::insert_one(&entry, context.db()).await {
+                error!("Failed to record audit entry {}: {}", entry, err);
+            }
+        });
+
+        Ok(())
+    }
+}
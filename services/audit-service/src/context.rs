@@ -0,0 +1,23 @@
+use mongodb::Database;
+use std::sync::Arc;
+
+/// The AppContext contains all the global data commonly used in the vast
+/// majority of request handlers.
+#[derive(Debug)]
+pub struct AppContext {
+    pub(crate) db: Arc<Database>,
+    pub(crate) export_max_records: u64,
+}
+
+impl AppContext {
+    /// A MongoDB reference to the underlying database. Used to interract with
+    /// collections, etc.
+    pub fn db(&self) -> &Database {
+        &self.db
+    }
+
+    /// Most records in a CSV export of the audit log.
+    pub fn export_max_records(&self) -> u64 {
+        self.export_max_records
+    }
+}
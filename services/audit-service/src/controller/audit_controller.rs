@@ -0,0 +1,105 @@
+use actix_web::{
+    web::{self, Bytes},
+    HttpResponse,
+    Scope,
+};
+use common::{
+    error::{ApiResult, InternalError},
+    model::request::{filter_request::FilterRequest, page_request::PageRequest},
+    util::principal::Principal,
+};
+use futures::{stream, StreamExt};
+use validator::Validate;
+
+use crate::{
+    context::AppContext,
+    model::domain::audit_entry::{csv_line, prelude::CSV_COLUMNS, AuditEntry},
+    repository::audit_entry_repository,
+};
+
+pub fn router() -> Scope {
+    web::scope("/audit")
+        .service(web::resource("").route(web::get().to(query_paginated)))
+        .service(web::resource("/export").route(web::get().to(export)))
+}
+
+/// Http handler for querying the audit log with pagination. Only
+/// administrators can read it, and those of a tenant only the entries of
+/// their tenant.
+#[tracing::instrument(
+    name = "query_paginated",
+    skip(entry, filter_request, page_request),
+    level = "info"
+)]
+pub async fn query_paginated(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(entry): web::Query<AuditEntry>,
+    web::Query(filter_request): web::Query<FilterRequest>,
+    web::Query(page_request): web::Query<PageRequest>,
+) -> ApiResult {
+    principal.require_admin()?;
+    filter_request.validate()?;
+    page_request.validate()?;
+
+    let entries = audit_entry_repository::find_all_paginated_with_query(
+        &scoped(entry, &principal),
+        &filter_request,
+        &page_request,
+        ctx.db(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(entries))
+}
+
+/// Http handler for exporting the audit log as CSV, oldest entries first,
+/// with the same conditions as the queries. Exports are bounded, narrower
+/// filters being needed beyond.
+#[tracing::instrument(name = "export", skip(entry, filter_request), level = "info")]
+pub async fn export(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(entry): web::Query<AuditEntry>,
+    web::Query(filter_request): web::Query<FilterRequest>,
+) -> ApiResult {
+    principal.require_admin()?;
+    filter_request.validate()?;
+
+    let entries = audit_entry_repository::stream_with_query(
+        &scoped(entry, &principal),
+        &filter_request,
+        ctx.export_max_records(),
+        ctx.db(),
+    )
+    .await?;
+
+    let header = csv_line(
+        &CSV_COLUMNS
+            .iter()
+            .map(|column| Some(column.to_string()))
+            .collect::<Vec<_>>(),
+    );
+    let lines = entries.map(|entry| {
+        entry
+            .map_err(InternalError::from)
+            .and_then(|entry| entry.csv_line())
+            .map(Bytes::from)
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv")
+        .insert_header(("Content-Disposition", "attachment; filename=\"audit.csv\""))
+        .streaming(stream::once(async move { Ok(Bytes::from(header)) }).chain(lines)))
+}
+
+// `entry` restricted to the tenant of the administrators of a tenant
+fn scoped(entry: AuditEntry, principal: &Principal) -> AuditEntry {
+    match principal.tenant_id {
+        Some(tenant_id) => AuditEntry {
+            tenant_id: Some(tenant_id.to_string()),
+            ..entry
+        },
+        None => entry,
+    }
+}
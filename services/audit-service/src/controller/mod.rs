@@ -0,0 +1,5 @@
+mod audit_controller;
+mod health_controller;
+mod router;
+
+pub use router::global_router;
@@ -0,0 +1,6 @@
+pub fn global_router(cfg: &mut actix_web::web::ServiceConfig) {
+    use super::*;
+
+    cfg.service(audit_controller::router());
+    cfg.service(health_controller::router());
+}
@@ -0,0 +1,130 @@
+mod actor;
+mod context;
+mod controller;
+mod model;
+mod repository;
+mod secrets;
+mod settings;
+
+use crate::{context::AppContext, settings::Settings};
+use actix::{Actor, Addr};
+use actix_web::{web, App, HttpResponse, HttpServer, Responder};
+use actix_web_opentelemetry::RequestTracing;
+use actor::audit_recorder::AuditRecorder;
+use common::{
+    client::db_mongo,
+    error::REDACTED_ERRORS,
+    model::event::v1::{audit::prelude::SERVICE_AUDIT_SUBJECT, Event},
+    util::{access_token::AccessTokenKey, actix_json_config::json_extractor_config, telemetry},
+};
+use nats_actor::subscriber::{subscribe_to_nats, NatsStreamMessage, NatsSubscriberConfig};
+use secrets::Secrets;
+use std::sync::Arc;
+use tracing::{info, warn};
+use tracing_actix_web::TracingLogger;
+
+pub async fn server() -> Result<(), std::io::Error> {
+    // configure tracing subscriber
+    let app_name = concat!(env!("CARGO_PKG_NAME"), "-", env!("CARGO_PKG_VERSION")).to_string();
+
+    let settings = Settings::load().expect("Failed to read configuration.");
+    *REDACTED_ERRORS.write() = settings.log.redacted_errors;
+
+    let jaeger_url = format!(
+        "{}:{}",
+        &settings.tracer.jaeger.host, &settings.tracer.jaeger.port,
+    );
+    telemetry::config_telemetry(&app_name, &jaeger_url);
+
+    tracing::info!("services starting...");
+
+    // Start Web server
+    start_web_service(&app_name, settings).await?;
+    // Ensure all spans have been reported
+    opentelemetry::global::shutdown_tracer_provider();
+    Ok(())
+}
+
+pub async fn start_web_service(
+    app_name: &str,
+    configuration: Settings,
+) -> Result<(), std::io::Error> {
+    let this_server_address = format!(
+        "{}:{}",
+        configuration.application.host, configuration.application.port
+    );
+
+    let secrets: Secrets = secrets::read(&configuration).await?;
+    // Verifies the access tokens asserting the principal of the requests
+    let access_token_key = AccessTokenKey::new(secrets.access_token.signing_key.clone());
+
+    let db_client = db_mongo::connect(app_name, &configuration.db, &secrets.db)
+        .await
+        .expect("db client connection failure");
+    db_mongo::migrate(&db_client, &repository::migrations::SCHEMA)
+        .await
+        .expect("db schema migration failure");
+
+    // Instantiate the application context. This application state will be
+    // cloned for each Actix thread but the Arc of the DbContext will be
+    // reused in each Actix thread.
+    let app_context = web::Data::new(AppContext {
+        db: Arc::new(db_client),
+        export_max_records: configuration.export.max_records,
+    });
+
+    // Start the recorder actor appending the audit records to the audit log
+    let audit_recorder = AuditRecorder {
+        context: Arc::clone(&app_context),
+    }
+    .start();
+
+    // start NATS subscriber for the audit records
+    let config = NatsSubscriberConfig {
+        client_settings: configuration.nats.clone(),
+        subject: SERVICE_AUDIT_SUBJECT.into(),
+        mailbox_size: configuration.application.nats_subscriber_mailbox_size,
+    };
+    actix::spawn(async move {
+        subscribe_to_nats(config, move |msg: NatsStreamMessage| {
+            forward_event(&audit_recorder, msg);
+            Ok(())
+        })
+        .await
+        .expect("nats connection/subscriber setup failure");
+    });
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_context.clone())
+            .app_data(access_token_key.clone())
+            .app_data(json_extractor_config(4096))
+            .wrap(TracingLogger::default())
+            .wrap(RequestTracing::new())
+            .service(web::scope("/audit/v1.0").configure(controller::global_router))
+            .default_service(web::get().to(not_found))
+    })
+    .bind(&this_server_address)?;
+
+    server
+        .workers(configuration.application.workers)
+        .run()
+        .await?;
+
+    Ok(())
+}
+
+fn forward_event(recorder: &Addr<AuditRecorder>, msg: NatsStreamMessage) {
+    info!("Received event {:?}", msg);
+    let event: Result<Event, _> = serde_json::from_slice::<cloudevents::Event>(&msg.msg.data)
+        .map_err(common::error::InternalError::from)
+        .and_then(|event| event.try_into());
+    match event {
+        Ok(event) => recorder.do_send(event),
+        Err(err) => warn!("Skipping unreadable event: {}", err),
+    }
+}
+
+pub async fn not_found() -> impl Responder {
+    HttpResponse::NotFound().body("the requested resource does not exist")
+}
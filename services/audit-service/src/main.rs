@@ -0,0 +1,4 @@
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    audit_service::server().await
+}
@@ -0,0 +1,215 @@
+use common::{
+    client::{
+        mongo_filter::{FilterField, FilterFieldType},
+        mongo_repository::MongoEntity,
+    },
+    error::InternalError,
+    model::event::v1::audit::{AuditChange, AuditRecord},
+};
+use mongodb::bson;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::{fmt, fmt::Formatter};
+
+use self::prelude::*;
+
+pub mod prelude {
+    // Collection name
+    pub const COLLECTION_AUDIT_ENTRIES: &str = "audit_entries";
+
+    // Audit entry fields.
+    pub const ID: &str = "_id";
+    pub const ACTOR_ID: &str = "ACTOR_ID";
+    pub const ACTOR_TENANT_ID: &str = "ACTOR_TENANT_ID";
+    pub const ACTION: &str = "ACTION";
+    pub const TARGET_TYPE: &str = "TARGET_TYPE";
+    pub const TARGET_ID: &str = "TARGET_ID";
+    pub const TENANT_ID: &str = "TENANT_ID";
+    pub const CHANGES: &str = "CHANGES";
+    pub const IP: &str = "IP";
+    pub const TRACE_ID: &str = "TRACE_ID";
+    pub const SOURCE: &str = "SOURCE";
+    pub const OCCURRED_AT: &str = "OCCURRED_AT";
+    pub const RECORDED_AT: &str = "RECORDED_AT";
+
+    // Columns of the CSV exports
+    pub const CSV_COLUMNS: &[&str] = &[
+        OCCURRED_AT,
+        ACTOR_ID,
+        ACTOR_TENANT_ID,
+        ACTION,
+        TARGET_TYPE,
+        TARGET_ID,
+        TENANT_ID,
+        CHANGES,
+        IP,
+        TRACE_ID,
+        SOURCE,
+        ID,
+    ];
+}
+
+/// An administrative action as stored in the audit log. Entries are only ever
+/// inserted, never updated nor deleted.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    #[serde(rename = "_id")]
+    pub id: Option<bson::Uuid>,
+    #[serde(rename = "ACTOR_ID")]
+    pub actor_id: Option<String>,
+    #[serde(rename = "ACTOR_TENANT_ID")]
+    pub actor_tenant_id: Option<String>,
+    #[serde(rename = "ACTION")]
+    pub action: Option<String>,
+    #[serde(rename = "TARGET_TYPE")]
+    pub target_type: Option<String>,
+    #[serde(rename = "TARGET_ID")]
+    pub target_id: Option<String>,
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: Option<String>,
+    #[serde(rename = "CHANGES")]
+    pub changes: Option<Vec<AuditChange>>,
+    #[serde(rename = "IP")]
+    pub ip: Option<String>,
+    #[serde(rename = "TRACE_ID")]
+    pub trace_id: Option<String>,
+    #[serde(rename = "SOURCE")]
+    pub source: Option<String>,
+    #[serde(rename = "OCCURRED_AT")]
+    pub occurred_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "RECORDED_AT")]
+    pub recorded_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl MongoEntity for AuditEntry {
+    type Id = bson::Uuid;
+
+    const COLLECTION: &'static str = COLLECTION_AUDIT_ENTRIES;
+    const SORT_FIELDS: &'static [&'static str] = &[OCCURRED_AT];
+    const FILTER_FIELDS: &'static [FilterField] = &[
+        FilterField::new(ID, FilterFieldType::Uuid),
+        FilterField::new(ACTOR_ID, FilterFieldType::String),
+        FilterField::new(ACTOR_TENANT_ID, FilterFieldType::String),
+        FilterField::new(ACTION, FilterFieldType::String),
+        FilterField::new(TARGET_TYPE, FilterFieldType::String),
+        FilterField::new(TARGET_ID, FilterFieldType::String),
+        FilterField::new(TENANT_ID, FilterFieldType::String),
+        FilterField::new(IP, FilterFieldType::String),
+        FilterField::new(TRACE_ID, FilterFieldType::String),
+        FilterField::new(SOURCE, FilterFieldType::String),
+        FilterField::new(OCCURRED_AT, FilterFieldType::DateTime),
+    ];
+
+    fn id(&self) -> Option<&bson::Uuid> {
+        self.id.as_ref()
+    }
+
+    fn set_id(&mut self, id: bson::Uuid) {
+        self.id = Some(id);
+    }
+}
+
+impl From<AuditRecord> for AuditEntry {
+    fn from(record: AuditRecord) -> Self {
+        AuditEntry {
+            id: Some(bson::Uuid::new()),
+            actor_id: record.actor_id,
+            actor_tenant_id: record.actor_tenant_id,
+            action: Some(record.action),
+            target_type: Some(record.target_type),
+            target_id: Some(record.target_id),
+            tenant_id: record.tenant_id,
+            changes: Some(record.changes),
+            ip: record.ip,
+            trace_id: record.trace_id,
+            source: Some(record.source),
+            occurred_at: Some(record.occurred_at),
+            recorded_at: Some(chrono::Utc::now()),
+        }
+    }
+}
+
+impl AuditEntry {
+    /// The line of this entry in a CSV export, its fields in the order of
+    /// `CSV_COLUMNS`.
+    pub fn csv_line(&self) -> Result<String, InternalError> {
+        let changes = match &self.changes {
+            Some(changes) => Some(serde_json::to_string(changes)?),
+            None => None,
+        };
+        Ok(csv_line(&[
+            self.occurred_at.map(|occurred_at| occurred_at.to_rfc3339()),
+            self.actor_id.clone(),
+            self.actor_tenant_id.clone(),
+            self.action.clone(),
+            self.target_type.clone(),
+            self.target_id.clone(),
+            self.tenant_id.clone(),
+            changes,
+            self.ip.clone(),
+            self.trace_id.clone(),
+            self.source.clone(),
+            self.id.map(|id| id.to_string()),
+        ]))
+    }
+}
+
+/// A CSV line of `fields`, quoted as needed, an absent field being empty.
+pub fn csv_line(fields: &[Option<String>]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| csv_field(field.as_deref().unwrap_or_default()))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+fn csv_field(value: &str) -> String {
+    // values read as formulas by spreadsheets are escaped
+    let value = match value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        true => format!("'{value}"),
+        false => value.to_string(),
+    };
+    match value.contains([',', '"', '\r', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value,
+    }
+}
+
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", serde_json::to_string(&self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_formulas() {
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-1"), "'-1");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("\t=1"), "'\t=1");
+        assert_eq!(csv_field("a=1"), "a=1");
+    }
+
+    #[test]
+    fn quotes_separators() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+        assert_eq!(csv_field("ab"), "ab");
+    }
+
+    #[test]
+    fn joins_fields_in_a_line() {
+        let line = csv_line(&[Some("a".to_string()), None, Some("b,c".to_string())]);
+
+        assert_eq!(line, "a,,\"b,c\"\r\n");
+    }
+}
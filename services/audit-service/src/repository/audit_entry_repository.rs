@@ -0,0 +1,70 @@
+use crate::model::domain::audit_entry::{prelude::*, AuditEntry};
+use common::{
+    client::mongo_repository::{find_page, MongoRepository},
+    error::InternalError,
+    model::{
+        request::{
+            filter_request::FilterRequest,
+            page_request::{PageRequest, SortOrder},
+        },
+        response::page_response::PageResponse,
+    },
+};
+use mongodb::{bson::doc, options::FindOptions, Cursor, Database};
+
+type AuditEntryRepository = MongoRepository<AuditEntry>;
+
+/// Append `entry` to the audit log.
+pub async fn insert_one(entry: &AuditEntry, db: &Database) -> Result<AuditEntry, InternalError> {
+    AuditEntryRepository::new(db).insert_one(entry).await
+}
+
+/// The page `page_request` of the entries matching the fields set in `cond`
+/// and the filter expression of `filter_request`, latest first unless
+/// ordered otherwise.
+pub async fn find_all_paginated_with_query(
+    cond: &AuditEntry,
+    filter_request: &FilterRequest,
+    page_request: &PageRequest,
+    db: &Database,
+) -> Result<PageResponse<AuditEntry>, InternalError> {
+    let filter = AuditEntryRepository::filter_with_expression(cond, filter_request)?;
+    let sort = page_request.sort_order(&[], SortOrder::desc(OCCURRED_AT))?;
+    find_page(
+        AuditEntryRepository::new(db).collection(),
+        filter,
+        &sort,
+        page_request,
+    )
+    .await
+}
+
+/// The entries matching the fields set in `cond` and the filter expression of
+/// `filter_request`, oldest first, provided there are at most `max_entries`.
+pub async fn stream_with_query(
+    cond: &AuditEntry,
+    filter_request: &FilterRequest,
+    max_entries: u64,
+    db: &Database,
+) -> Result<Cursor<AuditEntry>, InternalError> {
+    let filter = AuditEntryRepository::filter_with_expression(cond, filter_request)?;
+    let repository = AuditEntryRepository::new(db);
+
+    let count = repository
+        .collection()
+        .count_documents(filter.clone(), None)
+        .await?;
+    if count > max_entries {
+        return Err(InternalError::RequestFormatError {
+            reason: format!(
+                "{count} audit entries match, exports are limited to {max_entries}: narrow the \
+                 filter"
+            ),
+        });
+    }
+
+    let find_opts = FindOptions::builder()
+        .sort(doc! { OCCURRED_AT: 1, ID: 1 })
+        .build();
+    Ok(repository.collection().find(filter, find_opts).await?)
+}
@@ -0,0 +1,41 @@
+use crate::model::domain::audit_entry::prelude::*;
+use common::{
+    client::db_mongo::{self, IndexDefinition, Migration, Schema},
+    error::InternalError,
+};
+use futures::future::BoxFuture;
+use mongodb::Database;
+
+pub const SCHEMA: Schema = Schema {
+    migrations: &[Migration::new(1, "create collections", create_collections)],
+    indexes: &[
+        IndexDefinition::new(
+            COLLECTION_AUDIT_ENTRIES,
+            "audit_entries_occurred_at",
+            &[(OCCURRED_AT, 1)],
+        ),
+        // the actions of an actor, of a tenant and on a target, latest first
+        IndexDefinition::new(
+            COLLECTION_AUDIT_ENTRIES,
+            "audit_entries_actor_id",
+            &[(ACTOR_ID, 1), (OCCURRED_AT, -1)],
+        ),
+        IndexDefinition::new(
+            COLLECTION_AUDIT_ENTRIES,
+            "audit_entries_tenant_id",
+            &[(TENANT_ID, 1), (OCCURRED_AT, -1)],
+        ),
+        IndexDefinition::new(
+            COLLECTION_AUDIT_ENTRIES,
+            "audit_entries_target",
+            &[(TARGET_TYPE, 1), (TARGET_ID, 1), (OCCURRED_AT, -1)],
+        ),
+    ],
+};
+
+fn create_collections(db: &Database) -> BoxFuture<'_, Result<(), InternalError>> {
+    Box::pin(db_mongo::create_collections(
+        db,
+        &[COLLECTION_AUDIT_ENTRIES],
+    ))
+}
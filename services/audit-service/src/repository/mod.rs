@@ -0,0 +1,2 @@
+pub mod audit_entry_repository;
+pub mod migrations;
@@ -0,0 +1,29 @@
+use crate::settings::Settings;
+use common::{
+    client::{db_mongo::MongoClientSecrets, sm_vault},
+    error::InternalError,
+    util::access_token::AccessTokenSecrets,
+};
+use serde::Deserialize;
+use vaultrs::client::VaultClient;
+
+#[derive(Debug, Deserialize)]
+pub struct Secrets {
+    pub db: MongoClientSecrets,
+    pub access_token: AccessTokenSecrets,
+}
+
+pub async fn read(settings: &Settings) -> Result<Secrets, InternalError> {
+    let vault_client: VaultClient = sm_vault::connect(&settings.vault)?;
+
+    let db_secrets: MongoClientSecrets =
+        sm_vault::get_secret_value(&vault_client, &settings.db_secrets_path).await?;
+
+    let access_token_secrets: AccessTokenSecrets =
+        sm_vault::get_secret_value(&vault_client, &settings.access_token_secrets_path).await?;
+
+    Ok(Secrets {
+        db: db_secrets,
+        access_token: access_token_secrets,
+    })
+}
@@ -1,19 +1,39 @@
 use actix::Addr;
-use common::client::cache_redis::Cache;
-use mongodb::Database;
-use nats_actor::publisher::NatsPublisher;
+use chrono::Duration;
+use common::{
+    client::cache_redis::Cache,
+    error::InternalError,
+    model::event::{
+        v1::{audit::AuditRecord, Event},
+        EventMessage,
+        EventMetadata,
+    },
+    util::access_token::AccessTokenKey,
+};
+use mongodb::{Client, Database};
+use nats_actor::{publisher::NatsPublisher, EventMessage as NatsEventMessage};
 use std::sync::Arc;
 
 /// The AppContext contains all the global data commonly used in the vast
 /// majority of request handlers.
 #[derive(Debug)]
 pub struct AppContext {
+    pub(crate) mongo_client: Arc<Client>,
     pub(crate) db: Arc<Database>,
     pub(crate) cache: Arc<Cache>,
     pub(crate) event_publisher: Arc<Addr<NatsPublisher>>,
+    pub(crate) audit_publisher: Arc<Addr<NatsPublisher>>,
+    pub(crate) access_token_key: AccessTokenKey,
+    pub(crate) access_token_ttl: Duration,
+    pub(crate) otp_sender: String,
 }
 
 impl AppContext {
+    /// The MongoDB client the database belongs to. Used to run transactions.
+    pub fn mongo_client(&self) -> &Client {
+        &self.mongo_client
+    }
+
     /// A MongoDB reference to the underlying database. Used to interract with
     /// collections, etc.
     pub fn db(&self) -> &Database {
@@ -29,4 +49,37 @@ impl AppContext {
     pub fn event_publisher(&self) -> &Addr<NatsPublisher> {
         &self.event_publisher
     }
+
+    /// Key signing the access tokens issued on login.
+    pub fn access_token_key(&self) -> &AccessTokenKey {
+        &self.access_token_key
+    }
+
+    /// Lifetime of the access tokens issued on login.
+    pub fn access_token_ttl(&self) -> Duration {
+        self.access_token_ttl
+    }
+
+    /// The address the login codes are emailed from.
+    pub fn otp_sender(&self) -> &str {
+        &self.otp_sender
+    }
+
+    /// Publish `record` to the audit log, in the background. A failed publish
+    /// is retried while this instance runs, but the record is lost if it
+    /// stops first: the log is best effort, not written along with the action.
+    pub fn audit(&self, record: AuditRecord) -> Result<(), InternalError> {
+        let meta = EventMetadata::new(
+            record.source.clone(),
+            record.trace_id.as_deref().unwrap_or("trace_id"),
+        );
+        let audit_event = Event::AuditRecorded(EventMessage {
+            meta,
+            payload: record,
+        });
+        self.audit_publisher.do_send(NatsEventMessage {
+            event: audit_event.try_into()?,
+        });
+        Ok(())
+    }
 }
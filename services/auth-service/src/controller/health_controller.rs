@@ -31,6 +31,7 @@ pub async fn health(ctx: web::Data<AppContext>) -> Result<HttpResponse, Internal
     Ok(HttpResponseBuilder::new(status).json(json!(
         {
             "MongoDB": health["mongodb"],
+            "cache": ctx.cache().stats(),
         }
     )))
 }
@@ -9,15 +9,18 @@ use actix_web::{
 use bson::Uuid;
 use chrono::Utc;
 use common::{
+    client::db_mongo,
     error::{ApiResult, InternalError},
     model::event::{
         v1::{
-            auth::{prelude::SERVICE_AUTH_SUBJECT, UserCreatedMessage},
+            audit::prelude::{AUDIT_ACTION_USER_INVITE, AUDIT_TARGET_USER},
+            auth::{prelude::SERVICE_AUTH_SUBJECT, SendOtpMessage, UserCreatedMessage},
             Event,
         },
         EventMessage,
         EventMetadata,
     },
+    util::{audit::AuditActor, principal::Principal},
 };
 use futures::TryFutureExt;
 use nats_actor::{publisher::NatsPublisher, EventMessage as NatsEventMessage};
@@ -28,9 +31,10 @@ use crate::{
     model::{
         domain::{
             login::LoginAttempt,
-            user::{User, UserRole, UserStatus},
+            user::{prelude::CACHE_ENTITY_USER, User, UserRole, UserStatus},
         },
         request::login::login_request::{Identify, Invite, InviteConfirmation, Verify},
+        response::login_response::AccessToken,
     },
     repository::{login_repository, user_repository},
 };
@@ -42,40 +46,77 @@ pub fn router(cfg: &mut web::ServiceConfig) {
     cfg.service(verify);
 }
 
-/// Adminstrator can invite users with this API by providing their email address that will be used
-/// to send a auth login link to them.
-#[tracing::instrument(name = "invite", skip(invite_request), level = "info")]
+/// Adminstrator can invite users with this API by providing their email address, to which the
+/// invitation code is sent. Administrators acting for a tenant invite into their tenant, and only
+/// platform administrators invite administrators outside of any tenant.
+#[tracing::instrument(name = "invite", skip(actor, invite_request), level = "info")]
 #[post("/invite")]
 pub async fn invite(
     ctx: web::Data<AppContext>,
+    principal: Principal,
+    actor: AuditActor,
     web::Query(invite_request): web::Query<Invite>,
 ) -> ApiResult {
-    // #TODO check if called by admin
     // #TODO check if email domain is whitelisted
-    // #TODO generate random uuid, set expiry as per policy
+    // #TODO set expiry of the invitation as per policy
     //       we can manage expiry date based on created_date
-    // #TODO send email to user
     invite_request.validate()?;
+    let tenant_id = invite_request.tenant(&principal)?;
+    let email = invite_request
+        .email
+        .ok_or(InternalError::RequestFormatError {
+            reason: "require fields: `email`".to_string(),
+        })?;
 
     // #TODO if email is already invited or registered
 
     let now = Some(Utc::now());
     let to_create = User {
         id: Some(bson::Uuid::new()),
-        email: invite_request.email,
+        email: Some(email.clone()),
         status: Some(UserStatus::Invited),
         role: invite_request.role,
+        tenant_id,
         created_at: now,
         updated_at: now,
     };
 
     user_repository::insert_one(&to_create, ctx.db()).await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_AUTH_SUBJECT,
+                AUDIT_ACTION_USER_INVITE,
+                AUDIT_TARGET_USER,
+                to_create.id.unwrap(),
+            )
+            .with_tenant(tenant_id)
+            .with_changes(None, Some(&to_create))?,
+    )?;
+
+    // the invitation is confirmed with the code mailed to the user
+    let invitation = LoginAttempt {
+        email: email.clone(),
+        otp_code: Uuid::new(),
+        otp_requests_count: 0,
+    };
+    login_repository::insert_one(&invitation, ctx.db()).await?;
+    send_otp(
+        &ctx,
+        email,
+        "Your invitation",
+        format!(
+            "Use this code to confirm your invitation: {}",
+            invitation.otp_code
+        ),
+        tenant_id,
+    )?;
 
     Ok(HttpResponse::Ok().finish())
 }
 
 /// Users click the admin invitation link in their email and this API will be called for account
-/// creation.
+/// creation, with the invitation code mailed to them. The code can only be used once.
 #[tracing::instrument(
     name = "invite_confirmation",
     skip(invite_confirmation),
@@ -94,16 +135,50 @@ pub async fn invite_confirmation(
         .ok_or(InternalError::RequestFormatError {
             reason: "require fields: `email`".to_string(),
         })?;
-    // find user by email
-    // #TODO sanitize email before db query
-    let mut user = user_repository::find_by_email(&email, ctx.db())
+    let otp_code = invite_confirmation
+        .otp_code
+        .ok_or(InternalError::RequestFormatError {
+            reason: "require fields: `otpCode`".to_string(),
+        })?;
+    login_repository::consume_otp(&email, &otp_code, ctx.db())
         .await?
         .ok_or(InternalError::AuthInvalidInvitation {
-            cause: "this email is not invited".to_string(),
+            cause: "invalid invitation code".to_string(),
         })?;
 
-    user.status = Some(UserStatus::Active);
-    let _ = user_repository::update_by_id(&user, ctx.db()).await?;
+    // find user by email and activate it, the event being emitted only once
+    // the activation is committed
+    // #TODO sanitize email before db query
+    let (user, activated) = db_mongo::with_transaction(
+        ctx.mongo_client(),
+        &(ctx.db(), email.as_str()),
+        |session, (db, email)| {
+            Box::pin(async move {
+                let mut user = user_repository::find_by_email(email, Some(&mut *session), db)
+                    .await?
+                    .ok_or(InternalError::AuthInvalidInvitation {
+                        cause: "this email is not invited".to_string(),
+                    })?;
+                if user.status == Some(UserStatus::Active) {
+                    return Ok((user, false));
+                }
+
+                user.status = Some(UserStatus::Active);
+                let _ = user_repository::update_by_id(&user, Some(session), db).await?;
+                Ok((user, true))
+            })
+        },
+    )
+    .await?;
+    if !activated {
+        return Ok(HttpResponse::Ok()
+            .content_type("application/json")
+            .json(user));
+    }
+
+    ctx.cache()
+        .invalidate(&[&ctx.cache().key(CACHE_ENTITY_USER).id(user.id.unwrap())])
+        .await?;
 
     // emit an event
     let user_created_event = Event::AuthUserCreated(EventMessage {
@@ -111,6 +186,7 @@ pub async fn invite_confirmation(
         payload: UserCreatedMessage {
             user_id: user.id.unwrap().to_string(),
             email: user.email.clone().unwrap(),
+            tenant_id: user.tenant_id.map(|tenant_id| tenant_id.to_string()),
         },
     });
     let publisher = Arc::clone(&ctx.event_publisher);
@@ -140,7 +216,7 @@ pub async fn identify(
     // check if user exists
     // #TODO sanitize email before db query
     // #TODO handle case: duplicate users by email
-    let _ = user_repository::find_by_email(&email, ctx.db())
+    let user = user_repository::find_by_email(&email, None, ctx.db())
         .await?
         .ok_or(InternalError::AuthUserNotFound)?;
 
@@ -161,13 +237,45 @@ pub async fn identify(
 
     let _ = login_repository::insert_one(&login_attempt, ctx.db()).await?;
 
-    // #TODO mail otp
+    send_otp(
+        &ctx,
+        email,
+        "Your login code",
+        format!("Use this code to log in: {}", login_attempt.otp_code),
+        user.tenant_id,
+    )?;
 
     Ok(HttpResponse::Ok().finish())
 }
 
-/// Users can verify by clicking on the link in their mails, which will call this API. If the user
-/// has attempted to login and their uuids match, this means the authentication was successful.
+// Mail an otp, the emails of a tenant counting towards its send limits
+fn send_otp(
+    ctx: &AppContext,
+    to: String,
+    subject: &str,
+    body: String,
+    tenant_id: Option<Uuid>,
+) -> Result<(), InternalError> {
+    let send_otp_command = Event::AuthSendOtp(EventMessage {
+        meta: EventMetadata::new(SERVICE_AUTH_SUBJECT.into(), "trace_id"),
+        payload: SendOtpMessage {
+            from: ctx.otp_sender().to_string(),
+            to,
+            sub: subject.to_string(),
+            body,
+            tenant_id: tenant_id.map(|tenant_id| tenant_id.to_string()),
+        },
+    });
+    ctx.event_publisher().do_send(NatsEventMessage {
+        event: send_otp_command.try_into()?,
+    });
+    Ok(())
+}
+
+/// Users can verify by clicking on the link in their mails, which will call this API with their
+/// email and code. If the user has attempted to login and their uuids match, this means the
+/// authentication was successful and an access token asserting the user, their tenant and role is
+/// returned. The code can only be verified once.
 #[tracing::instrument(name = "verify", skip(verify), level = "info")]
 #[post("/verify")]
 pub async fn verify(
@@ -176,15 +284,41 @@ pub async fn verify(
 ) -> ApiResult {
     verify.validate()?;
 
-    let otp_code = verify.id.ok_or(InternalError::RequestFormatError {
-        reason: "require fields: `id`".to_string(),
-    })?;
-    // #TODO sanitize otp before db query
-    let _ = login_repository::find_by_otp(&otp_code, ctx.db())
+    let (email, otp_code) =
+        verify
+            .email
+            .zip(verify.id)
+            .ok_or(InternalError::RequestFormatError {
+                reason: "require fields: `email`, `id`".to_string(),
+            })?;
+    // the attempts per email are limited by the `email` rate limit of the
+    // route, whatever the ip they come from
+    let login_attempt = login_repository::consume_otp(&email, &otp_code, ctx.db())
         .await?
         .ok_or(InternalError::AuthUserNotFound)?;
 
-    // #TODO find user by email
-    // send authorization token in response
-    Ok(HttpResponse::Ok().finish())
+    let user = user_repository::find_by_email(&login_attempt.email, None, ctx.db())
+        .await?
+        .filter(|user| user.status == Some(UserStatus::Active))
+        .ok_or(InternalError::AuthUserNotFound)?;
+    let principal = Principal {
+        user_id: user
+            .id
+            .ok_or(InternalError::AuthUserNotFound)?
+            .to_uuid_0_8(),
+        tenant_id: user.tenant_id.map(|tenant_id| tenant_id.to_uuid_0_8()),
+        role: user.role.map(|role| role.to_string()),
+        email: Some(login_attempt.email),
+    };
+    let access_token = ctx
+        .access_token_key()
+        .issue(&principal, ctx.access_token_ttl())?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(AccessToken {
+            access_token,
+            token_type: "Bearer",
+            expires_in: ctx.access_token_ttl().num_seconds(),
+        }))
 }
@@ -5,15 +5,26 @@ use actix_web::{
 };
 use bson::Uuid;
 use chrono::Utc;
-use common::error::{ApiResult, InternalError};
+use common::{
+    error::{ApiResult, InternalError},
+    model::event::v1::{
+        audit::prelude::{AUDIT_ACTION_USER_DELETE, AUDIT_ACTION_USER_UPDATE, AUDIT_TARGET_USER},
+        auth::prelude::SERVICE_AUTH_SUBJECT,
+    },
+    util::{audit::AuditActor, principal::Principal, tenant_scope::TenantScope},
+};
+use validator::Validate;
 
 use crate::{
     context::AppContext,
-    model::domain::user::{
-        prelude::{CACHE_KEY_PREFIX_USER_ID, CACHE_USER_EXPIRY},
-        User,
-        UserRole,
-        UserStatus,
+    model::{
+        domain::user::{
+            prelude::{CACHE_ENTITY_USER, CACHE_USER_EXPIRY},
+            User,
+            UserRole,
+            UserStatus,
+        },
+        request::user_request::UpdateUser,
     },
     repository::user_repository,
 };
@@ -27,70 +38,156 @@ pub fn router() -> Scope {
     )
 }
 
-/// Http handler for querying users.
+/// Http handler for querying users, among the users of the tenant of the
+/// caller.
 #[tracing::instrument(name = "query", skip(user), level = "info")]
-pub async fn query(ctx: web::Data<AppContext>, web::Query(user): web::Query<User>) -> ApiResult {
+pub async fn query(
+    ctx: web::Data<AppContext>,
+    scope: TenantScope,
+    web::Query(user): web::Query<User>,
+) -> ApiResult {
     match user.id {
-        Some(id) => get_by_id(ctx, &id).await,
-        None => get_by_condition(ctx, user).await,
+        Some(id) => get_by_id(ctx, &scope, &id).await,
+        None => get_by_condition(ctx, &scope, user).await,
     }
 }
 
-async fn get_by_id(ctx: web::Data<AppContext>, id: &Uuid) -> ApiResult {
-    let cache_key_user_id = format!("{CACHE_KEY_PREFIX_USER_ID}_{id}");
+async fn get_by_id(ctx: web::Data<AppContext>, scope: &TenantScope, id: &Uuid) -> ApiResult {
+    let cache_key_user_id = ctx.cache().key(CACHE_ENTITY_USER).id(id);
 
-    match ctx.cache().get::<User>(&cache_key_user_id).await? {
-        Some(user) => Ok(HttpResponse::Ok()
-            .content_type("application/json")
-            .json(user)),
-        None => match user_repository::find_by_id(id, ctx.db()).await? {
-            Some(user) => {
-                ctx.cache()
-                    .set::<User>(&cache_key_user_id, user.clone(), CACHE_USER_EXPIRY)
-                    .await?;
-                Ok(HttpResponse::Ok()
-                    .content_type("application/json")
-                    .json(user))
-            }
-            None => Err(InternalError::UserNotFound {
-                user_id: id.to_uuid_0_8(),
-            }),
-        },
-    }
+    let user = ctx
+        .cache()
+        .get_or_load(&cache_key_user_id, CACHE_USER_EXPIRY, || {
+            user_repository::find_by_id(id, ctx.db())
+        })
+        .await?
+        .filter(|user| in_scope(scope, user))
+        .ok_or(InternalError::UserNotFound {
+            user_id: id.to_uuid_0_8(),
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(user))
 }
 
-async fn get_by_condition(ctx: web::Data<AppContext>, user: User) -> ApiResult {
+async fn get_by_condition(
+    ctx: web::Data<AppContext>,
+    scope: &TenantScope,
+    user: User,
+) -> ApiResult {
+    let user = match scope.tenant_id() {
+        Some(tenant_id) => User {
+            tenant_id: Some(Uuid::from_uuid_0_8(tenant_id)),
+            ..user
+        },
+        None => user,
+    };
     let users = user_repository::find_all_with_query(&user, ctx.db()).await?;
     Ok(HttpResponse::Ok()
         .content_type("application/json")
         .json(users))
 }
 
-/// Http handler for updating an user.
-#[tracing::instrument(name = "update_by_id", skip(user), level = "info")]
-pub async fn update_by_id(ctx: web::Data<AppContext>, user: web::Json<User>) -> ApiResult {
-    // verify necessary fields
-    if user.id.is_none() {
-        return Err(InternalError::RequestFormatError {
-            reason: "require fields: `_id`".to_string(),
-        });
+/// Http handler for updating an user. Users can only update themselves, and
+/// only administrators can change the status and the role of a user.
+/// Administrators acting for a tenant only update the users of their tenant.
+#[tracing::instrument(name = "update_by_id", skip(actor, update_user), level = "info")]
+pub async fn update_by_id(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    scope: TenantScope,
+    actor: AuditActor,
+    web::Json(update_user): web::Json<UpdateUser>,
+) -> ApiResult {
+    update_user.validate()?;
+    let id = update_user.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })?;
+    if update_user.requires_admin() || id.to_uuid_0_8() != principal.user_id {
+        principal.require_admin()?;
     }
 
-    let _: u64 = user_repository::update_by_id(&user, ctx.db()).await?;
+    let previous = find_in_scope(&ctx, &scope, &id).await?;
+    let user = User {
+        updated_at: Some(Utc::now()),
+        ..User::from(update_user)
+    };
+    let _: u64 = user_repository::update_by_id(&user, None, ctx.db()).await?;
+    ctx.cache()
+        .invalidate(&[&ctx.cache().key(CACHE_ENTITY_USER).id(id)])
+        .await?;
+    let updated = user_repository::find_by_id(&id, ctx.db()).await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_AUTH_SUBJECT,
+                AUDIT_ACTION_USER_UPDATE,
+                AUDIT_TARGET_USER,
+                id,
+            )
+            .with_tenant(previous.tenant_id)
+            .with_changes(Some(&previous), updated.as_ref())?,
+    )?;
 
     Ok(HttpResponse::Ok().finish())
 }
 
-/// Http handler for deleting an user.
-#[tracing::instrument(name = "delete_by_id", skip(user), level = "info")]
+/// Http handler for deleting an user. Users can only delete themselves, and
+/// administrators acting for a tenant only the users of their tenant.
+#[tracing::instrument(name = "delete_by_id", skip(actor, user), level = "info")]
 pub async fn delete_by_id(
     ctx: web::Data<AppContext>,
+    principal: Principal,
+    scope: TenantScope,
+    actor: AuditActor,
     web::Query(user): web::Query<User>,
 ) -> ApiResult {
     let id = user.id.ok_or(InternalError::RequestFormatError {
         reason: "require fields: `_id`".to_string(),
     })?;
+    if id.to_uuid_0_8() != principal.user_id {
+        principal.require_admin()?;
+    }
 
+    let previous = find_in_scope(&ctx, &scope, &id).await?;
     let _: u64 = user_repository::delete_one(&id, ctx.db()).await?;
+    ctx.cache()
+        .invalidate(&[&ctx.cache().key(CACHE_ENTITY_USER).id(id)])
+        .await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_AUTH_SUBJECT,
+                AUDIT_ACTION_USER_DELETE,
+                AUDIT_TARGET_USER,
+                id,
+            )
+            .with_tenant(previous.tenant_id)
+            .with_changes(Some(&previous), None)?,
+    )?;
     Ok(HttpResponse::Ok().finish())
 }
+
+// The user `id`, unless of another tenant than that of `scope`
+async fn find_in_scope(
+    ctx: &AppContext,
+    scope: &TenantScope,
+    id: &Uuid,
+) -> Result<User, InternalError> {
+    user_repository::find_by_id(id, ctx.db())
+        .await?
+        .filter(|user| in_scope(scope, user))
+        .ok_or(InternalError::UserNotFound {
+            user_id: id.to_uuid_0_8(),
+        })
+}
+
+fn in_scope(scope: &TenantScope, user: &User) -> bool {
+    match scope.tenant_id() {
+        Some(tenant_id) => {
+            user.tenant_id.map(|tenant_id| tenant_id.to_uuid_0_8()) == Some(tenant_id)
+        }
+        None => true,
+    }
+}
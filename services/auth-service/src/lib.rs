@@ -14,8 +14,16 @@ use common::{
         db_mongo::{self},
     },
     error::REDACTED_ERRORS,
-    model::event::v1::auth::prelude::SERVICE_AUTH_SUBJECT,
-    util::{actix_json_config::json_extractor_config, telemetry},
+    model::event::v1::{
+        audit::prelude::SERVICE_AUDIT_SUBJECT,
+        auth::prelude::SERVICE_AUTH_SUBJECT,
+    },
+    util::{
+        access_token::AccessTokenKey,
+        actix_json_config::json_extractor_config,
+        rate_limit::RateLimiter,
+        telemetry,
+    },
 };
 use nats_actor::{
     publisher::{NatsPublisher, NatsPublisherConfig},
@@ -23,6 +31,7 @@ use nats_actor::{
 };
 use secrets::Secrets;
 use std::sync::Arc;
+use tracing::error;
 use tracing_actix_web::TracingLogger;
 
 pub async fn server() -> Result<(), std::io::Error> {
@@ -55,42 +64,82 @@ pub async fn start_web_service(
     );
 
     let secrets: Secrets = secrets::read(&configuration).await?;
+    // Verifies the access tokens asserting the principal of the requests
+    let access_token_key = AccessTokenKey::new(secrets.access_token.signing_key.clone());
+    let trusted_proxies = configuration.trusted_proxies.clone();
 
-    let db_client = db_mongo::connect(app_name, &configuration.db, &secrets.db)
+    let mongo_client = db_mongo::connect_client(app_name, &configuration.db, &secrets.db)
         .await
         .expect("db client connection failure");
+    let db_client = mongo_client.database(&configuration.db.database_name);
+    db_mongo::migrate(&db_client, &repository::migrations::SCHEMA)
+        .await
+        .expect("db schema migration failure");
 
-    let cache_pool: CachePool = cache_redis::connect(&configuration.cache, &secrets.cache)?;
-    let cache_client: Cache = cache_redis::Cache::new(cache_pool);
+    let cache_pool: CachePool = cache_redis::connect(&configuration.cache, &secrets.cache).await?;
+    let cache_client: Cache = cache_redis::Cache::new(cache_pool, env!("CARGO_PKG_NAME"))
+        .with_local_tier(configuration.cache.local.as_ref());
+    let pubsub_client = cache_redis::connect_pubsub(&configuration.cache, &secrets.cache).await?;
+
+    // Evict the values invalidated by any replica from the in-process tier
+    let local_cache = cache_client.clone();
+    actix::spawn(async move {
+        if let Err(err) = local_cache.follow_invalidations(&pubsub_client).await {
+            error!("Failed to follow cache invalidations: {}", err);
+        }
+    });
 
     // Start the NATS publisher actor.
+    let nats_client_settings = NatsClientSettings {
+        addresses: configuration.nats.addresses,
+        max_reconnects: configuration.nats.max_reconnects,
+        retry_timeout: configuration.nats.retry_timeout,
+    };
     let publisher = NatsPublisher::start_new(NatsPublisherConfig {
-        client_settings: NatsClientSettings {
-            addresses: configuration.nats.addresses,
-            max_reconnects: configuration.nats.max_reconnects,
-            retry_timeout: configuration.nats.retry_timeout,
-        },
+        client_settings: nats_client_settings.clone(),
         subject: SERVICE_AUTH_SUBJECT.into(),
         mailbox_size: configuration.application.nats_publisher_mailbox_size,
     })
     .await
     .expect("nats connection setup failure");
 
+    // Start the NATS publisher actor of the audit records.
+    let audit_publisher = NatsPublisher::start_new(NatsPublisherConfig {
+        client_settings: nats_client_settings,
+        subject: SERVICE_AUDIT_SUBJECT.into(),
+        mailbox_size: configuration.application.nats_publisher_mailbox_size,
+    })
+    .await
+    .expect("nats connection setup failure");
+
+    // Limit the requests to the public routes, the windows being shared
+    // through the cache
+    let rate_limit_cache = cache_client.clone();
+    let http_rate_limit = configuration.http_rate_limit.clone();
+
     // Instantiate the application context. This application state will be
     // cloned for each Actix thread but the Arc of the DbContext will be
     // reused in each Actix thread.
     let app_context = web::Data::new(AppContext {
+        mongo_client: Arc::new(mongo_client),
         db: Arc::new(db_client),
         cache: Arc::new(cache_client),
         event_publisher: Arc::new(publisher),
+        audit_publisher: Arc::new(audit_publisher),
+        access_token_key: access_token_key.clone(),
+        access_token_ttl: chrono::Duration::seconds(configuration.access_token.ttl_secs),
+        otp_sender: configuration.otp.sender.clone(),
     });
 
     let server = HttpServer::new(move || {
         App::new()
             .app_data(app_context.clone())
+            .app_data(access_token_key.clone())
+            .app_data(trusted_proxies.clone())
             .app_data(json_extractor_config(4096))
             .wrap(TracingLogger::default())
             .wrap(RequestTracing::new())
+            .wrap(RateLimiter::new(rate_limit_cache.clone(), &http_rate_limit))
             .service(web::scope("/auth/v1.0").configure(controller::global_router))
             .default_service(web::get().to(not_found))
     })
@@ -1,11 +1,13 @@
-use mongodb::bson::{self, doc, Bson};
-use redis::{FromRedisValue, RedisResult, RedisWrite, ToRedisArgs, Value};
+use common::client::mongo_repository::MongoEntity;
+use mongodb::bson::{self, Bson};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::{fmt, fmt::Formatter};
 use strum::{Display, EnumString};
 
 pub mod prelude {
+    use common::client::cache_key::CacheEntity;
+
     // Collection name
     pub const COLLECTION_USERS: &str = "users";
 
@@ -14,11 +16,12 @@ pub mod prelude {
     pub const EMAIL: &str = "EMAIL";
     pub const STATUS: &str = "STATUS";
     pub const ROLE: &str = "ROLE";
+    pub const TENANT_ID: &str = "TENANT_ID";
     pub const CREATED_AT: &str = "CREATED_AT";
     pub const UPDATED_AT: &str = "UPDATED_AT";
 
     // Cache keys
-    pub const CACHE_KEY_PREFIX_USER_ID: &str = "_id";
+    pub const CACHE_ENTITY_USER: CacheEntity = CacheEntity::new("user", 1);
     pub const CACHE_USER_EXPIRY: usize = 600;
 }
 
@@ -68,6 +71,9 @@ pub struct User {
     pub status: Option<UserStatus>,
     #[serde(rename = "ROLE")]
     pub role: Option<UserRole>,
+    // the tenant the user is invited to, none for the platform administrators
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: Option<bson::Uuid>,
     // #[serde_as(as = "Option<bson::DateTime>")]
     #[serde(rename = "CREATED_AT")]
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
@@ -76,28 +82,17 @@ pub struct User {
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-impl ToRedisArgs for User {
-    fn write_redis_args<W>(&self, output: &mut W)
-    where
-        W: ?Sized + RedisWrite,
-    {
-        output.write_arg_fmt(serde_json::to_string(self).unwrap());
+impl MongoEntity for User {
+    type Id = bson::Uuid;
+
+    const COLLECTION: &'static str = prelude::COLLECTION_USERS;
+
+    fn id(&self) -> Option<&bson::Uuid> {
+        self.id.as_ref()
     }
-}
 
-impl FromRedisValue for User {
-    fn from_redis_value(value: &Value) -> RedisResult<Self> {
-        match *value {
-            redis::Value::Data(ref value_slice) => match serde_json::from_slice(value_slice) {
-                Err(_) => Err((redis::ErrorKind::TypeError, "Can't serialize value").into()),
-                Ok(user) => Ok(user),
-            },
-            _ => Err((
-                redis::ErrorKind::ResponseError,
-                "Response type not Profile compatible.",
-            )
-                .into()),
-        }
+    fn set_id(&mut self, id: bson::Uuid) {
+        self.id = Some(id);
     }
 }
 
@@ -1,4 +1,8 @@
 use bson::Uuid;
+use common::{
+    error::InternalError,
+    util::{principal::Principal, tenant_scope::TenantScope},
+};
 use serde::Deserialize;
 use validator::Validate;
 
@@ -11,6 +15,25 @@ pub struct Invite {
     #[validate(required)]
     #[serde(rename = "ROLE")]
     pub role: Option<UserRole>,
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: Option<Uuid>,
+}
+
+impl Invite {
+    /// The tenant of the user invited by `principal`, who must be an
+    /// administrator: the tenant of the administrator, or across the platform
+    /// the requested one. Only platform administrators invite administrators
+    /// outside of any tenant.
+    pub fn tenant(&self, principal: &Principal) -> Result<Option<Uuid>, InternalError> {
+        principal.require_admin()?;
+        match (TenantScope::of(principal)?, self.role, self.tenant_id) {
+            (TenantScope::Platform, Some(UserRole::Admin), None) => Ok(None),
+            (scope, _, requested) => {
+                let tenant_id = scope.owner(requested.map(|tenant_id| tenant_id.to_uuid_0_8()))?;
+                Ok(Some(Uuid::from_uuid_0_8(tenant_id)))
+            }
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, Validate)]
@@ -31,6 +54,93 @@ pub struct Identify {
 
 #[derive(Debug, Deserialize, Clone, Validate)]
 pub struct Verify {
+    #[validate(required, email(message = "email is not valid"))]
+    pub email: Option<String>,
     #[validate(required)]
     pub id: Option<Uuid>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin(tenant_id: Option<uuid::Uuid>) -> Principal {
+        Principal {
+            user_id: uuid::Uuid::new_v4(),
+            tenant_id,
+            role: Some("ADMIN".to_string()),
+            email: Some("admin@example.com".to_string()),
+        }
+    }
+
+    fn invite(role: UserRole, tenant_id: Option<uuid::Uuid>) -> Invite {
+        Invite {
+            email: Some("invited@example.com".to_string()),
+            role: Some(role),
+            tenant_id: tenant_id.map(Uuid::from_uuid_0_8),
+        }
+    }
+
+    #[test]
+    fn invites_into_the_tenant_of_the_administrator() {
+        let tenant_id = uuid::Uuid::new_v4();
+
+        for requested in [None, Some(tenant_id)] {
+            assert_eq!(
+                invite(UserRole::Admin, requested)
+                    .tenant(&admin(Some(tenant_id)))
+                    .unwrap(),
+                Some(Uuid::from_uuid_0_8(tenant_id))
+            );
+        }
+        assert_eq!(
+            invite(UserRole::User, Some(tenant_id))
+                .tenant(&admin(None))
+                .unwrap(),
+            Some(Uuid::from_uuid_0_8(tenant_id))
+        );
+        assert_eq!(
+            invite(UserRole::Admin, None).tenant(&admin(None)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_invitations_of_non_administrators() {
+        let user = Principal {
+            role: Some("USER".to_string()),
+            ..admin(Some(uuid::Uuid::new_v4()))
+        };
+        let anonymous = Principal {
+            role: None,
+            ..admin(None)
+        };
+
+        assert!(invite(UserRole::User, None).tenant(&user).is_err());
+        assert!(invite(UserRole::Admin, None).tenant(&anonymous).is_err());
+    }
+
+    #[test]
+    fn rejects_invitations_into_other_tenants() {
+        let principal = admin(Some(uuid::Uuid::new_v4()));
+
+        assert!(invite(UserRole::User, Some(uuid::Uuid::new_v4()))
+            .tenant(&principal)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_tenantless_users() {
+        assert!(invite(UserRole::User, None).tenant(&admin(None)).is_err());
+    }
+
+    #[test]
+    fn requires_the_invitation_code() {
+        let confirmation = InviteConfirmation {
+            email: Some("invited@example.com".to_string()),
+            otp_code: None,
+        };
+
+        assert!(confirmation.validate().is_err());
+    }
+}
@@ -1 +1,2 @@
 pub mod login;
+pub mod user_request;
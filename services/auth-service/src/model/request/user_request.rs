@@ -0,0 +1,42 @@
+use crate::model::domain::user::{User, UserRole, UserStatus};
+use bson::Uuid;
+use serde::Deserialize;
+use validator::Validate;
+
+/// The fields of a user that can be changed, the status and the role by
+/// administrators only. The tenant of a user and its timestamps are kept by
+/// the service.
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct UpdateUser {
+    #[validate(required)]
+    #[serde(rename = "_id")]
+    pub id: Option<Uuid>,
+    #[validate(email(message = "email is not valid"))]
+    #[serde(rename = "EMAIL")]
+    pub email: Option<String>,
+    #[serde(rename = "STATUS")]
+    pub status: Option<UserStatus>,
+    #[serde(rename = "ROLE")]
+    pub role: Option<UserRole>,
+}
+
+impl UpdateUser {
+    /// Whether the request changes fields only administrators can change.
+    pub fn requires_admin(&self) -> bool {
+        self.status.is_some() || self.role.is_some()
+    }
+}
+
+impl From<UpdateUser> for User {
+    fn from(update: UpdateUser) -> Self {
+        User {
+            id: update.id,
+            email: update.email,
+            status: update.status,
+            role: update.role,
+            tenant_id: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+/// The access token issued on a verified login, to be sent by the client as a
+/// bearer token in the `Authorization` header.
+#[derive(Debug, Serialize, Clone)]
+pub struct AccessToken {
+    pub access_token: String,
+    pub token_type: &'static str,
+    /// Seconds before the token expires.
+    pub expires_in: i64,
+}
@@ -1 +1 @@
-
+pub mod login_response;
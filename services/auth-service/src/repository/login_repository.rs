@@ -19,14 +19,17 @@ pub async fn find_by_email(
     Ok(login_attempt)
 }
 
-pub async fn find_by_otp(
+/// Find and remove the login attempt of `email` with `otp_code`, so that the
+/// code can only be verified once.
+pub async fn consume_otp(
+    email: &str,
     otp_code: &Uuid,
     db: &Database,
 ) -> Result<Option<LoginAttempt>, InternalError> {
-    let filter = doc! { OTP_CODE: otp_code };
+    let filter = doc! { EMAIL: email, OTP_CODE: otp_code };
     let login_attempt = db
         .collection::<LoginAttempt>(COLLECTION_LOGIN_ATTEMPTS)
-        .find_one(filter, None)
+        .find_one_and_delete(filter, None)
         .await?;
     Ok(login_attempt)
 }
@@ -0,0 +1,32 @@
+use crate::model::domain::{login::prelude as login, user::prelude as user};
+use common::{
+    client::db_mongo::{self, IndexDefinition, Migration, Schema},
+    error::InternalError,
+};
+use futures::future::BoxFuture;
+use mongodb::Database;
+
+pub const SCHEMA: Schema = Schema {
+    migrations: &[Migration::new(1, "create collections", create_collections)],
+    indexes: &[
+        IndexDefinition::new(user::COLLECTION_USERS, "users_email", &[(user::EMAIL, 1)]).unique(),
+        IndexDefinition::new(
+            login::COLLECTION_LOGIN_ATTEMPTS,
+            "login_attempts_email",
+            &[(login::EMAIL, 1)],
+        ),
+        IndexDefinition::new(
+            login::COLLECTION_LOGIN_ATTEMPTS,
+            "login_attempts_otp_code",
+            &[(login::OTP_CODE, 1)],
+        )
+        .unique(),
+    ],
+};
+
+fn create_collections(db: &Database) -> BoxFuture<'_, Result<(), InternalError>> {
+    Box::pin(db_mongo::create_collections(
+        db,
+        &[user::COLLECTION_USERS, login::COLLECTION_LOGIN_ATTEMPTS],
+    ))
+}
@@ -1,2 +1,3 @@
 pub mod login_repository;
+pub mod migrations;
 pub mod user_repository;
@@ -2,6 +2,7 @@ use crate::settings::Settings;
 use common::{
     client::{cache_redis::RedisClientSecrets, db_mongo::MongoClientSecrets, sm_vault},
     error::InternalError,
+    util::access_token::AccessTokenSecrets,
 };
 use serde::Deserialize;
 use vaultrs::client::VaultClient;
@@ -10,6 +11,7 @@ use vaultrs::client::VaultClient;
 pub struct Secrets {
     pub cache: RedisClientSecrets,
     pub db: MongoClientSecrets,
+    pub access_token: AccessTokenSecrets,
 }
 
 pub async fn read(settings: &Settings) -> Result<Secrets, InternalError> {
@@ -21,8 +23,12 @@ pub async fn read(settings: &Settings) -> Result<Secrets, InternalError> {
     let db_secrets: MongoClientSecrets =
         sm_vault::get_secret_value(&vault_client, &settings.db_secrets_path).await?;
 
+    let access_token_secrets: AccessTokenSecrets =
+        sm_vault::get_secret_value(&vault_client, &settings.access_token_secrets_path).await?;
+
     Ok(Secrets {
         cache: cache_secrets,
         db: db_secrets,
+        access_token: access_token_secrets,
     })
 }
@@ -4,7 +4,7 @@ use common::{
         db_mongo::MongoClientSettings,
         sm_vault::{VaultClientConfig, VaultKvPath},
     },
-    util::configuration,
+    util::{client_ip::TrustedProxies, configuration, rate_limit::HttpRateLimitSettings},
 };
 use nats_actor::NatsClientSettings;
 use serde_aux::field_attributes::deserialize_number_from_string;
@@ -17,7 +17,16 @@ pub struct Settings {
     pub db_secrets_path: VaultKvPath,
     pub cache: RedisClientSettings,
     pub cache_secrets_path: VaultKvPath,
+    pub access_token_secrets_path: VaultKvPath,
     pub nats: NatsClientSettings,
+    pub access_token: AccessTokenSettings,
+    pub otp: OtpSettings,
+    #[serde(default)]
+    pub http_rate_limit: HttpRateLimitSettings,
+    /// The proxies trusted to forward the address of the clients, none
+    /// unless set.
+    #[serde(default)]
+    pub trusted_proxies: TrustedProxies,
     pub log: LogSettings,
     pub tracer: Tracer,
 }
@@ -34,6 +43,19 @@ pub struct ApplicationSettings {
     pub nats_publisher_mailbox_size: usize,
 }
 
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct AccessTokenSettings {
+    /// Lifetime of the access tokens issued on login.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub ttl_secs: i64,
+}
+
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct OtpSettings {
+    /// The address the login codes are emailed from.
+    pub sender: String,
+}
+
 #[derive(Debug, serde::Deserialize, Clone)]
 pub struct LogSettings {
     pub level: String,
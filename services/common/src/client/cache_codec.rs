@@ -0,0 +1,49 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::InternalError;
+
+/// Serialization format of the values stored in the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheCodec {
+    Json,
+    MessagePack,
+}
+
+impl Default for CacheCodec {
+    fn default() -> Self {
+        CacheCodec::Json
+    }
+}
+
+impl CacheCodec {
+    pub fn encode<T>(&self, value: &T) -> Result<Vec<u8>, InternalError>
+    where
+        T: Serialize,
+    {
+        match self {
+            CacheCodec::Json => Ok(serde_json::to_vec(value)?),
+            // struct fields are encoded by name so that optional fields
+            // skipped on serialization can still be decoded
+            CacheCodec::MessagePack => {
+                rmp_serde::to_vec_named(value).map_err(|err| InternalError::CacheOperationError {
+                    cause: err.to_string(),
+                })
+            }
+        }
+    }
+
+    pub fn decode<T>(&self, bytes: &[u8]) -> Result<T, InternalError>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            CacheCodec::Json => Ok(serde_json::from_slice(bytes)?),
+            CacheCodec::MessagePack => {
+                rmp_serde::from_slice(bytes).map_err(|err| InternalError::CacheOperationError {
+                    cause: err.to_string(),
+                })
+            }
+        }
+    }
+}
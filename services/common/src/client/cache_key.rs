@@ -0,0 +1,56 @@
+use std::fmt::Display;
+
+/// A type of cached value. The version is part of every key of the entity:
+/// bumping it when the cached struct changes makes the values written by
+/// previous releases unreachable, instead of failing to decode them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheEntity {
+    pub name: &'static str,
+    pub version: u32,
+}
+
+impl CacheEntity {
+    pub const fn new(name: &'static str, version: u32) -> Self {
+        CacheEntity { name, version }
+    }
+}
+
+/// Builder of the keys of an entity, namespaced by service and optionally by
+/// tenant:
+///
+/// - `{service}:{entity}:v{version}:{id}`
+/// - `{service}:tenant:{tenant_id}:{entity}:v{version}:{id}`
+#[derive(Debug, Clone)]
+pub struct CacheKeyBuilder<'a> {
+    service: &'a str,
+    entity: CacheEntity,
+    tenant_id: Option<String>,
+}
+
+impl<'a> CacheKeyBuilder<'a> {
+    pub(crate) fn new(service: &'a str, entity: CacheEntity) -> Self {
+        CacheKeyBuilder {
+            service,
+            entity,
+            tenant_id: None,
+        }
+    }
+
+    /// Scope the key to `tenant_id`.
+    pub fn tenant(self, tenant_id: impl Display) -> Self {
+        CacheKeyBuilder {
+            tenant_id: Some(tenant_id.to_string()),
+            ..self
+        }
+    }
+
+    pub fn id(&self, id: impl Display) -> String {
+        let CacheEntity { name, version } = self.entity;
+        match &self.tenant_id {
+            Some(tenant_id) => {
+                format!("{}:tenant:{tenant_id}:{name}:v{version}:{id}", self.service)
+            }
+            None => format!("{}:{name}:v{version}:{id}", self.service),
+        }
+    }
+}
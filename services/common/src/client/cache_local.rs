@@ -0,0 +1,71 @@
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::deserialize_number_from_string;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LocalCacheSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub capacity: usize,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub ttl_secs: u64,
+}
+
+struct Entry {
+    value: Arc<Vec<u8>>,
+    expires_at: Instant,
+}
+
+/// Bounded in-process cache tier in front of Redis. Values are kept encoded,
+/// as stored in Redis, so that a single tier serves every cached type. Entries
+/// are evicted by least recent use and expire after the configured ttl, which
+/// bounds how long a replica may serve a value missed by the invalidation
+/// broadcast.
+pub struct LocalCache {
+    entries: Mutex<LruCache<String, Entry>>,
+    ttl: Duration,
+}
+
+impl LocalCache {
+    pub fn new(settings: &LocalCacheSettings) -> Self {
+        LocalCache {
+            entries: Mutex::new(LruCache::new(settings.capacity)),
+            ttl: Duration::from_secs(settings.ttl_secs),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<Arc<Vec<u8>>> {
+        let mut entries = self.entries.lock();
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(Arc::clone(&entry.value)),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Keep `value` for the ttl of the tier, which is expected to be well
+    /// below the expiry of the values in Redis.
+    pub fn put(&self, key: &str, value: Arc<Vec<u8>>) {
+        self.entries.lock().put(
+            key.to_string(),
+            Entry {
+                value,
+                expires_at: Instant::now() + self.ttl,
+            },
+        );
+    }
+
+    pub fn evict<K: AsRef<str>>(&self, keys: &[K]) {
+        let mut entries = self.entries.lock();
+        for key in keys {
+            entries.pop(key.as_ref());
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use super::cache_redis::Cache;
+use crate::error::InternalError;
+use redis::Script;
+use std::time::Duration;
+use tracing::debug;
+
+// Take the lock if free and hand out the next fencing token. The counter is
+// kept apart from the lock so that tokens keep increasing across holders.
+// Returns the token, or nil when the lock is held.
+const ACQUIRE_SCRIPT: &str = r#"
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+    return redis.call('INCR', KEYS[2])
+end
+return false
+"#;
+
+// Extend the lease, provided the lock is still held with the same token.
+const RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+// Delete the lock, provided it is still held with the same token, so that a
+// holder whose lease expired cannot release the lock of the next one.
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// A lease on a named lock, shared by every replica of a service.
+///
+/// The fencing token increases with every acquisition of the lock. Writes
+/// made under the lock should carry it, so that the storage can reject the
+/// writes of a holder that stalled past its lease once a newer holder exists.
+#[derive(Debug, Clone)]
+pub struct Lock {
+    pub name: String,
+    pub fencing_token: u64,
+    pub lease: Duration,
+    key: String,
+    token: String,
+}
+
+impl Cache {
+    /// Try to take the lock `name` for `lease`. Returns `None` when it is held
+    /// by someone else.
+    pub async fn try_lock(
+        &self,
+        name: &str,
+        lease: Duration,
+    ) -> Result<Option<Lock>, InternalError> {
+        let mut cache = self.connection().await?;
+        let key = self.lock_key(name);
+        let token = uuid::Uuid::new_v4().to_string();
+
+        let fencing_token: Option<u64> = Script::new(ACQUIRE_SCRIPT)
+            .key(&key)
+            .key(format!("{key}:fencing"))
+            .arg(&token)
+            .arg(lease.as_millis() as u64)
+            .invoke_async(&mut *cache)
+            .await?;
+
+        debug!("LOCK | {name} | {fencing_token:?}");
+        Ok(fencing_token.map(|fencing_token| Lock {
+            name: name.to_string(),
+            fencing_token,
+            lease,
+            key,
+            token,
+        }))
+    }
+
+    /// Extend the lease of `lock` by its duration. Returns false when the lock
+    /// was lost, in which case the work done under it must stop.
+    pub async fn renew_lock(&self, lock: &Lock) -> Result<bool, InternalError> {
+        let mut cache = self.connection().await?;
+
+        let renewed: u8 = Script::new(RENEW_SCRIPT)
+            .key(&lock.key)
+            .arg(&lock.token)
+            .arg(lock.lease.as_millis() as u64)
+            .invoke_async(&mut *cache)
+            .await?;
+
+        debug!("RENEW LOCK | {} | {}", lock.name, renewed == 1);
+        Ok(renewed == 1)
+    }
+
+    /// Release `lock`. Returns false when it was no longer held.
+    pub async fn release_lock(&self, lock: Lock) -> Result<bool, InternalError> {
+        let mut cache = self.connection().await?;
+
+        let released: u8 = Script::new(RELEASE_SCRIPT)
+            .key(&lock.key)
+            .arg(&lock.token)
+            .invoke_async(&mut *cache)
+            .await?;
+
+        debug!("RELEASE LOCK | {} | {}", lock.name, released == 1);
+        Ok(released == 1)
+    }
+}
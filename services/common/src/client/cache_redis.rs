@@ -1,53 +1,367 @@
+use super::{
+    cache_codec::CacheCodec,
+    cache_key::{CacheEntity, CacheKeyBuilder},
+    cache_local::{LocalCache, LocalCacheSettings},
+};
 use crate::error::InternalError;
-use deadpool_redis::{Config as RedisConfig, Connection, Pool, Runtime};
-use redis::{AsyncCommands, FromRedisValue, RedisResult, ToRedisArgs};
+use deadpool::{
+    async_trait,
+    managed::{self, Object, RecycleError, RecycleResult},
+};
+use deadpool_redis::{PoolConfig, Runtime, Timeouts};
+use futures::{Future, Stream, StreamExt};
+use redis::{
+    aio::Connection as RedisConnection,
+    AsyncCommands,
+    Client,
+    ErrorKind,
+    Msg,
+    RedisError,
+    RedisResult,
+    Script,
+};
 use secrecy::{ExposeSecret, Secret};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_aux::field_attributes::deserialize_number_from_string;
-use std::fmt::{self, Debug};
-use tracing::info;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, info, warn};
+use url::Url;
 
-pub type CachePool = Pool;
+pub type CachePool = managed::Pool<CacheManager>;
+type Connection = Object<CacheManager>;
+
+/// Channel on which invalidated keys are broadcast, so that every replica can
+/// drop its local copies.
+pub const CHANNEL_CACHE_INVALIDATION: &str = "cache.invalidation";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheInvalidation {
+    pub keys: Vec<String>,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RedisClientSettings {
+    // address of the server, or of the master when no sentinel is set
     pub host: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
+    // connect with `rediss://`
+    #[serde(default)]
+    pub tls: bool,
+    // ACL user, the password being read from the secrets
+    pub username: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_number_from_string")]
+    pub db: i64,
+    #[serde(default)]
+    pub pool: RedisPoolSettings,
+    // discover the master through sentinels instead of `host` and `port`
+    pub sentinel: Option<RedisSentinelSettings>,
+    // in-process tier, disabled when absent
+    pub local: Option<LocalCacheSettings>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RedisPoolSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_size: usize,
+    // waiting for a connection to be available
+    pub wait_timeout_ms: Option<u64>,
+    // opening a new connection
+    pub create_timeout_ms: Option<u64>,
+    // checking a connection returned to the pool
+    pub recycle_timeout_ms: Option<u64>,
+}
+
+impl Default for RedisPoolSettings {
+    fn default() -> Self {
+        RedisPoolSettings {
+            max_size: 16,
+            wait_timeout_ms: None,
+            create_timeout_ms: None,
+            recycle_timeout_ms: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RedisSentinelSettings {
+    pub master_name: String,
+    // `host:port` of the sentinels, tried in order
+    pub addresses: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
 pub struct RedisClientSecrets {
     pub password: Secret<String>,
 }
 
-pub fn connect(
+async fn server_url(
+    config: &RedisClientSettings,
+    secrets: &RedisClientSecrets,
+) -> Result<String, InternalError> {
+    let scheme = if config.tls { "rediss" } else { "redis" };
+    let (host, port) = match &config.sentinel {
+        Some(sentinel) => find_master(scheme, sentinel).await?,
+        None => (config.host.clone(), config.port),
+    };
+
+    // credentials are set through `Url` so that they are percent-encoded
+    let mut url = Url::parse(&format!("{scheme}://{host}:{port}/{}", config.db))?;
+    let credentials = url
+        .set_username(config.username.as_deref().unwrap_or_default())
+        .and_then(|_| url.set_password(Some(secrets.password.expose_secret())));
+    if credentials.is_err() {
+        return Err(InternalError::CacheClientCreationError {
+            cause: "invalid credentials".to_string(),
+        });
+    }
+    Ok(url.to_string())
+}
+
+/// Ask the sentinels for the address of the current master.
+async fn find_master(
+    scheme: &str,
+    sentinel: &RedisSentinelSettings,
+) -> Result<(String, u16), InternalError> {
+    let mut cause = "no sentinel address".to_string();
+    for address in &sentinel.addresses {
+        let master: Result<Option<(String, u16)>, RedisError> = async {
+            let client = Client::open(format!("{scheme}://{address}"))?;
+            let mut connection = client.get_async_connection().await?;
+            redis::cmd("SENTINEL")
+                .arg("get-master-addr-by-name")
+                .arg(&sentinel.master_name)
+                .query_async(&mut connection)
+                .await
+        }
+        .await;
+
+        match master {
+            Ok(Some(master)) => {
+                info!(
+                    "SENTINEL | {} | {}:{}",
+                    sentinel.master_name, master.0, master.1
+                );
+                return Ok(master);
+            }
+            Ok(None) => cause = format!("unknown master {}", sentinel.master_name),
+            Err(err) => {
+                warn!("Failed to query sentinel {}: {}", address, err);
+                cause = err.to_string();
+            }
+        }
+    }
+    Err(InternalError::CacheClientConnectionError { cause })
+}
+
+/// Opens the connections of the cache pool. Behind sentinels, the master is
+/// resolved for every new connection and the pooled connections to a master
+/// since demoted are dropped, so that the pool follows a failover.
+#[derive(Debug)]
+pub struct CacheManager {
+    config: RedisClientSettings,
+    secrets: RedisClientSecrets,
+}
+
+#[async_trait]
+impl managed::Manager for CacheManager {
+    type Type = RedisConnection;
+    type Error = RedisError;
+
+    async fn create(&self) -> Result<RedisConnection, RedisError> {
+        let url = server_url(&self.config, &self.secrets)
+            .await
+            .map_err(|err| RedisError::from((ErrorKind::IoError, "no server", err.to_string())))?;
+        Client::open(url)?.get_async_connection().await
+    }
+
+    async fn recycle(&self, connection: &mut RedisConnection) -> RecycleResult<RedisError> {
+        if self.config.sentinel.is_none() {
+            redis::cmd("PING").query_async::<_, ()>(connection).await?;
+            return Ok(());
+        }
+
+        let role: Vec<redis::Value> = redis::cmd("ROLE").query_async(connection).await?;
+        match role.first().map(redis::from_redis_value::<String>) {
+            Some(Ok(role)) if role == "master" => Ok(()),
+            _ => Err(RecycleError::StaticMessage("not connected to the master")),
+        }
+    }
+}
+
+/// Create the cache pool, failing unless the server, or the master named by
+/// the sentinels, answers.
+pub async fn connect(
     config: &RedisClientSettings,
     secrets: &RedisClientSecrets,
 ) -> Result<CachePool, InternalError> {
-    let redis_server_url = format!(
-        "redis://:{}@{}:{}",
-        secrets.password.expose_secret(),
-        config.host,
-        config.port,
-    );
-
-    let redis_config = RedisConfig {
-        url: Some(redis_server_url),
-        connection: None,
-        pool: None,
+    let mut pool = PoolConfig::new(config.pool.max_size);
+    pool.timeouts = Timeouts {
+        wait: config.pool.wait_timeout_ms.map(Duration::from_millis),
+        create: config.pool.create_timeout_ms.map(Duration::from_millis),
+        recycle: config.pool.recycle_timeout_ms.map(Duration::from_millis),
     };
 
-    redis_config
-        .create_pool(Some(Runtime::Tokio1))
-        .map_err(|_| InternalError::CacheClientConnectionError {
-            cause: "unexpected error".to_string(),
-        })
+    let manager = CacheManager {
+        config: config.clone(),
+        secrets: secrets.clone(),
+    };
+    let pool: CachePool = managed::Pool::builder(manager)
+        .config(pool)
+        .runtime(Runtime::Tokio1)
+        .build()?;
+
+    let mut connection = pool.get().await?;
+    redis::cmd("PING")
+        .query_async::<_, ()>(&mut *connection)
+        .await?;
+    Ok(pool)
 }
 
+/// Create a client dedicated to pub/sub. Subscribed connections cannot issue
+/// regular commands, so they are kept out of the cache pool.
+pub async fn connect_pubsub(
+    config: &RedisClientSettings,
+    secrets: &RedisClientSecrets,
+) -> Result<Client, InternalError> {
+    Ok(Client::open(server_url(config, secrets).await?)?)
+}
+
+/// Subscribe to the invalidation broadcast. Unreadable messages are skipped.
+pub async fn invalidations(
+    client: &Client,
+) -> Result<impl Stream<Item = CacheInvalidation>, InternalError> {
+    let messages = subscribe(client, CHANNEL_CACHE_INVALIDATION).await?;
+    Ok(messages.filter_map(|msg| async move {
+        msg.get_payload::<String>()
+            .ok()
+            .and_then(|payload| serde_json::from_str(&payload).ok())
+    }))
+}
+
+/// Subscribe to `channel` and return the stream of received messages. The
+/// stream ends when the underlying connection is lost.
+pub async fn subscribe(
+    client: &Client,
+    channel: &str,
+) -> Result<impl Stream<Item = Msg>, InternalError> {
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(channel).await?;
+
+    info!("SUBSCRIBE | {channel}");
+    Ok(pubsub.into_on_message())
+}
+
+// Sliding window log kept in a sorted set scored by hit time (ms): expired
+// hits are trimmed, then the hit is recorded only if the window has room.
+// Returns `{allowed, count, retry_after_ms}`.
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local now = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local limit = tonumber(ARGV[3])
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+local count = redis.call('ZCARD', KEYS[1])
+if count < limit then
+    redis.call('ZADD', KEYS[1], now, ARGV[4])
+    redis.call('PEXPIRE', KEYS[1], window)
+    return {1, count + 1, 0}
+end
+local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+return {0, count, tonumber(oldest[2]) + window - now}
+"#;
+
+// The same over several windows: the hit is recorded in every window, or in
+// none when any of them is full. ARGV holds the time and the member of the hit,
+// then the window and limit of each key. Returns `{allowed, index, retry_after_ms}`
+// with the 1-based index of the first full window.
+const SLIDING_WINDOWS_SCRIPT: &str = r#"
+local now = tonumber(ARGV[1])
+for i, key in ipairs(KEYS) do
+    local window = tonumber(ARGV[2 * i + 1])
+    local limit = tonumber(ARGV[2 * i + 2])
+    redis.call('ZREMRANGEBYSCORE', key, '-inf', now - window)
+    if redis.call('ZCARD', key) >= limit then
+        local oldest = redis.call('ZRANGE', key, 0, 0, 'WITHSCORES')
+        local retry_after = window
+        if oldest[2] then
+            retry_after = tonumber(oldest[2]) + window - now
+        end
+        return {0, i, retry_after}
+    end
+end
+for i, key in ipairs(KEYS) do
+    redis.call('ZADD', key, now, ARGV[2])
+    redis.call('PEXPIRE', key, tonumber(ARGV[2 * i + 1]))
+end
+return {1, 0, 0}
+"#;
+
+/// The window rejecting a hit against several sliding window limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitRejection {
+    /// Position of the first full window among those hit.
+    pub index: usize,
+    /// Time until that window has room again.
+    pub retry_after: Duration,
+}
+
+/// Outcome of a hit against a sliding window limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub allowed: bool,
+    /// Hits in the window, including this one when allowed.
+    pub count: u64,
+    /// Time until the window has room again, zero when allowed.
+    pub retry_after: Duration,
+}
+
+/// Counts of the lookups served by each tier, since the start of the process.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub local_hits: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// Misses served by a loader; concurrent misses of a key share one load.
+    pub loads: u64,
+}
+
+#[derive(Default)]
+struct CacheMetrics {
+    local_hits: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    loads: AtomicU64,
+}
+
+impl CacheMetrics {
+    fn record(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// One lock per key being loaded, so that concurrent misses wait for the first
+// load instead of running their own.
+type InFlightLoads = parking_lot::Mutex<HashMap<String, Arc<futures::lock::Mutex<()>>>>;
+
+/// Two-tier cache: an optional bounded in-process tier in front of Redis.
+/// Clones share both tiers. Keys are namespaced by the service owning the
+/// cache, see `key`.
 #[derive(Clone)]
 pub struct Cache {
     pool: CachePool,
+    namespace: String,
+    codec: CacheCodec,
+    local: Option<Arc<LocalCache>>,
+    in_flight: Arc<InFlightLoads>,
+    metrics: Arc<CacheMetrics>,
 }
 
 impl fmt::Debug for Cache {
@@ -57,52 +371,316 @@ impl fmt::Debug for Cache {
 }
 
 impl Cache {
-    pub const fn new(pool: CachePool) -> Self {
-        Self { pool }
+    /// Create a cache whose keys are namespaced by `service`.
+    pub fn new(pool: CachePool, service: &str) -> Self {
+        Self {
+            pool,
+            namespace: service.to_string(),
+            codec: CacheCodec::Json,
+            local: None,
+            in_flight: Arc::new(InFlightLoads::default()),
+            metrics: Arc::new(CacheMetrics::default()),
+        }
+    }
+
+    /// Use `codec` for the values read and written by `get_or_load`.
+    pub fn with_codec(self, codec: CacheCodec) -> Self {
+        Self { codec, ..self }
+    }
+
+    /// Keep recently read values in process, as configured by `settings`.
+    pub fn with_local_tier(self, settings: Option<&LocalCacheSettings>) -> Self {
+        Self {
+            local: settings.map(|settings| Arc::new(LocalCache::new(settings))),
+            ..self
+        }
+    }
+
+    /// Build the keys of `entity` in the namespace of this cache.
+    pub fn key(&self, entity: CacheEntity) -> CacheKeyBuilder<'_> {
+        CacheKeyBuilder::new(&self.namespace, entity)
+    }
+
+    fn tag_key(&self, tag: &str) -> String {
+        format!("{}:tag:{tag}", self.namespace)
+    }
+
+    pub(super) fn lock_key(&self, name: &str) -> String {
+        format!("{}:lock:{name}", self.namespace)
+    }
+
+    /// The lookups served so far, reported by the health check of the
+    /// services.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            local_hits: self.metrics.local_hits.load(Ordering::Relaxed),
+            hits: self.metrics.hits.load(Ordering::Relaxed),
+            misses: self.metrics.misses.load(Ordering::Relaxed),
+            loads: self.metrics.loads.load(Ordering::Relaxed),
+        }
     }
 
-    async fn connection(&self) -> Result<Connection, InternalError> {
+    /// Drop `keys` from the in-process tier of every replica as their
+    /// invalidation is broadcast. Returns when the subscription is lost.
+    pub async fn follow_invalidations(&self, client: &Client) -> Result<(), InternalError> {
+        let local = match &self.local {
+            Some(local) => Arc::clone(local),
+            None => return Ok(()),
+        };
+
+        let mut invalidations = Box::pin(invalidations(client).await?);
+        while let Some(invalidation) = invalidations.next().await {
+            debug!("EVICT | {:?}", invalidation.keys);
+            local.evict(invalidation.keys.as_slice());
+        }
+        Ok(())
+    }
+
+    pub(super) async fn connection(&self) -> Result<Connection, InternalError> {
         Ok(self.pool.get().await?)
     }
 
-    pub async fn get<T>(&self, key: &str) -> Result<Option<T>, InternalError>
+    /// Read the value at `key`, decoded with `codec`, from the in-process
+    /// tier or else with a single `GET` on Redis. Values which cannot be
+    /// decoded (e.g. written by an older version of the type) are reported as
+    /// a miss.
+    pub async fn get_with<T>(
+        &self,
+        codec: CacheCodec,
+        key: &str,
+    ) -> Result<Option<T>, InternalError>
     where
-        T: FromRedisValue + Debug + Send + Sync,
+        T: DeserializeOwned,
     {
+        if let Some(bytes) = self.local.as_ref().and_then(|local| local.get(key)) {
+            if let Ok(value) = codec.decode(&bytes) {
+                CacheMetrics::record(&self.metrics.local_hits);
+                return Ok(Some(value));
+            }
+        }
+
         let mut cache = self.connection().await?;
 
-        if self.exists(key).await? {
-            let result = cache.get(&key).await;
-            if let Ok(inner) = result {
-                info!("HIT | {key} | {inner:#?}");
-                return Ok(Some(inner));
+        let bytes: Option<Vec<u8>> = cache.get(key).await?;
+        let bytes = match bytes {
+            Some(bytes) => Arc::new(bytes),
+            None => {
+                CacheMetrics::record(&self.metrics.misses);
+                return Ok(None);
+            }
+        };
+        match codec.decode(&bytes) {
+            Ok(value) => {
+                CacheMetrics::record(&self.metrics.hits);
+                if let Some(local) = &self.local {
+                    local.put(key, bytes);
+                }
+                Ok(Some(value))
+            }
+            Err(err) => {
+                warn!("MISS | {key} | undecodable value: {err}");
+                CacheMetrics::record(&self.metrics.misses);
+                Ok(None)
             }
         }
-
-        info!("MISS | {key}");
-        Ok(None)
     }
 
-    pub async fn set<T>(&self, key: &str, value: T, expiry: usize) -> Result<(), InternalError>
+    /// Write `value` at `key`, encoded with `codec`, expiring after `expiry`
+    /// seconds.
+    pub async fn set_with<T>(
+        &self,
+        codec: CacheCodec,
+        key: &str,
+        value: &T,
+        expiry: usize,
+    ) -> Result<(), InternalError>
     where
-        T: ToRedisArgs + Debug + Send + Sync,
+        T: Serialize,
     {
         let mut cache = self.connection().await?;
+        let bytes = Arc::new(codec.encode(value)?);
 
-        info!("SET | {key} | {value:#?}");
-        let result = cache.set_ex::<_, _, ()>(&key, value, expiry).await;
+        debug!("SET | {key}");
+        cache
+            .set_ex::<_, _, ()>(key, bytes.as_slice(), expiry)
+            .await?;
+        if let Some(local) = &self.local {
+            local.put(key, bytes);
+        }
+        Ok(())
+    }
 
-        if result.is_err() {
-            info!("Failed to set");
+    pub async fn get_json<T>(&self, key: &str) -> Result<Option<T>, InternalError>
+    where
+        T: DeserializeOwned,
+    {
+        self.get_with(CacheCodec::Json, key).await
+    }
+
+    pub async fn set_json<T>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry: usize,
+    ) -> Result<(), InternalError>
+    where
+        T: Serialize,
+    {
+        self.set_with(CacheCodec::Json, key, value, expiry).await
+    }
+
+    pub async fn get_msgpack<T>(&self, key: &str) -> Result<Option<T>, InternalError>
+    where
+        T: DeserializeOwned,
+    {
+        self.get_with(CacheCodec::MessagePack, key).await
+    }
+
+    pub async fn set_msgpack<T>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry: usize,
+    ) -> Result<(), InternalError>
+    where
+        T: Serialize,
+    {
+        self.set_with(CacheCodec::MessagePack, key, value, expiry)
+            .await
+    }
+
+    /// Cache-aside read: return the cached value at `key` or, on a miss, the
+    /// one produced by `loader`, which is then cached for `expiry` seconds.
+    /// Values the loader does not find are not cached.
+    pub async fn get_or_load<T, F, Fut>(
+        &self,
+        key: &str,
+        expiry: usize,
+        loader: F,
+    ) -> Result<Option<T>, InternalError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<T>, InternalError>>,
+    {
+        self.get_or_load_tagged(key, &[], expiry, loader).await
+    }
+
+    /// Same as `get_or_load`, also attaching `tags` to the cached value so
+    /// that it is dropped by `invalidate_tag`.
+    pub async fn get_or_load_tagged<T, F, Fut>(
+        &self,
+        key: &str,
+        tags: &[&str],
+        expiry: usize,
+        loader: F,
+    ) -> Result<Option<T>, InternalError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<T>, InternalError>>,
+    {
+        if let Some(value) = self.get_with(self.codec, key).await? {
+            return Ok(Some(value));
+        }
+
+        let load = Arc::clone(self.in_flight.lock().entry(key.to_string()).or_default());
+        let loaded: Result<Option<T>, InternalError> = async {
+            let _guard = match load.try_lock() {
+                Some(guard) => guard,
+                None => {
+                    // another lookup is loading the value: wait for it, then
+                    // read what it cached
+                    let guard = load.lock().await;
+                    if let Some(value) = self.get_with(self.codec, key).await? {
+                        return Ok(Some(value));
+                    }
+                    guard
+                }
+            };
+
+            CacheMetrics::record(&self.metrics.loads);
+            let value = loader().await?;
+            if let Some(value) = &value {
+                self.set_with(self.codec, key, value, expiry).await?;
+                for tag in tags {
+                    self.tag(tag, key, expiry).await?;
+                }
+            }
+            Ok(value)
         }
+        .await;
+
+        let mut in_flight = self.in_flight.lock();
+        if matches!(in_flight.get(key), Some(current) if Arc::ptr_eq(current, &load)) {
+            in_flight.remove(key);
+        }
+        loaded
+    }
+
+    /// Attach `tag` to `key`. The tag expires along with the latest key
+    /// attached to it.
+    pub async fn tag(&self, tag: &str, key: &str, expiry: usize) -> Result<(), InternalError> {
+        let mut cache = self.connection().await?;
+        let tag_key = self.tag_key(tag);
+
+        debug!("TAG | {tag} | {key}");
+        redis::pipe()
+            .sadd(&tag_key, key)
+            .ignore()
+            .expire(&tag_key, expiry)
+            .ignore()
+            .query_async::<_, ()>(&mut *cache)
+            .await?;
         Ok(())
     }
 
+    /// Remove `keys` and broadcast their invalidation to the other replicas.
+    pub async fn invalidate(&self, keys: &[&str]) -> Result<(), InternalError> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let mut cache = self.connection().await?;
+
+        debug!("INVALIDATE | {keys:?}");
+        cache.del::<_, ()>(keys).await?;
+        if let Some(local) = &self.local {
+            local.evict(keys);
+        }
+        self.broadcast_invalidation(keys.iter().map(|key| key.to_string()).collect())
+            .await
+    }
+
+    /// Remove every key attached to `tag`, then the tag itself, and broadcast
+    /// their invalidation to the other replicas.
+    pub async fn invalidate_tag(&self, tag: &str) -> Result<(), InternalError> {
+        let mut cache = self.connection().await?;
+        let tag_key = self.tag_key(tag);
+
+        let mut keys: Vec<String> = cache.smembers(&tag_key).await?;
+        keys.push(tag_key);
+
+        debug!("INVALIDATE TAG | {tag} | {keys:?}");
+        cache.del::<_, ()>(&keys).await?;
+        if let Some(local) = &self.local {
+            local.evict(keys.as_slice());
+        }
+        self.broadcast_invalidation(keys).await
+    }
+
+    async fn broadcast_invalidation(&self, keys: Vec<String>) -> Result<(), InternalError> {
+        let message = serde_json::to_string(&CacheInvalidation { keys })?;
+        self.publish(CHANNEL_CACHE_INVALIDATION, &message).await
+    }
+
     pub async fn delete(&self, key: &str) -> Result<(), InternalError> {
         let mut cache = self.connection().await?;
 
-        info!("DELETE | {key}");
+        debug!("DELETE | {key}");
         let result = cache.del::<_, ()>(&key).await;
+        if let Some(local) = &self.local {
+            local.evict(&[key]);
+        }
 
         if result.is_err() {
             info!("Failed to delete");
@@ -110,16 +688,99 @@ impl Cache {
         Ok(())
     }
 
+    pub async fn publish(&self, channel: &str, message: &str) -> Result<(), InternalError> {
+        let mut cache = self.connection().await?;
+
+        debug!("PUBLISH | {channel}");
+        cache.publish::<_, _, ()>(channel, message).await?;
+        Ok(())
+    }
+
+    /// Record a hit on `key` unless `limit` hits were already recorded within
+    /// the last `window`. The check and the update are atomic, so the limit
+    /// holds across replicas.
+    pub async fn sliding_window_hit(
+        &self,
+        key: &str,
+        limit: u64,
+        window: Duration,
+    ) -> Result<RateLimit, InternalError> {
+        let mut cache = self.connection().await?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let member = format!("{now}-{}", uuid::Uuid::new_v4());
+
+        let (allowed, count, retry_after_ms): (u8, u64, u64) = Script::new(SLIDING_WINDOW_SCRIPT)
+            .key(key)
+            .arg(now)
+            .arg(window.as_millis() as u64)
+            .arg(limit)
+            .arg(member)
+            .invoke_async(&mut *cache)
+            .await?;
+
+        debug!("RATE | {key} | {count}/{limit}");
+        Ok(RateLimit {
+            allowed: allowed == 1,
+            count,
+            retry_after: Duration::from_millis(retry_after_ms),
+        })
+    }
+
+    /// Record a hit in each of the `(key, limit, window)` windows, provided
+    /// all of them have room: a hit rejected by one window is recorded in
+    /// none, so that it does not use up the room of the others.
+    pub async fn sliding_windows_hit(
+        &self,
+        windows: &[(&str, u64, Duration)],
+    ) -> Result<Option<RateLimitRejection>, InternalError> {
+        if windows.is_empty() {
+            return Ok(None);
+        }
+        let mut cache = self.connection().await?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let member = format!("{now}-{}", uuid::Uuid::new_v4());
+
+        let script = Script::new(SLIDING_WINDOWS_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        invocation.arg(now).arg(member);
+        for (key, limit, window) in windows {
+            invocation
+                .key(*key)
+                .arg(window.as_millis() as u64)
+                .arg(*limit);
+        }
+        let (allowed, index, retry_after_ms): (u8, usize, u64) =
+            invocation.invoke_async(&mut *cache).await?;
+
+        debug!(
+            "RATE | {} windows | allowed: {}",
+            windows.len(),
+            allowed == 1
+        );
+        Ok((allowed == 0).then(|| RateLimitRejection {
+            index: index.saturating_sub(1),
+            retry_after: Duration::from_millis(retry_after_ms),
+        }))
+    }
+
     pub async fn exists(&self, key: &str) -> Result<bool, InternalError> {
         let mut cache = self.connection().await?;
 
         let cache_exists: RedisResult<bool> = cache.exists(&key).await;
         if let Ok(exists) = cache_exists {
-            info!("EXISTS | {key} | {exists}");
+            debug!("EXISTS | {key} | {exists}");
             return Ok(exists);
         }
 
-        info!("EXISTS | {key} | false");
+        debug!("EXISTS | {key} | false");
         Ok(false)
     }
 }
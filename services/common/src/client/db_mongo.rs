@@ -1,17 +1,48 @@
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
 
+use super::update_schema_enabled;
 use crate::error::InternalError;
+use futures::future::BoxFuture;
 use mongodb::{
-    bson::{doc, Document},
-    options::ClientOptions,
+    bson::{self, doc, Document},
+    error::{ErrorKind, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT},
+    options::{ClientOptions, IndexOptions, UpdateOptions},
     Client,
+    ClientSession,
+    Collection,
     Database,
+    IndexModel,
 };
-use tracing::info;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
 
 use serde_aux::field_attributes::deserialize_number_from_string;
 
+pub use super::ENV_UPDATE_SCHEMA_ENABLED;
+
+// Collection of the single document holding the schema version of the db and
+// the lock of its updates
+const COLLECTION_SCHEMA: &str = "_schema";
+const SCHEMA_ID: &str = "schema";
+const SCHEMA_VERSION: &str = "VERSION";
+const SCHEMA_LOCKED_BY: &str = "LOCKED_BY";
+const SCHEMA_LOCKED_UNTIL: &str = "LOCKED_UNTIL";
+const SCHEMA_UPDATED_AT: &str = "UPDATED_AT";
+
+// Renewed before every migration step, so it bounds the duration of a step
+const SCHEMA_LOCK_LEASE: Duration = Duration::from_secs(300);
+const SCHEMA_LOCK_RETRY: Duration = Duration::from_secs(2);
+
+// Codes of the errors dropping an index which is already gone
+const NAMESPACE_NOT_FOUND: i32 = 26;
+const INDEX_NOT_FOUND: i32 = 27;
+
+// Bound on the retries of a transaction, and on the backoff between them
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(120);
+const TRANSACTION_RETRY_BASE: Duration = Duration::from_millis(10);
+const TRANSACTION_RETRY_MAX: Duration = Duration::from_secs(1);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MongoClientSettings {
     pub host: String,
@@ -31,6 +62,17 @@ pub async fn connect(
     config: &MongoClientSettings,
     secrets: &MongoClientSecrets,
 ) -> Result<Database, InternalError> {
+    let client = connect_client(app_name, config, secrets).await?;
+    Ok(client.database(&config.database_name))
+}
+
+/// Connect to the deployment, for the services running transactions with
+/// [`with_transaction`]. The database of the service is `client.database`.
+pub async fn connect_client(
+    app_name: &str,
+    config: &MongoClientSettings,
+    secrets: &MongoClientSecrets,
+) -> Result<Client, InternalError> {
     let mongo_server_url = format!(
         "mongodb://{}:{}@{}:{}",
         secrets.user_name,
@@ -50,13 +92,432 @@ pub async fn connect(
 
     info!("Connecting to MongoDB...");
 
-    let db = client.database(&config.database_name);
-    ping(&db).await?;
+    ping(&client.database(&config.database_name)).await?;
 
     info!("Connected to MongoDB");
-    Ok(db)
+    Ok(client)
+}
+
+/// Run `operation` in a transaction, committed when it succeeds and aborted
+/// when it fails.
+///
+/// The transaction runs again when aborted by a transient error, such as a
+/// write conflict, and its commit is retried when its outcome is unknown, for
+/// up to two minutes. Retries back off exponentially with jitter, so that
+/// conflicting transactions do not retry in step. `operation` must therefore
+/// be safe to run more than once, and reach the db through the session only.
+/// Transactions need the deployment to be a replica set.
+pub async fn with_transaction<C, R, F>(
+    client: &Client,
+    context: &C,
+    mut operation: F,
+) -> Result<R, InternalError>
+where
+    C: Sync,
+    F: for<'a> FnMut(&'a mut ClientSession, &'a C) -> BoxFuture<'a, Result<R, InternalError>>,
+{
+    let mut session = client.start_session(None).await?;
+    let deadline = Instant::now() + TRANSACTION_TIMEOUT;
+    let mut attempt = 0;
+
+    'transaction: loop {
+        session.start_transaction(None).await?;
+
+        let result = match operation(&mut session, context).await {
+            Ok(result) => result,
+            Err(err) => {
+                if let Err(abort_err) = session.abort_transaction().await {
+                    debug!("Failed to abort transaction: {}", abort_err);
+                }
+                if matches!(err, InternalError::DbTransientTransactionError { .. })
+                    && Instant::now() < deadline
+                {
+                    warn!("Retrying transaction: {}", err);
+                    backoff(&mut attempt).await;
+                    continue 'transaction;
+                }
+                return Err(err);
+            }
+        };
+
+        loop {
+            let err = match session.commit_transaction().await {
+                Ok(()) => return Ok(result),
+                Err(err) => err,
+            };
+            if Instant::now() >= deadline {
+                return Err(err.into());
+            }
+            if err.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT) {
+                warn!("Retrying commit of transaction: {}", err);
+                backoff(&mut attempt).await;
+                continue;
+            }
+            if err.contains_label(TRANSIENT_TRANSACTION_ERROR) {
+                warn!("Retrying transaction: {}", err);
+                backoff(&mut attempt).await;
+                continue 'transaction;
+            }
+            return Err(err.into());
+        }
+    }
+}
+
+async fn backoff(attempt: &mut u32) {
+    actix::clock::sleep(retry_delay(*attempt)).await;
+    *attempt = attempt.saturating_add(1);
+}
+
+// A random delay up to a ceiling doubling with every attempt, up to a max
+fn retry_delay(attempt: u32) -> Duration {
+    let ceiling = TRANSACTION_RETRY_BASE
+        .saturating_mul(1 << attempt.min(16))
+        .min(TRANSACTION_RETRY_MAX);
+    // the low bits of a v4 uuid are random
+    let jitter = uuid::Uuid::new_v4().as_u128() as u32;
+    ceiling.mul_f64(f64::from(jitter) / f64::from(u32::MAX))
 }
 
 pub async fn ping(db: &Database) -> Result<Document, InternalError> {
     Ok(db.run_command(doc! { "ping": 1 }, None).await?)
 }
+
+pub type MigrationStep = for<'a> fn(&'a Database) -> BoxFuture<'a, Result<(), InternalError>>;
+
+/// A step of the schema of a service, bringing it to `version`. Versions start
+/// at 1 and increase by one with each step. Steps must be idempotent, as a
+/// step interrupted before its version is recorded runs again.
+#[derive(Clone, Copy)]
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub run: MigrationStep,
+}
+
+impl Migration {
+    pub const fn new(version: i32, description: &'static str, run: MigrationStep) -> Self {
+        Migration {
+            version,
+            description,
+            run,
+        }
+    }
+}
+
+/// An index of a collection, created at startup when missing.
+#[derive(Debug, Clone, Copy)]
+pub struct IndexDefinition {
+    pub collection: &'static str,
+    pub name: &'static str,
+    /// Fields with their order, `1` ascending or `-1` descending.
+    pub keys: &'static [(&'static str, i32)],
+    pub unique: bool,
+    pub expire_after: Option<Duration>,
+    /// Dropped rather than created, for the indexes replaced by another.
+    pub obsolete: bool,
+}
+
+impl IndexDefinition {
+    pub const fn new(
+        collection: &'static str,
+        name: &'static str,
+        keys: &'static [(&'static str, i32)],
+    ) -> Self {
+        IndexDefinition {
+            collection,
+            name,
+            keys,
+            unique: false,
+            expire_after: None,
+            obsolete: false,
+        }
+    }
+
+    pub const fn unique(self) -> Self {
+        IndexDefinition {
+            unique: true,
+            ..self
+        }
+    }
+
+    /// Drop the index where it still exists, e.g. once replaced by an index
+    /// of another name.
+    pub const fn obsolete(self) -> Self {
+        IndexDefinition {
+            obsolete: true,
+            ..self
+        }
+    }
+
+    /// Delete the documents `expire_after` the date of the first key.
+    pub const fn expire_after(self, expire_after: Duration) -> Self {
+        IndexDefinition {
+            expire_after: Some(expire_after),
+            ..self
+        }
+    }
+}
+
+/// The migrations and indexes of the db of a service.
+pub struct Schema {
+    pub migrations: &'static [Migration],
+    pub indexes: &'static [IndexDefinition],
+}
+
+impl Schema {
+    /// The version the code expects the db to be at.
+    pub fn version(&self) -> i32 {
+        self.migrations
+            .iter()
+            .map(|migration| migration.version)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Bring the db to the version of `schema` and create its indexes.
+///
+/// Only the instances started with `UPDATE_SCHEMA_ENABLED` run migrations,
+/// the others refuse to start on an outdated db. A lock with a lease keeps
+/// instances from migrating concurrently: the instances finding the db locked
+/// wait for the update to complete, up to the lease.
+pub async fn migrate(db: &Database, schema: &Schema) -> Result<(), InternalError> {
+    let code_version = schema.version();
+    let collection = db.collection::<Document>(COLLECTION_SCHEMA);
+
+    let options = UpdateOptions::builder().upsert(true).build();
+    let created = collection
+        .update_one(
+            doc! { "_id": SCHEMA_ID },
+            doc! { "$setOnInsert": { SCHEMA_VERSION: 0 } },
+            options,
+        )
+        .await;
+    if let Err(err) = created {
+        match InternalError::from(err) {
+            // created concurrently by another instance
+            InternalError::DbDuplicateError { .. } => {}
+            err => return Err(err),
+        }
+    }
+
+    let deadline = Instant::now() + SCHEMA_LOCK_LEASE;
+    loop {
+        let db_version = schema_version(&collection).await?;
+        if db_version > code_version {
+            // an older release, e.g. during a rolling update
+            warn!("Db schema is v{db_version}, ahead of v{code_version} of this release");
+        }
+        if db_version >= code_version {
+            break;
+        }
+        if !update_schema_enabled() {
+            return Err(InternalError::DbSchemaError {
+                code_version,
+                db_version,
+            });
+        }
+
+        let owner = uuid::Uuid::new_v4().to_string();
+        if lock_schema(&collection, &owner).await? {
+            let migrated = run_migrations(db, &collection, schema, &owner).await;
+            unlock_schema(&collection, &owner).await?;
+            migrated?;
+            break;
+        }
+
+        if Instant::now() >= deadline {
+            let lock = collection
+                .find_one(doc! { "_id": SCHEMA_ID }, None)
+                .await?
+                .unwrap_or_default();
+            return Err(InternalError::DbLockedForUpdate {
+                cause: format!(
+                    "Locked by {} until {}",
+                    lock.get_str(SCHEMA_LOCKED_BY).unwrap_or("unknown"),
+                    lock.get_datetime(SCHEMA_LOCKED_UNTIL)
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
+                ),
+            });
+        }
+        info!("Waiting for the db schema update of another instance...");
+        actix::clock::sleep(SCHEMA_LOCK_RETRY).await;
+    }
+
+    create_indexes(db, schema.indexes).await
+}
+
+async fn schema_version(collection: &Collection<Document>) -> Result<i32, InternalError> {
+    let schema = collection
+        .find_one(doc! { "_id": SCHEMA_ID }, None)
+        .await?
+        .unwrap_or_default();
+    Ok(schema.get_i32(SCHEMA_VERSION).unwrap_or(0))
+}
+
+// Take the lock, or its lease when it is ours. Returns false when it is held
+// by another instance.
+async fn lock_schema(
+    collection: &Collection<Document>,
+    owner: &str,
+) -> Result<bool, InternalError> {
+    let now = bson::DateTime::now();
+    let lease_until =
+        bson::DateTime::from_millis(now.timestamp_millis() + SCHEMA_LOCK_LEASE.as_millis() as i64);
+
+    let res = collection
+        .update_one(
+            doc! {
+                "_id": SCHEMA_ID,
+                "$or": [
+                    { SCHEMA_LOCKED_BY: owner },
+                    { SCHEMA_LOCKED_UNTIL: { "$exists": false } },
+                    { SCHEMA_LOCKED_UNTIL: { "$lt": now } },
+                ],
+            },
+            doc! { "$set": { SCHEMA_LOCKED_BY: owner, SCHEMA_LOCKED_UNTIL: lease_until } },
+            None,
+        )
+        .await?;
+    Ok(res.matched_count == 1)
+}
+
+async fn unlock_schema(
+    collection: &Collection<Document>,
+    owner: &str,
+) -> Result<(), InternalError> {
+    collection
+        .update_one(
+            doc! { "_id": SCHEMA_ID, SCHEMA_LOCKED_BY: owner },
+            doc! { "$unset": { SCHEMA_LOCKED_BY: "", SCHEMA_LOCKED_UNTIL: "" } },
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+async fn run_migrations(
+    db: &Database,
+    collection: &Collection<Document>,
+    schema: &Schema,
+    owner: &str,
+) -> Result<(), InternalError> {
+    // another instance may have migrated before the lock was taken
+    let db_version = schema_version(collection).await?;
+
+    let mut pending: Vec<&Migration> = schema
+        .migrations
+        .iter()
+        .filter(|migration| migration.version > db_version)
+        .collect();
+    pending.sort_by_key(|migration| migration.version);
+
+    for migration in pending {
+        if !lock_schema(collection, owner).await? {
+            return Err(InternalError::DbLockedForUpdate {
+                cause: "The lease of the lock expired during the update".to_string(),
+            });
+        }
+
+        info!(
+            "Migrating db schema to v{}: {}",
+            migration.version, migration.description
+        );
+        (migration.run)(db).await?;
+
+        collection
+            .update_one(
+                doc! { "_id": SCHEMA_ID, SCHEMA_LOCKED_BY: owner },
+                doc! {
+                    "$set": {
+                        SCHEMA_VERSION: migration.version,
+                        SCHEMA_UPDATED_AT: bson::DateTime::now(),
+                    }
+                },
+                None,
+            )
+            .await?;
+    }
+    info!("Db schema is up to date at v{}", schema.version());
+    Ok(())
+}
+
+/// Create the `indexes` missing from their collections.
+pub async fn create_indexes(
+    db: &Database,
+    indexes: &[IndexDefinition],
+) -> Result<(), InternalError> {
+    for index in indexes {
+        create_index(&db.collection(index.collection), index).await?;
+    }
+    Ok(())
+}
+
+/// Create `index` in `collection` when missing, rather than in the collection
+/// it is defined for, e.g. in the collection of a tenant. An obsolete index is
+/// dropped instead.
+pub async fn create_index(
+    collection: &Collection<Document>,
+    index: &IndexDefinition,
+) -> Result<(), InternalError> {
+    if index.obsolete {
+        return match collection.drop_index(index.name, None).await {
+            Err(err) if index_not_found(&err) => Ok(()),
+            result => Ok(result?),
+        };
+    }
+
+    let mut keys = Document::new();
+    for (field, order) in index.keys {
+        keys.insert(*field, *order);
+    }
+    let options = IndexOptions::builder()
+        .name(index.name.to_string())
+        .unique(index.unique)
+        .expire_after(index.expire_after)
+        .build();
+
+    collection
+        .create_index(
+            IndexModel::builder().keys(keys).options(options).build(),
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+fn index_not_found(err: &mongodb::error::Error) -> bool {
+    matches!(
+        &*err.kind,
+        ErrorKind::Command(command)
+            if command.code == NAMESPACE_NOT_FOUND || command.code == INDEX_NOT_FOUND
+    )
+}
+
+/// Create the collections missing among `names`, for the migrations setting
+/// up a schema.
+pub async fn create_collections(db: &Database, names: &[&str]) -> Result<(), InternalError> {
+    let existing = db.list_collection_names(None).await?;
+    for name in names {
+        if !existing.iter().any(|collection| collection == name) {
+            db.create_collection(*name, None).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_up_to_a_doubling_ceiling() {
+        for _ in 0..100 {
+            assert!(retry_delay(0) <= Duration::from_millis(10));
+            assert!(retry_delay(3) <= Duration::from_millis(80));
+            assert!(retry_delay(10) <= TRANSACTION_RETRY_MAX);
+            assert!(retry_delay(u32::MAX) <= TRANSACTION_RETRY_MAX);
+        }
+    }
+}
@@ -1,22 +1,26 @@
-use secrecy::ExposeSecret;
-use sqlx::postgres::PgPoolOptions;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use sqlx::{migrate::Migrator, postgres::PgPoolOptions};
 use std::time::Duration;
-use tracing::error;
+use tracing::{error, info, warn};
+
+use super::update_schema_enabled;
+use crate::error::InternalError;
 
-use secrecy::Secret;
 use serde_aux::field_attributes::deserialize_number_from_string;
 
 pub type DatabasePool = sqlx::postgres::PgPool;
 pub type DatabaseRow = sqlx::postgres::PgRow;
 pub type QueryResult = sqlx::postgres::PgQueryResult;
 
-#[derive(Debug, serde::Deserialize, Clone)]
+// Table in which sqlx records the migrations applied
+const TABLE_MIGRATIONS: &str = "_sqlx_migrations";
+
+#[derive(Debug, Deserialize, Clone)]
 pub struct PostgresClientSettings {
-    pub user_name: String,
-    pub password: Secret<String>,
+    pub host: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
-    pub host: String,
     pub database_name: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub connections_per_pool: u32,
@@ -24,17 +28,26 @@ pub struct PostgresClientSettings {
     pub database_lifetime: u64,
 }
 
-pub async fn connect(config: &PostgresClientSettings) -> Result<DatabasePool, sqlx::Error> {
+#[derive(Debug, Deserialize)]
+pub struct PostgresClientSecrets {
+    pub user_name: String,
+    pub password: Secret<String>,
+}
+
+pub async fn connect(
+    config: &PostgresClientSettings,
+    secrets: &PostgresClientSecrets,
+) -> Result<DatabasePool, InternalError> {
     let pg_server_url = format!(
         "postgresql://{}:{}@{}:{}/{}",
-        config.user_name,
-        config.password.expose_secret(),
+        secrets.user_name,
+        secrets.password.expose_secret(),
         config.host,
         config.port,
         config.database_name,
     );
 
-    PgPoolOptions::new()
+    let pool = PgPoolOptions::new()
         .max_connections(config.connections_per_pool)
         .max_lifetime(Duration::from_secs(config.database_lifetime))
         .connect(&pg_server_url)
@@ -42,5 +55,60 @@ pub async fn connect(config: &PostgresClientSettings) -> Result<DatabasePool, sq
         .map_err(|error| {
             error!("Failed to connect to Postgres: {error:#?}");
             error
-        })
+        })?;
+    Ok(pool)
+}
+
+pub async fn ping(pool: &DatabasePool) -> Result<(), InternalError> {
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
+}
+
+/// Apply the pending migrations of `migrator`.
+///
+/// As for Mongo, only the instances started with `UPDATE_SCHEMA_ENABLED` run
+/// migrations and the others refuse to start on an outdated db. sqlx holds an
+/// advisory lock while migrating, so that instances migrate one at a time.
+pub async fn migrate(pool: &DatabasePool, migrator: &Migrator) -> Result<(), InternalError> {
+    let code_version = migrator
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0);
+    let db_version = schema_version(pool).await?;
+    if db_version > code_version {
+        // an older release, e.g. during a rolling update
+        warn!("Db schema is v{db_version}, ahead of v{code_version} of this release");
+    }
+    if db_version >= code_version {
+        return Ok(());
+    }
+    if !update_schema_enabled() {
+        return Err(InternalError::DbSchemaError {
+            code_version: i32::try_from(code_version)?,
+            db_version: i32::try_from(db_version)?,
+        });
+    }
+
+    info!("Migrating db schema from v{db_version} to v{code_version}");
+    migrator.run(pool).await?;
+    Ok(())
+}
+
+// The version of the last migration applied, 0 on a new db
+async fn schema_version(pool: &DatabasePool) -> Result<i64, InternalError> {
+    let migrations: Option<String> = sqlx::query_scalar("SELECT to_regclass($1)::text")
+        .bind(TABLE_MIGRATIONS)
+        .fetch_one(pool)
+        .await?;
+    if migrations.is_none() {
+        return Ok(0);
+    }
+
+    let version = sqlx::query_scalar(&format!(
+        "SELECT coalesce(max(version), 0) FROM {TABLE_MIGRATIONS} WHERE success"
+    ))
+    .fetch_one(pool)
+    .await?;
+    Ok(version)
 }
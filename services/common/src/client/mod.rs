@@ -1,6 +1,44 @@
+pub mod cache_codec;
+pub mod cache_key;
+pub mod cache_local;
+pub mod cache_lock;
 pub mod cache_redis;
 #[cfg(feature = "mongo")]
 pub mod db_mongo;
 #[cfg(feature = "postgres")]
 pub mod db_postgres;
+#[cfg(feature = "mongo")]
+pub mod mongo_filter;
+#[cfg(feature = "mongo")]
+pub mod mongo_repository;
+#[cfg(feature = "mongo")]
+pub mod mongo_search;
+#[cfg(all(feature = "mongo", feature = "postgres"))]
+pub mod postgres_repository;
 pub mod sm_vault;
+#[cfg(feature = "mongo")]
+pub mod tenant_isolation;
+
+use serde::Deserialize;
+
+/// Set to `true` on the instances allowed to update the db schema.
+pub const ENV_UPDATE_SCHEMA_ENABLED: &str = "UPDATE_SCHEMA_ENABLED";
+
+/// Whether this instance may update the db schema, see
+/// [`ENV_UPDATE_SCHEMA_ENABLED`].
+#[cfg(any(feature = "mongo", feature = "postgres"))]
+pub(crate) fn update_schema_enabled() -> bool {
+    std::env::var(ENV_UPDATE_SCHEMA_ENABLED)
+        .map(|enabled| enabled.eq_ignore_ascii_case("true") || enabled == "1")
+        .unwrap_or(false)
+}
+
+/// The store of the entities a service can keep either in Mongo or, built
+/// with the `postgres` feature, in Postgres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DbBackend {
+    #[default]
+    Mongo,
+    Postgres,
+}
@@ -0,0 +1,308 @@
+use crate::error::InternalError;
+use chrono::{DateTime, NaiveDate, Utc};
+use mongodb::bson::{self, doc, Bson, Document};
+
+/// The type of the values of a filterable field. Values are type checked and
+/// converted to the bson the entity serializes the field to, so that they
+/// compare with the stored documents.
+#[derive(Debug, Clone, Copy)]
+pub enum FilterFieldType {
+    String,
+    Uuid,
+    /// An RFC 3339 date time, or a date standing for its midnight UTC.
+    DateTime,
+    /// One of the serialized names of the variants, matched ignoring case.
+    Enum(&'static [&'static str]),
+}
+
+/// A field the listings of an entity can be filtered on.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterField {
+    pub name: &'static str,
+    pub field_type: FilterFieldType,
+}
+
+impl FilterField {
+    pub const fn new(name: &'static str, field_type: FilterFieldType) -> Self {
+        FilterField { name, field_type }
+    }
+
+    fn value(&self, value: &str) -> Result<Bson, InternalError> {
+        let invalid = || invalid_filter(format!("invalid value of `{}`: `{value}`", self.name));
+        match self.field_type {
+            FilterFieldType::String => Ok(Bson::String(value.to_string())),
+            FilterFieldType::Uuid => {
+                let uuid = bson::Uuid::parse_str(value).map_err(|_| invalid())?;
+                Ok(bson::to_bson(&uuid)?)
+            }
+            FilterFieldType::DateTime => {
+                let date_time = DateTime::parse_from_rfc3339(value)
+                    .map(|date_time| date_time.with_timezone(&Utc))
+                    .or_else(|_| {
+                        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                            .map(|date| DateTime::<Utc>::from_utc(date.and_hms(0, 0, 0), Utc))
+                    })
+                    .map_err(|_| invalid())?;
+                Ok(bson::to_bson(&date_time)?)
+            }
+            FilterFieldType::Enum(variants) => variants
+                .iter()
+                .find(|variant| variant.eq_ignore_ascii_case(value))
+                .map(|variant| Bson::String(variant.to_string()))
+                .ok_or_else(invalid),
+        }
+    }
+}
+
+/// The comparison of a condition of a filter expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOperator {
+    Eq,
+    Ne,
+    In,
+    Nin,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Prefix,
+    Exists,
+}
+
+impl FilterOperator {
+    fn parse(operator: &str) -> Option<Self> {
+        let operator = match operator.to_ascii_lowercase().as_str() {
+            "eq" => FilterOperator::Eq,
+            "ne" => FilterOperator::Ne,
+            "in" => FilterOperator::In,
+            "nin" => FilterOperator::Nin,
+            "gt" => FilterOperator::Gt,
+            "gte" => FilterOperator::Gte,
+            "lt" => FilterOperator::Lt,
+            "lte" => FilterOperator::Lte,
+            "prefix" => FilterOperator::Prefix,
+            "exists" => FilterOperator::Exists,
+            _ => return None,
+        };
+        Some(operator)
+    }
+}
+
+fn invalid_filter(cause: String) -> InternalError {
+    InternalError::ParameterValidationError { cause }
+}
+
+/// A condition of a filter expression, its values converted to the bson of
+/// the field. `exists` has a single boolean value, and `prefix` the raw prefix.
+#[derive(Debug, Clone)]
+pub struct FilterCondition {
+    pub field: FilterField,
+    pub operator: FilterOperator,
+    pub values: Vec<Bson>,
+}
+
+/// Parse a filter expression into a Mongo filter document.
+///
+/// An expression is a `;` separated list of `FIELD:operator:value` conditions,
+/// all of which must hold, e.g.
+/// `STATUS:in:ACTIVE,INACTIVE;CREATED_AT:gte:2026-01-01;EMAIL:prefix:foo`.
+/// The operators are `eq`, `ne`, `gt`, `gte`, `lt`, `lte`, `prefix` (strings
+/// only), `exists` (`true` or `false`), and `in`/`nin` taking `,` separated
+/// values. Only the `fields` can be filtered on.
+pub fn parse_filter(expression: &str, fields: &[FilterField]) -> Result<Document, InternalError> {
+    let conditions = parse_conditions(expression, fields)?
+        .into_iter()
+        .map(condition_document)
+        .collect::<Vec<_>>();
+
+    Ok(match conditions.len() {
+        0 => doc! {},
+        1 => conditions.into_iter().next().unwrap_or_default(),
+        _ => doc! { "$and": conditions },
+    })
+}
+
+/// Parse the conditions of a filter expression, see [`parse_filter`], for the
+/// stores other than Mongo to render.
+pub fn parse_conditions(
+    expression: &str,
+    fields: &[FilterField],
+) -> Result<Vec<FilterCondition>, InternalError> {
+    expression
+        .split(';')
+        .filter(|condition| !condition.trim().is_empty())
+        .map(|condition| parse_condition(condition, fields))
+        .collect()
+}
+
+fn parse_condition(
+    condition: &str,
+    fields: &[FilterField],
+) -> Result<FilterCondition, InternalError> {
+    // the value is last so that it may hold `:`, as date times do
+    let mut parts = condition.trim().splitn(3, ':');
+    let (name, operator, value) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(name), Some(operator), Some(value)) => (name, operator, value),
+        _ => {
+            return Err(invalid_filter(format!(
+                "invalid condition: `{condition}`, expected `FIELD:operator:value`"
+            )))
+        }
+    };
+
+    let field = fields
+        .iter()
+        .find(|field| field.name == name)
+        .ok_or_else(|| invalid_filter(format!("unsupported filter field: `{name}`")))?;
+    let operator = FilterOperator::parse(operator)
+        .ok_or_else(|| invalid_filter(format!("unsupported filter operator: `{operator}`")))?;
+
+    let values = match operator {
+        FilterOperator::In | FilterOperator::Nin => value
+            .split(',')
+            .map(|value| field.value(value))
+            .collect::<Result<Vec<_>, _>>()?,
+        FilterOperator::Prefix => {
+            if !matches!(field.field_type, FilterFieldType::String) {
+                return Err(invalid_filter(format!(
+                    "`prefix` only applies to string fields, not `{name}`"
+                )));
+            }
+            vec![Bson::String(value.to_string())]
+        }
+        FilterOperator::Exists => {
+            let exists = value
+                .parse::<bool>()
+                .map_err(|_| invalid_filter(format!("invalid value of `exists`: `{value}`")))?;
+            vec![Bson::Boolean(exists)]
+        }
+        _ => vec![field.value(value)?],
+    };
+    Ok(FilterCondition {
+        field: *field,
+        operator,
+        values,
+    })
+}
+
+fn condition_document(condition: FilterCondition) -> Document {
+    let FilterCondition {
+        field,
+        operator,
+        values,
+    } = condition;
+    let value = values.first().cloned().unwrap_or(Bson::Null);
+
+    let predicate = match operator {
+        FilterOperator::Eq => value,
+        FilterOperator::Ne => Bson::Document(doc! { "$ne": value }),
+        FilterOperator::Gt => Bson::Document(doc! { "$gt": value }),
+        FilterOperator::Gte => Bson::Document(doc! { "$gte": value }),
+        FilterOperator::Lt => Bson::Document(doc! { "$lt": value }),
+        FilterOperator::Lte => Bson::Document(doc! { "$lte": value }),
+        FilterOperator::In => Bson::Document(doc! { "$in": values }),
+        FilterOperator::Nin => Bson::Document(doc! { "$nin": values }),
+        FilterOperator::Prefix => {
+            let prefix = value.as_str().unwrap_or_default();
+            Bson::Document(doc! { "$regex": format!("^{}", escape_regex(prefix)) })
+        }
+        FilterOperator::Exists => Bson::Document(doc! { "$exists": value }),
+    };
+    doc! { field.name: predicate }
+}
+
+/// Escape `value` to match it literally in a regex.
+pub(crate) fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUSES: &[&str] = &["ACTIVE", "INACTIVE"];
+    const FIELDS: &[FilterField] = &[
+        FilterField::new("EMAIL", FilterFieldType::String),
+        FilterField::new("STATUS", FilterFieldType::Enum(STATUSES)),
+        FilterField::new("TENANT_ID", FilterFieldType::Uuid),
+        FilterField::new("CREATED_AT", FilterFieldType::DateTime),
+    ];
+
+    #[test]
+    fn parses_single_conditions() {
+        assert_eq!(parse_filter("", FIELDS).unwrap(), doc! {});
+        assert_eq!(
+            parse_filter("EMAIL:eq:a@b.c", FIELDS).unwrap(),
+            doc! { "EMAIL": "a@b.c" }
+        );
+        assert_eq!(
+            parse_filter("STATUS:NE:active", FIELDS).unwrap(),
+            doc! { "STATUS": { "$ne": "ACTIVE" } }
+        );
+        assert_eq!(
+            parse_filter("EMAIL:exists:false", FIELDS).unwrap(),
+            doc! { "EMAIL": { "$exists": false } }
+        );
+    }
+
+    #[test]
+    fn joins_conditions_and_splits_listed_values() {
+        assert_eq!(
+            parse_filter("STATUS:in:ACTIVE,inactive; EMAIL:nin:a@b.c,d@e.f", FIELDS).unwrap(),
+            doc! { "$and": [
+                { "STATUS": { "$in": ["ACTIVE", "INACTIVE"] } },
+                { "EMAIL": { "$nin": ["a@b.c", "d@e.f"] } },
+            ] }
+        );
+    }
+
+    #[test]
+    fn escapes_prefixes() {
+        assert_eq!(
+            parse_filter("EMAIL:prefix:a.b+c", FIELDS).unwrap(),
+            doc! { "EMAIL": { "$regex": r"^a\.b\+c" } }
+        );
+        assert!(parse_filter("STATUS:prefix:ACT", FIELDS).is_err());
+    }
+
+    #[test]
+    fn converts_uuids_and_date_times() {
+        let tenant_id = bson::Uuid::new();
+        assert_eq!(
+            parse_filter(&format!("TENANT_ID:eq:{tenant_id}"), FIELDS).unwrap(),
+            doc! { "TENANT_ID": bson::to_bson(&tenant_id).unwrap() }
+        );
+
+        // a date stands for its midnight UTC, and date times keep their `:`
+        assert_eq!(
+            parse_filter("CREATED_AT:gte:2026-01-01", FIELDS).unwrap(),
+            parse_filter("CREATED_AT:gte:2026-01-01T01:00:00+01:00", FIELDS).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_conditions() {
+        for expression in [
+            "EMAIL",
+            "EMAIL:eq",
+            "PASSWORD:eq:secret",
+            "EMAIL:like:a",
+            "STATUS:eq:DELETED",
+            "TENANT_ID:eq:not-a-uuid",
+            "CREATED_AT:lt:yesterday",
+            "EMAIL:exists:maybe",
+        ] {
+            assert!(
+                parse_filter(expression, FIELDS).is_err(),
+                "accepted `{expression}`"
+            );
+        }
+    }
+}
@@ -0,0 +1,843 @@
+use super::{
+    mongo_filter::{parse_filter, FilterField},
+    mongo_search::{fuzzy_filter, rank, search_terms, SearchField, TEXT_SCORE},
+};
+use crate::{
+    error::InternalError,
+    model::{
+        domain::pagination::Pagination,
+        request::{
+            filter_request::FilterRequest,
+            page_request::{PageRequest, SortDirection, SortOrder},
+            search_request::SearchRequest,
+        },
+        response::{page_response::PageResponse, search_hit::SearchHit},
+    },
+};
+use base64::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc, Bson, Document},
+    options::{FindOneAndUpdateOptions, FindOptions, IndexOptions, ReturnDocument},
+    ClientSession,
+    Collection,
+    Database,
+    IndexModel,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+// Every listing is ordered by id last, so that the order is total and a
+// cursor designates a single position.
+const TIE_BREAKER: &str = "_id";
+
+// Bound on the documents ranked by a search, from each of the text index and
+// the fuzzy match, and on the time the fuzzy match scans the collection for
+const MAX_SEARCH_CANDIDATES: i64 = 500;
+const MAX_FUZZY_SEARCH_TIME: Duration = Duration::from_secs(2);
+const MAX_TIME_EXPIRED: i32 = 50;
+
+/// The time a soft deleted entity was deleted at, unset on the live ones.
+pub const DELETED_AT: &str = "DELETED_AT";
+
+/// The version of a versioned entity, bumped by every write.
+pub const VERSION: &str = "VERSION";
+
+/// The creation and last write times of a timestamped entity.
+pub const CREATED_AT: &str = "CREATED_AT";
+pub const UPDATED_AT: &str = "UPDATED_AT";
+
+/// An entity stored in its own collection.
+///
+/// Filter and update documents are the serialized entity, so that they match
+/// the stored documents field for field. Entities are expected to skip their
+/// `None` fields on serialization (`#[skip_serializing_none]`), leaving only
+/// the fields set in a query or an update.
+pub trait MongoEntity: Serialize + DeserializeOwned + Clone + Unpin + Send + Sync {
+    type Id: Serialize + DeserializeOwned + Clone;
+
+    const COLLECTION: &'static str;
+    const ID: &'static str = "_id";
+    /// Fields, besides the id, the listings can be sorted by. They must be set
+    /// on every entity, and are indexed along with the id at startup.
+    const SORT_FIELDS: &'static [&'static str] = &[];
+    /// Fields the listings can be filtered on with a filter expression.
+    const FILTER_FIELDS: &'static [FilterField] = &[];
+    /// Fields searched by free text, covered by the text index of the
+    /// collection created at startup.
+    const SEARCH_FIELDS: &'static [SearchField] = &[];
+    /// Whether deleting an entity only sets its `DELETED_AT`, hiding it from
+    /// the queries until it is restored or purged.
+    const SOFT_DELETE: bool = false;
+    /// Whether the entity has a `VERSION`, starting at 1 and bumped by every
+    /// write, so that concurrent writes can be detected.
+    const VERSIONED: bool = false;
+    /// Whether the entity has a `CREATED_AT` and an `UPDATED_AT`, stamped by
+    /// the repository on every write rather than taken from the entity.
+    const TIMESTAMPED: bool = false;
+
+    fn id(&self) -> Option<&Self::Id>;
+
+    fn set_id(&mut self, id: Self::Id);
+
+    /// The version of a versioned entity.
+    fn version(&self) -> Option<i64> {
+        None
+    }
+}
+
+/// The documents matching both `filter` and `other`.
+fn and(filter: Document, other: Document) -> Document {
+    match (filter.is_empty(), other.is_empty()) {
+        (_, true) => filter,
+        (true, false) => other,
+        (false, false) => doc! { "$and": [filter, other] },
+    }
+}
+
+fn max_time_expired(err: &mongodb::error::Error) -> bool {
+    matches!(
+        &*err.kind,
+        mongodb::error::ErrorKind::Command(command) if command.code == MAX_TIME_EXPIRED
+    )
+}
+
+/// Position in a listing, after the element with these sort and id values.
+/// The values are kept as extended JSON so that their bson type survives the
+/// round trip.
+#[derive(Debug, Serialize, Deserialize)]
+struct PageCursor {
+    sort: String,
+    value: serde_json::Value,
+    id: serde_json::Value,
+}
+
+impl PageCursor {
+    fn after<T: Serialize>(element: &T, sort: &SortOrder) -> Result<String, InternalError> {
+        let element = bson::to_document(element)?;
+        let value = element.get(&sort.field).cloned().unwrap_or(Bson::Null);
+        let id = element.get(TIE_BREAKER).cloned().unwrap_or(Bson::Null);
+        let cursor = PageCursor {
+            sort: sort.to_string(),
+            value: value.into_relaxed_extjson(),
+            id: id.into_relaxed_extjson(),
+        };
+        Ok(base64::encode_config(
+            serde_json::to_vec(&cursor)?,
+            URL_SAFE_NO_PAD,
+        ))
+    }
+
+    /// The filter of the elements following the cursor `token`.
+    fn filter(token: &str, sort: &SortOrder) -> Result<Document, InternalError> {
+        let invalid = || InternalError::RequestFormatError {
+            reason: "invalid cursor".to_string(),
+        };
+        let cursor = base64::decode_config(token, URL_SAFE_NO_PAD).map_err(|_| invalid())?;
+        let cursor: PageCursor = serde_json::from_slice(&cursor).map_err(|_| invalid())?;
+        // a cursor is only valid in the order it was issued for
+        if cursor.sort != sort.to_string() {
+            return Err(invalid());
+        }
+        let value = Bson::try_from(cursor.value).map_err(|_| invalid())?;
+        let id = Bson::try_from(cursor.id).map_err(|_| invalid())?;
+
+        let after = match sort.direction {
+            SortDirection::Asc => "$gt",
+            SortDirection::Desc => "$lt",
+        };
+        if sort.field == TIE_BREAKER {
+            return Ok(doc! { TIE_BREAKER: { after: id } });
+        }
+        Ok(doc! {
+            "$or": [
+                { sort.field.as_str(): { after: value.clone() } },
+                { sort.field.as_str(): value, TIE_BREAKER: { after: id } },
+            ]
+        })
+    }
+}
+
+/// The page `page_request` of the documents of `collection` matching `filter`,
+/// ordered by `sort`. The page starts after `page_request.cursor` when given,
+/// else at `page_request.page`. The total is counted with the same filter.
+pub async fn find_page<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    sort: &SortOrder,
+    page_request: &PageRequest,
+) -> Result<PageResponse<T>, InternalError>
+where
+    T: Serialize + DeserializeOwned + Unpin + Send + Sync,
+{
+    let total = collection.count_documents(filter.clone(), None).await?;
+
+    let (filter, skip) = match &page_request.cursor {
+        Some(cursor) => {
+            let after = PageCursor::filter(cursor, sort)?;
+            (doc! { "$and": [filter, after] }, 0)
+        }
+        None => (filter, page_request.skip()),
+    };
+
+    let direction = match sort.direction {
+        SortDirection::Asc => 1,
+        SortDirection::Desc => -1,
+    };
+    let mut sort_doc = doc! { sort.field.as_str(): direction };
+    sort_doc.insert(TIE_BREAKER, direction);
+
+    // one more element tells whether there is a following page
+    let find_opts = FindOptions::builder()
+        .sort(sort_doc)
+        .skip(skip)
+        .limit(page_request.limit().saturating_add(1))
+        .build();
+    let cursor = collection.find(filter, find_opts).await?;
+    let mut data: Vec<T> = cursor.try_collect().await?;
+
+    let next_cursor = if data.len() as u64 > page_request.page_size {
+        data.truncate(page_request.page_size as usize);
+        data.last()
+            .map(|last| PageCursor::after(last, sort))
+            .transpose()?
+    } else {
+        None
+    };
+
+    Ok(PageResponse {
+        page_info: Pagination::new(page_request, data.len(), total),
+        data,
+        next_cursor,
+        truncated: false,
+    })
+}
+
+/// The CRUD operations shared by every `MongoEntity`.
+#[derive(Debug, Clone)]
+pub struct MongoRepository<T: MongoEntity> {
+    collection: Collection<T>,
+    // the fields set on all the entities of the repository, e.g. their tenant
+    discriminator: Document,
+}
+
+impl<T: MongoEntity> MongoRepository<T> {
+    pub fn new(db: &Database) -> Self {
+        Self::with_collection(db.collection::<T>(T::COLLECTION))
+    }
+
+    /// The entities of `collection` rather than of `T::COLLECTION`, e.g. the
+    /// collection of a tenant.
+    pub fn with_collection(collection: Collection<T>) -> Self {
+        MongoRepository {
+            collection,
+            discriminator: Document::new(),
+        }
+    }
+
+    /// Only the entities with `field` at `value`, e.g. the entities of a
+    /// tenant in a collection shared by the tenants. Every filter is
+    /// restricted to them, and the entities inserted get the value, which
+    /// updates leave unchanged.
+    pub fn with_discriminator(mut self, field: &str, value: impl Into<Bson>) -> Self {
+        self.discriminator.insert(field, value.into());
+        self
+    }
+
+    /// The underlying collection, for the queries specific to an entity. They
+    /// are not restricted by the discriminator.
+    pub fn collection(&self) -> &Collection<T> {
+        &self.collection
+    }
+
+    /// The document matching the fields set in `cond`.
+    pub fn filter(cond: &T) -> Result<Document, InternalError> {
+        Ok(bson::to_document(cond)?)
+    }
+
+    /// The document matching the fields set in `cond` and the conditions of
+    /// the filter expression of `filter_request`, see [`parse_filter`].
+    pub fn filter_with_expression(
+        cond: &T,
+        filter_request: &FilterRequest,
+    ) -> Result<Document, InternalError> {
+        let filter = Self::filter(cond)?;
+        match &filter_request.filter {
+            Some(expression) => Ok(and(filter, parse_filter(expression, T::FILTER_FIELDS)?)),
+            None => Ok(filter),
+        }
+    }
+
+    fn id_filter(id: &T::Id) -> Result<Document, InternalError> {
+        Ok(doc! { T::ID: bson::to_bson(id)? })
+    }
+
+    // `filter` restricted to the entities of the discriminator
+    fn scoped(&self, filter: Document) -> Document {
+        and(filter, self.discriminator.clone())
+    }
+
+    // `filter` restricted to the entities of the discriminator not soft
+    // deleted
+    fn live(&self, filter: Document) -> Document {
+        let filter = self.scoped(filter);
+        if T::SOFT_DELETE {
+            and(filter, doc! { DELETED_AT: null })
+        } else {
+            filter
+        }
+    }
+
+    pub async fn find_by_id(&self, id: &T::Id) -> Result<Option<T>, InternalError> {
+        let filter = self.live(Self::id_filter(id)?);
+        Ok(self.collection.find_one(filter, None).await?)
+    }
+
+    pub async fn find_one(&self, cond: &T) -> Result<Option<T>, InternalError> {
+        self.find_one_with_session(cond, None).await
+    }
+
+    /// [`Self::find_one`], in the transaction of `session` if any.
+    pub async fn find_one_with_session(
+        &self,
+        cond: &T,
+        session: Option<&mut ClientSession>,
+    ) -> Result<Option<T>, InternalError> {
+        let filter = self.live(Self::filter(cond)?);
+        Ok(match session {
+            Some(session) => {
+                self.collection
+                    .find_one_with_session(filter, None, session)
+                    .await?
+            }
+            None => self.collection.find_one(filter, None).await?,
+        })
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<T>, InternalError> {
+        let cursor = self.collection.find(self.live(doc! {}), None).await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    /// The entities matching the fields set in `cond`, ordered by id.
+    pub async fn find_all_with_query(&self, cond: &T) -> Result<Vec<T>, InternalError> {
+        self.find_all_matching(Self::filter(cond)?).await
+    }
+
+    /// The entities matching `filter`, ordered by id.
+    pub async fn find_all_matching(&self, filter: Document) -> Result<Vec<T>, InternalError> {
+        let find_opts = FindOptions::builder().sort(doc! { T::ID: 1 }).build();
+
+        let cursor = self.collection.find(self.live(filter), find_opts).await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    /// The page `page_request` of the entities matching the fields set in
+    /// `cond`, in the requested order among `T::SORT_FIELDS`, else by id.
+    pub async fn find_all_paginated_with_query(
+        &self,
+        cond: &T,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<T>, InternalError> {
+        self.find_paginated_matching(Self::filter(cond)?, page_request)
+            .await
+    }
+
+    /// The page `page_request` of the entities matching `filter`, in the
+    /// requested order among `T::SORT_FIELDS`, else by id.
+    pub async fn find_paginated_matching(
+        &self,
+        filter: Document,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<T>, InternalError> {
+        let sort = page_request.sort_order(T::SORT_FIELDS, SortOrder::asc(T::ID))?;
+        find_page(&self.collection, self.live(filter), &sort, page_request).await
+    }
+
+    /// The page `page_request` of the entities within `scope` matching the
+    /// search, most relevant first. Candidates come from the text index, which
+    /// ranks whole words, and from a match on word prefixes tolerating a typo.
+    /// They are ranked together on the quality and weight of their matches.
+    ///
+    /// Only the `MAX_SEARCH_CANDIDATES` best text matches and first fuzzy
+    /// matches by id are ranked. The fuzzy match cannot use an index, so it is
+    /// given up past `MAX_FUZZY_SEARCH_TIME`. Either way the response is
+    /// flagged `truncated`.
+    pub async fn search(
+        &self,
+        scope: Document,
+        search_request: &SearchRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<SearchHit<T>>, InternalError> {
+        let scope = self.live(scope);
+        let terms = search_terms(&search_request.q);
+        let documents = self.collection.clone_with_type::<Document>();
+        let mut candidates: HashMap<String, Document> = HashMap::new();
+        let mut truncated = false;
+
+        if !terms.is_empty() && !T::SEARCH_FIELDS.is_empty() {
+            let text_filter = and(
+                scope.clone(),
+                doc! { "$text": { "$search": search_request.q.as_str() } },
+            );
+            let find_opts = FindOptions::builder()
+                .projection(doc! { TEXT_SCORE: { "$meta": "textScore" } })
+                .sort(doc! { TEXT_SCORE: { "$meta": "textScore" } })
+                .limit(MAX_SEARCH_CANDIDATES)
+                .build();
+            let found: Vec<Document> = documents
+                .find(text_filter, find_opts)
+                .await?
+                .try_collect()
+                .await?;
+
+            let fuzzy_filter = and(scope, fuzzy_filter(&terms, T::SEARCH_FIELDS));
+            let find_opts = FindOptions::builder()
+                .sort(doc! { T::ID: 1 })
+                .limit(MAX_SEARCH_CANDIDATES)
+                .max_time(MAX_FUZZY_SEARCH_TIME)
+                .build();
+            let fuzzy_found: Result<Vec<Document>, mongodb::error::Error> = async {
+                documents
+                    .find(fuzzy_filter, find_opts)
+                    .await?
+                    .try_collect()
+                    .await
+            }
+            .await;
+            let fuzzy_found = match fuzzy_found {
+                Ok(fuzzy_found) => fuzzy_found,
+                Err(err) if max_time_expired(&err) => {
+                    truncated = true;
+                    vec![]
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            truncated |= found.len() as i64 == MAX_SEARCH_CANDIDATES
+                || fuzzy_found.len() as i64 == MAX_SEARCH_CANDIDATES;
+
+            for document in found.into_iter().chain(fuzzy_found) {
+                let id = document.get(T::ID).map(Bson::to_string).unwrap_or_default();
+                candidates.entry(id).or_insert(document);
+            }
+        }
+
+        let mut hits = candidates
+            .into_values()
+            .filter_map(|mut document| {
+                let text_score = document.remove(TEXT_SCORE).and_then(|score| score.as_f64());
+                let (score, highlights) =
+                    rank(&document, &terms, T::SEARCH_FIELDS).unwrap_or((0.0, Default::default()));
+                let score = score + text_score.unwrap_or(0.0);
+                if score > 0.0 {
+                    Some((document, score, highlights))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        hits.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let total = hits.len() as u64;
+        let data = hits
+            .into_iter()
+            .skip(usize::try_from(page_request.skip()).unwrap_or(usize::MAX))
+            .take(page_request.page_size as usize)
+            .map(|(document, score, highlights)| {
+                Ok(SearchHit {
+                    entity: bson::from_document(document)?,
+                    score,
+                    highlights,
+                })
+            })
+            .collect::<Result<Vec<_>, InternalError>>()?;
+
+        Ok(PageResponse {
+            page_info: Pagination::new(page_request, data.len(), total),
+            data,
+            next_cursor: None,
+            truncated,
+        })
+    }
+
+    /// Insert `entity` and return it along with the id it was stored with, at
+    /// version 1 when `T::VERSIONED` and created now when `T::TIMESTAMPED`.
+    pub async fn insert_one(&self, entity: &T) -> Result<T, InternalError> {
+        let mut document = bson::to_document(entity)?;
+        document.extend(self.discriminator.clone());
+        if T::VERSIONED {
+            document.insert(VERSION, 1_i64);
+        }
+        if T::TIMESTAMPED {
+            let now = bson::to_bson(&Utc::now())?;
+            document.insert(CREATED_AT, now.clone());
+            document.insert(UPDATED_AT, now);
+        }
+
+        let res = self
+            .collection
+            .clone_with_type::<Document>()
+            .insert_one(&document, None)
+            .await?;
+
+        let mut ret: T = bson::from_document(document)?;
+        ret.set_id(bson::from_bson(res.inserted_id)?);
+        Ok(ret)
+    }
+
+    // The `$set` of the fields of `entity` but its id, stamped as every write
+    fn update_document(&self, entity: &T) -> Result<Document, InternalError> {
+        let mut update = Self::filter(entity)?;
+        update.remove(T::ID);
+        for field in self.discriminator.keys() {
+            update.remove(field);
+        }
+        // deleting, restoring, versioning and timestamps go through their own
+        // operations
+        update.remove(DELETED_AT);
+        update.remove(VERSION);
+        if T::TIMESTAMPED {
+            update.remove(CREATED_AT);
+            update.remove(UPDATED_AT);
+        }
+        if update.is_empty() {
+            return Err(InternalError::DbUpdateEmpty);
+        }
+        Self::stamped(doc! { "$set": update })
+    }
+
+    // `update` bumping the version of the versioned entities and the update
+    // time of the timestamped ones
+    fn stamped(mut update: Document) -> Result<Document, InternalError> {
+        if T::VERSIONED {
+            update.insert("$inc", doc! { VERSION: 1_i64 });
+        }
+        if T::TIMESTAMPED {
+            let now = bson::to_bson(&Utc::now())?;
+            match update.get_document_mut("$set") {
+                Ok(set) => {
+                    set.insert(UPDATED_AT, now);
+                }
+                Err(_) => {
+                    update.insert("$set", doc! { UPDATED_AT: now });
+                }
+            }
+        }
+        Ok(update)
+    }
+
+    fn required_id(entity: &T) -> Result<&T::Id, InternalError> {
+        entity.id().ok_or(InternalError::RequestFormatError {
+            reason: format!("require fields: `{}`", T::ID),
+        })
+    }
+
+    /// Set the fields of `entity` on the stored entity with the same id.
+    pub async fn update_by_id(&self, entity: &T) -> Result<u64, InternalError> {
+        self.update_by_id_with_session(entity, None).await
+    }
+
+    /// [`Self::update_by_id`], in the transaction of `session` if any.
+    pub async fn update_by_id_with_session(
+        &self,
+        entity: &T,
+        session: Option<&mut ClientSession>,
+    ) -> Result<u64, InternalError> {
+        let filter = self.live(Self::id_filter(Self::required_id(entity)?)?);
+        let update = self.update_document(entity)?;
+        let res = match session {
+            Some(session) => {
+                self.collection
+                    .update_one_with_session(filter, update, None, session)
+                    .await?
+            }
+            None => self.collection.update_one(filter, update, None).await?,
+        };
+        Ok(res.modified_count)
+    }
+
+    /// Set the fields of `entity` on the stored entity with the same id,
+    /// provided it is still at `expected_version` when given, and return the
+    /// updated entity. `None` when there is no entity of this id, and a
+    /// `PreconditionFailed` error when it is at another version.
+    pub async fn update_by_id_checked(
+        &self,
+        entity: &T,
+        expected_version: Option<i64>,
+    ) -> Result<Option<T>, InternalError> {
+        let id = Self::required_id(entity)?;
+        let filter = self.version_filter(id, expected_version)?;
+        let find_opts = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let updated = self
+            .collection
+            .find_one_and_update(filter, self.update_document(entity)?, find_opts)
+            .await?;
+        match updated {
+            Some(updated) => Ok(Some(updated)),
+            None => self.check_version(id, expected_version).await.map(|_| None),
+        }
+    }
+
+    /// Delete the entity `id`, only marking it deleted when `T::SOFT_DELETE`.
+    pub async fn delete_by_id(&self, id: &T::Id) -> Result<u64, InternalError> {
+        self.delete_by_id_with_session(id, None).await
+    }
+
+    /// [`Self::delete_by_id`], in the transaction of `session` if any.
+    pub async fn delete_by_id_with_session(
+        &self,
+        id: &T::Id,
+        session: Option<&mut ClientSession>,
+    ) -> Result<u64, InternalError> {
+        let filter = self.live(Self::id_filter(id)?);
+        self.delete_matching(filter, session).await
+    }
+
+    /// Delete the entity `id` as [`Self::delete_by_id`] does, provided it is
+    /// still at `expected_version` when given. `false` when there is no entity
+    /// of this id, and a `PreconditionFailed` error when it is at another
+    /// version.
+    pub async fn delete_by_id_checked(
+        &self,
+        id: &T::Id,
+        expected_version: Option<i64>,
+    ) -> Result<bool, InternalError> {
+        self.delete_by_id_checked_with_session(id, expected_version, None)
+            .await
+    }
+
+    /// [`Self::delete_by_id_checked`], in the transaction of `session` if any.
+    pub async fn delete_by_id_checked_with_session(
+        &self,
+        id: &T::Id,
+        expected_version: Option<i64>,
+        session: Option<&mut ClientSession>,
+    ) -> Result<bool, InternalError> {
+        let filter = self.version_filter(id, expected_version)?;
+        if self.delete_matching(filter, session).await? > 0 {
+            return Ok(true);
+        }
+        self.check_version(id, expected_version).await?;
+        Ok(false)
+    }
+
+    // Delete, or mark deleted when `T::SOFT_DELETE`, the entity matching
+    // `filter`
+    async fn delete_matching(
+        &self,
+        filter: Document,
+        session: Option<&mut ClientSession>,
+    ) -> Result<u64, InternalError> {
+        if !T::SOFT_DELETE {
+            return self.delete_many_with_session(filter, session).await;
+        }
+
+        let update = Self::stamped(doc! { "$set": { DELETED_AT: bson::to_bson(&Utc::now())? } })?;
+        let res = match session {
+            Some(session) => {
+                self.collection
+                    .update_one_with_session(filter, update, None, session)
+                    .await?
+            }
+            None => self.collection.update_one(filter, update, None).await?,
+        };
+        Ok(res.modified_count)
+    }
+
+    // The live entity `id`, at `expected_version` when given
+    fn version_filter(
+        &self,
+        id: &T::Id,
+        expected_version: Option<i64>,
+    ) -> Result<Document, InternalError> {
+        let mut filter = Self::id_filter(id)?;
+        if let Some(expected_version) = expected_version {
+            filter.insert(VERSION, expected_version);
+        }
+        Ok(self.live(filter))
+    }
+
+    // After a conditional write matched nothing, fail if the entity `id` is
+    // there at another version than expected, else tell whether it exists.
+    async fn check_version(
+        &self,
+        id: &T::Id,
+        expected_version: Option<i64>,
+    ) -> Result<bool, InternalError> {
+        let current = self.find_by_id(id).await?;
+        match (current, expected_version) {
+            (Some(current), Some(expected_version)) => Err(InternalError::PreconditionFailed {
+                cause: format!(
+                    "expected version {}, found {}",
+                    expected_version,
+                    current.version().unwrap_or_default()
+                ),
+            }),
+            (current, _) => Ok(current.is_some()),
+        }
+    }
+
+    /// Bring the soft deleted entity `id` back, returning whether there was
+    /// one to restore.
+    pub async fn restore_by_id(&self, id: &T::Id) -> Result<bool, InternalError> {
+        let filter = self.scoped(and(
+            Self::id_filter(id)?,
+            doc! { DELETED_AT: { "$ne": null } },
+        ));
+        let update = Self::stamped(doc! { "$unset": { DELETED_AT: "" } })?;
+        let res = self.collection.update_one(filter, update, None).await?;
+        Ok(res.modified_count > 0)
+    }
+
+    /// Up to `limit` of the entities soft deleted before `deleted_before`,
+    /// oldest deletion first.
+    pub async fn find_deleted_before(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<T>, InternalError> {
+        let filter = self.scoped(doc! { DELETED_AT: { "$lt": bson::to_bson(&deleted_before)? } });
+        let find_opts = FindOptions::builder()
+            .sort(doc! { DELETED_AT: 1 })
+            .limit(limit)
+            .build();
+        let cursor = self.collection.find(filter, find_opts).await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    /// Permanently delete the entity `id` if it is still soft deleted since
+    /// before `deleted_before`, in the transaction of `session` if any. An
+    /// entity restored in the meantime is kept.
+    pub async fn purge_by_id_with_session(
+        &self,
+        id: &T::Id,
+        deleted_before: DateTime<Utc>,
+        session: Option<&mut ClientSession>,
+    ) -> Result<u64, InternalError> {
+        let filter = and(
+            Self::id_filter(id)?,
+            doc! { DELETED_AT: { "$lt": bson::to_bson(&deleted_before)? } },
+        );
+        self.delete_many_with_session(filter, session).await
+    }
+
+    /// Delete the entities matching `filter`, in the transaction of `session`
+    /// if any.
+    pub async fn delete_many_with_session(
+        &self,
+        filter: Document,
+        session: Option<&mut ClientSession>,
+    ) -> Result<u64, InternalError> {
+        let filter = self.scoped(filter);
+        let res = match session {
+            Some(session) => {
+                self.collection
+                    .delete_many_with_session(filter, None, session)
+                    .await?
+            }
+            None => self.collection.delete_many(filter, None).await?,
+        };
+        Ok(res.deleted_count)
+    }
+
+    /// Create the indexes backing the sorted listings, one per sort field with
+    /// the id as tie breaker.
+    pub async fn create_sort_indexes(&self) -> Result<(), InternalError> {
+        if T::SORT_FIELDS.is_empty() {
+            return Ok(());
+        }
+        let indexes = T::SORT_FIELDS.iter().map(|field| {
+            let mut keys = doc! { *field: 1 };
+            keys.insert(T::ID, 1);
+            IndexModel::builder().keys(keys).build()
+        });
+        self.collection.create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    /// Create the text index of the search fields, weighted by field.
+    pub async fn create_search_index(&self) -> Result<(), InternalError> {
+        if T::SEARCH_FIELDS.is_empty() {
+            return Ok(());
+        }
+        let mut keys = Document::new();
+        let mut weights = Document::new();
+        for field in T::SEARCH_FIELDS {
+            keys.insert(field.name, "text");
+            weights.insert(field.name, field.weight);
+        }
+        // names and emails are not words of a language, so no stemming
+        let options = IndexOptions::builder()
+            .name("search".to_string())
+            .weights(weights)
+            .default_language("none".to_string())
+            .build();
+
+        let index = IndexModel::builder().keys(keys).options(options).build();
+        self.collection.create_index(index, None).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Element {
+        #[serde(rename = "_id")]
+        id: bson::Uuid,
+        #[serde(rename = "EMAIL")]
+        email: String,
+    }
+
+    fn element(id: bson::Uuid) -> Element {
+        Element {
+            id,
+            email: "a@b.c".to_string(),
+        }
+    }
+
+    #[test]
+    fn cursors_resume_after_their_element() {
+        let id = bson::Uuid::new();
+        let sort = SortOrder::asc("EMAIL");
+        let cursor = PageCursor::after(&element(id), &sort).unwrap();
+
+        assert_eq!(
+            PageCursor::filter(&cursor, &sort).unwrap(),
+            doc! {
+                "$or": [
+                    { "EMAIL": { "$gt": "a@b.c" } },
+                    { "EMAIL": "a@b.c", "_id": { "$gt": id } },
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn cursors_on_ids_resume_after_the_id() {
+        let id = bson::Uuid::new();
+        let sort = SortOrder::desc(TIE_BREAKER);
+        let cursor = PageCursor::after(&element(id), &sort).unwrap();
+
+        assert_eq!(
+            PageCursor::filter(&cursor, &sort).unwrap(),
+            doc! { "_id": { "$lt": id } }
+        );
+    }
+
+    #[test]
+    fn rejects_cursors_of_other_orders() {
+        let cursor =
+            PageCursor::after(&element(bson::Uuid::new()), &SortOrder::asc("EMAIL")).unwrap();
+
+        assert!(PageCursor::filter(&cursor, &SortOrder::desc("EMAIL")).is_err());
+        assert!(PageCursor::filter("not a cursor", &SortOrder::asc("EMAIL")).is_err());
+    }
+}
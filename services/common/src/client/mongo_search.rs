@@ -0,0 +1,300 @@
+use super::mongo_filter::escape_regex;
+use mongodb::bson::{doc, Bson, Document};
+use std::{cmp::Ordering, collections::BTreeMap, ops::Range};
+
+/// Name of the text score projected on the documents found by `$text`.
+pub(crate) const TEXT_SCORE: &str = "_text_score";
+
+// Highlighted words are wrapped in these, the rest of the value is escaped
+const HIGHLIGHT_START: &str = "<em>";
+const HIGHLIGHT_END: &str = "</em>";
+
+// Terms shorter than this only match exactly or as a prefix
+const MIN_FUZZY_TERM_LENGTH: usize = 4;
+const MAX_TERMS: usize = 8;
+
+/// A field searched by free text. Matches on fields of higher weight rank
+/// first.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchField {
+    pub name: &'static str,
+    pub weight: u32,
+}
+
+impl SearchField {
+    pub const fn new(name: &'static str, weight: u32) -> Self {
+        SearchField { name, weight }
+    }
+}
+
+/// The lowercased words of a search query, split as the searched values are.
+pub(crate) fn search_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+    {
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms.truncate(MAX_TERMS);
+    terms
+}
+
+/// The filter of the documents holding, at the start of a word of one of the
+/// `fields`, one of the `terms` or a spelling of it one edit away.
+pub(crate) fn fuzzy_filter(terms: &[String], fields: &[SearchField]) -> Document {
+    let pattern = fuzzy_pattern(terms);
+    let conditions = fields
+        .iter()
+        .map(|field| doc! { field.name: { "$regex": pattern.as_str(), "$options": "i" } })
+        .collect::<Vec<_>>();
+    doc! { "$or": conditions }
+}
+
+/// The case insensitive regex of [`fuzzy_filter`], also understood by the
+/// POSIX regexes of Postgres.
+pub(crate) fn fuzzy_pattern(terms: &[String]) -> String {
+    let alternatives = terms
+        .iter()
+        .flat_map(|term| term_patterns(term))
+        .collect::<Vec<_>>()
+        .join("|");
+    format!(r"(^|[\s@._-])({alternatives})")
+}
+
+// The term and, when long enough, every spelling one deletion, insertion,
+// substitution or transposition away from it.
+fn term_patterns(term: &str) -> Vec<String> {
+    let chars: Vec<char> = term.chars().collect();
+    let literal = |chars: &[char]| escape_regex(&chars.iter().collect::<String>());
+    let mut patterns = vec![literal(&chars)];
+    if chars.len() < MIN_FUZZY_TERM_LENGTH {
+        return patterns;
+    }
+
+    for i in 0..chars.len() {
+        let (before, after) = (literal(&chars[..i]), literal(&chars[i + 1..]));
+        patterns.push(format!("{before}{after}"));
+        patterns.push(format!("{before}.{after}"));
+        patterns.push(format!("{before}.{}", literal(&chars[i..])));
+        if i + 1 < chars.len() {
+            let mut swapped = chars.clone();
+            swapped.swap(i, i + 1);
+            patterns.push(literal(&swapped));
+        }
+    }
+    patterns.push(format!("{}.", literal(&chars)));
+    patterns.sort();
+    patterns.dedup();
+    patterns
+}
+
+/// The relevance of `document` to the search `terms` and the highlighted
+/// values of its matching fields, `None` when no term matches.
+pub(crate) fn rank(
+    document: &Document,
+    terms: &[String],
+    fields: &[SearchField],
+) -> Option<(f64, BTreeMap<String, String>)> {
+    let mut score = 0.0;
+    let mut matches: BTreeMap<&str, Vec<Range<usize>>> = BTreeMap::new();
+
+    for term in terms {
+        let best = fields
+            .iter()
+            .filter_map(|field| {
+                let value = match document.get(field.name) {
+                    Some(Bson::String(value)) => value,
+                    _ => return None,
+                };
+                words(value)
+                    .filter_map(|word| {
+                        let quality = match_quality(term, &value[word.clone()].to_lowercase())?;
+                        Some((quality * f64::from(field.weight), field.name, word))
+                    })
+                    .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+            })
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        if let Some((term_score, field, word)) = best {
+            score += term_score;
+            matches.entry(field).or_default().push(word);
+        }
+    }
+    if matches.is_empty() {
+        return None;
+    }
+
+    let highlights = matches
+        .into_iter()
+        .filter_map(|(field, words)| match document.get(field) {
+            Some(Bson::String(value)) => Some((field.to_string(), highlight(value, words))),
+            _ => None,
+        })
+        .collect();
+    Some((score, highlights))
+}
+
+// The byte ranges of the words of `value`
+fn words(value: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = None;
+    value
+        .char_indices()
+        .chain(std::iter::once((value.len(), ' ')))
+        .filter_map(move |(i, c)| match (start, c.is_alphanumeric()) {
+            (None, true) => {
+                start = Some(i);
+                None
+            }
+            (Some(s), false) => {
+                start = None;
+                Some(s..i)
+            }
+            _ => None,
+        })
+}
+
+// How well `term` matches `word`: fully, as a prefix, or with a typo
+fn match_quality(term: &str, word: &str) -> Option<f64> {
+    if word == term {
+        return Some(1.0);
+    }
+    if word.starts_with(term) {
+        return Some(0.75);
+    }
+    if term.chars().count() < MIN_FUZZY_TERM_LENGTH {
+        return None;
+    }
+    // a typo in the whole word, or in the part of it the term stands for
+    let term_length = term.chars().count();
+    let typo = (term_length - 1..=term_length + 1)
+        .map(|length| word.chars().take(length).collect::<String>())
+        .any(|prefix| edit_distance(term, &prefix) <= 1);
+    if typo {
+        Some(0.5)
+    } else {
+        None
+    }
+}
+
+// Optimal string alignment distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+fn highlight(value: &str, mut words: Vec<Range<usize>>) -> String {
+    words.sort_by_key(|word| word.start);
+    words.dedup();
+
+    let mut highlighted = String::with_capacity(value.len() + words.len() * 9);
+    let mut position = 0;
+    for word in words {
+        highlighted.push_str(&escape_html(&value[position..word.start]));
+        highlighted.push_str(HIGHLIGHT_START);
+        highlighted.push_str(&escape_html(&value[word.clone()]));
+        highlighted.push_str(HIGHLIGHT_END);
+        position = word.end;
+    }
+    highlighted.push_str(&escape_html(&value[position..]));
+    highlighted
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[SearchField] = &[SearchField::new("name", 2), SearchField::new("email", 1)];
+
+    fn terms(query: &str) -> Vec<String> {
+        search_terms(query)
+    }
+
+    #[test]
+    fn counts_single_edits_and_transpositions() {
+        assert_eq!(edit_distance("smith", "smith"), 0);
+        assert_eq!(edit_distance("smith", "smth"), 1);
+        assert_eq!(edit_distance("smith", "smiths"), 1);
+        assert_eq!(edit_distance("smith", "smyth"), 1);
+        assert_eq!(edit_distance("smith", "msith"), 1);
+        assert_eq!(edit_distance("smith", "jones"), 5);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn ranks_exact_matches_above_prefixes_and_typos() {
+        let exact = doc! { "name": "John Smith" };
+        let prefix = doc! { "name": "John Smithers" };
+        let typo = doc! { "name": "John Smyth" };
+
+        let score = |document: &Document| rank(document, &terms("smith"), FIELDS).unwrap().0;
+        assert!(score(&exact) > score(&prefix));
+        assert!(score(&prefix) > score(&typo));
+    }
+
+    #[test]
+    fn ranks_matches_on_heavier_fields_first() {
+        let on_name = doc! { "name": "Ada", "email": "someone@example.com" };
+        let on_email = doc! { "name": "Someone", "email": "ada@example.com" };
+
+        let score = |document: &Document| rank(document, &terms("ada"), FIELDS).unwrap().0;
+        assert!(score(&on_name) > score(&on_email));
+    }
+
+    #[test]
+    fn does_not_rank_documents_without_matches() {
+        let document = doc! { "name": "John Smith", "email": "john@example.com" };
+
+        assert!(rank(&document, &terms("jones"), FIELDS).is_none());
+        // short terms do not tolerate typos
+        assert!(rank(&document, &terms("jon"), FIELDS).is_none());
+    }
+
+    #[test]
+    fn highlights_the_matching_words() {
+        let document = doc! { "name": "John Smith", "email": "john@example.com" };
+
+        let (_, highlights) = rank(&document, &terms("john smyth"), FIELDS).unwrap();
+        assert_eq!(highlights["name"], "<em>John</em> <em>Smith</em>");
+        assert!(!highlights.contains_key("email"));
+    }
+
+    #[test]
+    fn escapes_the_highlighted_values() {
+        let highlighted = highlight("<b>Tom</b> & Jerry", vec![13..18, 3..6, 3..6]);
+
+        assert_eq!(
+            highlighted,
+            "&lt;b&gt;<em>Tom</em>&lt;/b&gt; &amp; <em>Jerry</em>"
+        );
+    }
+}
@@ -0,0 +1,985 @@
+use super::{
+    db_postgres::DatabasePool,
+    mongo_filter::{parse_conditions, FilterCondition, FilterOperator},
+    mongo_repository::{MongoEntity, CREATED_AT, DELETED_AT, UPDATED_AT, VERSION},
+    mongo_search::{fuzzy_pattern, rank, search_terms},
+};
+use crate::{
+    error::InternalError,
+    model::{
+        domain::pagination::Pagination,
+        request::{
+            filter_request::FilterRequest,
+            page_request::{PageRequest, SortDirection, SortOrder},
+            search_request::SearchRequest,
+        },
+        response::{page_response::PageResponse, search_hit::SearchHit},
+    },
+};
+use base64::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, Bson};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{postgres::PgArguments, Arguments};
+use std::{fmt::Write, marker::PhantomData};
+
+// Bound on the rows ranked by a search
+const MAX_SEARCH_CANDIDATES: i64 = 500;
+
+/// The type of a column, the values of its field are bound as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// `TEXT`, also holding the enums by their serialized name.
+    Text,
+    Uuid,
+    TimestampTz,
+    BigInt,
+}
+
+/// The column a field of an entity is stored in.
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub field: &'static str,
+    pub name: &'static str,
+    pub column_type: ColumnType,
+}
+
+impl Column {
+    pub const fn new(field: &'static str, name: &'static str, column_type: ColumnType) -> Self {
+        Column {
+            field,
+            name,
+            column_type,
+        }
+    }
+}
+
+/// An entity stored in a table of its own, a column per field.
+///
+/// The entity is otherwise described by its [`MongoEntity`] implementation:
+/// its id, sort, filter and search fields, and whether it is soft deleted,
+/// versioned and timestamped, so that both stores behave alike.
+pub trait PostgresEntity: MongoEntity {
+    const TABLE: &'static str;
+    /// The columns of every field the entity serializes.
+    const COLUMNS: &'static [Column];
+}
+
+/// A value bound to a query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Text(String),
+    Uuid(uuid::Uuid),
+    TimestampTz(DateTime<Utc>),
+    BigInt(i64),
+}
+
+impl SqlValue {
+    /// The value of `column` serialized as `json`, `None` when null.
+    fn from_json(column: &Column, json: &Value) -> Result<Option<Self>, InternalError> {
+        let invalid = || InternalError::InvalidFormatError {
+            cause: format!("invalid value of `{}`: `{json}`", column.field),
+        };
+        if json.is_null() {
+            return Ok(None);
+        }
+
+        let value = match column.column_type {
+            ColumnType::Text => SqlValue::Text(json.as_str().ok_or_else(invalid)?.to_string()),
+            ColumnType::Uuid => {
+                let uuid = json.as_str().ok_or_else(invalid)?;
+                SqlValue::Uuid(uuid::Uuid::parse_str(uuid).map_err(|_| invalid())?)
+            }
+            ColumnType::TimestampTz => {
+                let date_time = json.as_str().ok_or_else(invalid)?;
+                let date_time = DateTime::parse_from_rfc3339(date_time).map_err(|_| invalid())?;
+                SqlValue::TimestampTz(date_time.with_timezone(&Utc))
+            }
+            ColumnType::BigInt => SqlValue::BigInt(json.as_i64().ok_or_else(invalid)?),
+        };
+        Ok(Some(value))
+    }
+
+    /// The value of `column` parsed from a filter expression.
+    fn from_bson(column: &Column, value: &Bson) -> Result<Self, InternalError> {
+        let value = match (column.column_type, value) {
+            (ColumnType::Text, Bson::String(value)) => SqlValue::Text(value.clone()),
+            (ColumnType::Uuid, Bson::Binary(_)) => {
+                let uuid: bson::Uuid = bson::from_bson(value.clone())?;
+                SqlValue::Uuid(uuid.to_uuid_0_8())
+            }
+            (ColumnType::TimestampTz, Bson::DateTime(date_time)) => {
+                SqlValue::TimestampTz(date_time.to_chrono())
+            }
+            // the date times of the filter expressions, as chrono serializes them
+            (ColumnType::TimestampTz, Bson::String(date_time)) => {
+                let date_time = DateTime::parse_from_rfc3339(date_time).map_err(|_| {
+                    InternalError::InvalidFormatError {
+                        cause: format!("invalid value of `{}`: `{value}`", column.field),
+                    }
+                })?;
+                SqlValue::TimestampTz(date_time.with_timezone(&Utc))
+            }
+            (ColumnType::BigInt, Bson::Int64(value)) => SqlValue::BigInt(*value),
+            _ => {
+                return Err(InternalError::InvalidFormatError {
+                    cause: format!("invalid value of `{}`: `{value}`", column.field),
+                })
+            }
+        };
+        Ok(value)
+    }
+
+    fn add_to(self, arguments: &mut PgArguments) {
+        match self {
+            SqlValue::Text(value) => arguments.add(value),
+            SqlValue::Uuid(value) => arguments.add(value),
+            SqlValue::TimestampTz(value) => arguments.add(value),
+            SqlValue::BigInt(value) => arguments.add(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum SqlPart {
+    Sql(String),
+    Value(SqlValue),
+}
+
+/// The rows matching all of a list of conditions on the columns of an entity.
+#[derive(Debug, Clone, Default)]
+pub struct SqlFilter {
+    conditions: Vec<Vec<SqlPart>>,
+}
+
+impl SqlFilter {
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// The rows matching both this filter and `other`.
+    pub fn and(mut self, other: SqlFilter) -> Self {
+        self.conditions.extend(other.conditions);
+        self
+    }
+
+    fn with(mut self, condition: Vec<SqlPart>) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+}
+
+/// A statement and the values of its `$n` parameters.
+#[derive(Debug, Default)]
+struct SqlQuery {
+    sql: String,
+    values: Vec<SqlValue>,
+}
+
+impl SqlQuery {
+    fn new(sql: impl Into<String>) -> Self {
+        SqlQuery {
+            sql: sql.into(),
+            values: vec![],
+        }
+    }
+
+    fn push(&mut self, sql: &str) -> &mut Self {
+        self.sql.push_str(sql);
+        self
+    }
+
+    fn bind(&mut self, value: SqlValue) -> &mut Self {
+        self.values.push(value);
+        let _ = write!(self.sql, "${}", self.values.len());
+        self
+    }
+
+    fn push_filter(&mut self, filter: &SqlFilter) -> &mut Self {
+        for (i, condition) in filter.conditions.iter().enumerate() {
+            self.push(if i == 0 { " WHERE (" } else { " AND (" });
+            for part in condition {
+                match part {
+                    SqlPart::Sql(sql) => self.push(sql),
+                    SqlPart::Value(value) => self.bind(value.clone()),
+                };
+            }
+            self.push(")");
+        }
+        self
+    }
+
+    fn arguments(self) -> (String, PgArguments) {
+        let mut arguments = PgArguments::default();
+        for value in self.values {
+            value.add_to(&mut arguments);
+        }
+        (self.sql, arguments)
+    }
+}
+
+/// Escape `value` to match it literally in a `LIKE` pattern.
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\%_".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Position in a listing, after the row with these sort and id values, as
+/// the entity serializes them.
+#[derive(Debug, Serialize, Deserialize)]
+struct PageCursor {
+    sort: String,
+    value: Value,
+    id: Value,
+}
+
+/// The CRUD operations shared by every `PostgresEntity`, with the semantics
+/// of their `MongoRepository` counterparts. Rows are read as the JSON of the
+/// entity, their columns renamed to its fields.
+#[derive(Debug, Clone)]
+pub struct PostgresRepository<T: PostgresEntity> {
+    pool: DatabasePool,
+    // the columns set on all the rows of the repository, e.g. their tenant
+    discriminator: Vec<(&'static Column, SqlValue)>,
+    entity: PhantomData<T>,
+}
+
+impl<T: PostgresEntity> PostgresRepository<T> {
+    pub fn new(pool: &DatabasePool) -> Self {
+        PostgresRepository {
+            pool: pool.clone(),
+            discriminator: vec![],
+            entity: PhantomData,
+        }
+    }
+
+    /// Only the rows whose field `field` is `value`, as
+    /// [`super::mongo_repository::MongoRepository::with_discriminator`].
+    pub fn with_discriminator(mut self, field: &str, value: &Value) -> Result<Self, InternalError> {
+        let column = Self::column(field)?;
+        let value = SqlValue::from_json(column, value)?.ok_or_else(|| {
+            InternalError::InvalidFormatError {
+                cause: format!("null discriminator `{field}`"),
+            }
+        })?;
+        self.discriminator.push((column, value));
+        Ok(self)
+    }
+
+    /// The underlying pool, for the queries specific to an entity.
+    pub fn pool(&self) -> &DatabasePool {
+        &self.pool
+    }
+
+    /// The column of the field `field`.
+    pub fn column(field: &str) -> Result<&'static Column, InternalError> {
+        T::COLUMNS
+            .iter()
+            .find(|column| column.field == field)
+            .ok_or_else(|| InternalError::DbError {
+                cause: format!("no column for `{field}` in `{}`", T::TABLE),
+            })
+    }
+
+    // The fields set on `entity`, as serialized
+    fn fields(entity: &T) -> Result<Map<String, Value>, InternalError> {
+        match serde_json::to_value(entity)? {
+            Value::Object(fields) => Ok(fields),
+            _ => Err(InternalError::InvalidFormatError {
+                cause: format!("`{}` entities are not serialized as objects", T::TABLE),
+            }),
+        }
+    }
+
+    // The entity of a row read as JSON
+    fn entity(row: &str) -> Result<T, InternalError> {
+        let columns: Map<String, Value> = serde_json::from_str(row)?;
+        let fields = columns
+            .into_iter()
+            .filter(|(_, value)| !value.is_null())
+            .filter_map(|(name, value)| {
+                T::COLUMNS
+                    .iter()
+                    .find(|column| column.name == name)
+                    .map(|column| (column.field.to_string(), value))
+            })
+            .collect::<Map<_, _>>();
+        Ok(serde_json::from_value(Value::Object(fields))?)
+    }
+
+    fn entities(rows: Vec<String>) -> Result<Vec<T>, InternalError> {
+        rows.iter().map(|row| Self::entity(row)).collect()
+    }
+
+    /// The rows whose field `field` equals `value`.
+    pub fn field_filter(field: &str, value: &Value) -> Result<SqlFilter, InternalError> {
+        let column = Self::column(field)?;
+        let condition = match SqlValue::from_json(column, value)? {
+            Some(value) => vec![
+                SqlPart::Sql(format!("{} = ", column.name)),
+                SqlPart::Value(value),
+            ],
+            None => vec![SqlPart::Sql(format!("{} IS NULL", column.name))],
+        };
+        Ok(SqlFilter::default().with(condition))
+    }
+
+    /// The rows matching the fields set in `cond`.
+    pub fn filter(cond: &T) -> Result<SqlFilter, InternalError> {
+        Self::fields(cond)?
+            .iter()
+            .try_fold(SqlFilter::default(), |filter, (field, value)| {
+                Ok(filter.and(Self::field_filter(field, value)?))
+            })
+    }
+
+    /// The rows matching the fields set in `cond` and the conditions of the
+    /// filter expression of `filter_request`, see
+    /// [`super::mongo_filter::parse_filter`].
+    pub fn filter_with_expression(
+        cond: &T,
+        filter_request: &FilterRequest,
+    ) -> Result<SqlFilter, InternalError> {
+        let filter = Self::filter(cond)?;
+        match &filter_request.filter {
+            Some(expression) => parse_conditions(expression, T::FILTER_FIELDS)?
+                .into_iter()
+                .try_fold(filter, |filter, condition| {
+                    Ok(filter.with(Self::condition(condition)?))
+                }),
+            None => Ok(filter),
+        }
+    }
+
+    // The SQL of a condition of a filter expression. As in Mongo, `ne` and
+    // `nin` also match the rows where the field is not set.
+    fn condition(condition: FilterCondition) -> Result<Vec<SqlPart>, InternalError> {
+        let column = Self::column(condition.field.name)?;
+        let name = column.name;
+        let comparison = |operator: &str| -> Result<Vec<SqlPart>, InternalError> {
+            let value = condition.values.first().unwrap_or(&Bson::Null);
+            Ok(vec![
+                SqlPart::Sql(format!("{name} {operator} ")),
+                SqlPart::Value(SqlValue::from_bson(column, value)?),
+            ])
+        };
+        let list = |start: String| -> Result<Vec<SqlPart>, InternalError> {
+            let mut parts = vec![SqlPart::Sql(start)];
+            for (i, value) in condition.values.iter().enumerate() {
+                if i > 0 {
+                    parts.push(SqlPart::Sql(", ".to_string()));
+                }
+                parts.push(SqlPart::Value(SqlValue::from_bson(column, value)?));
+            }
+            parts.push(SqlPart::Sql(")".to_string()));
+            Ok(parts)
+        };
+
+        match condition.operator {
+            FilterOperator::Eq => comparison("="),
+            FilterOperator::Ne => comparison("IS DISTINCT FROM"),
+            FilterOperator::Gt => comparison(">"),
+            FilterOperator::Gte => comparison(">="),
+            FilterOperator::Lt => comparison("<"),
+            FilterOperator::Lte => comparison("<="),
+            FilterOperator::In => list(format!("{name} IN (")),
+            FilterOperator::Nin => {
+                let mut parts = list(format!("{name} IS NULL OR {name} NOT IN ("))?;
+                parts.insert(0, SqlPart::Sql("(".to_string()));
+                parts.push(SqlPart::Sql(")".to_string()));
+                Ok(parts)
+            }
+            FilterOperator::Prefix => {
+                let prefix = condition
+                    .values
+                    .first()
+                    .and_then(Bson::as_str)
+                    .unwrap_or_default();
+                Ok(vec![
+                    SqlPart::Sql(format!("{name} LIKE ")),
+                    SqlPart::Value(SqlValue::Text(format!("{}%", escape_like(prefix)))),
+                ])
+            }
+            FilterOperator::Exists => {
+                let exists = condition
+                    .values
+                    .first()
+                    .and_then(Bson::as_bool)
+                    .unwrap_or(true);
+                let test = if exists { "IS NOT NULL" } else { "IS NULL" };
+                Ok(vec![SqlPart::Sql(format!("{name} {test}"))])
+            }
+        }
+    }
+
+    fn id_filter(id: &T::Id) -> Result<SqlFilter, InternalError> {
+        Self::field_filter(T::ID, &serde_json::to_value(id)?)
+    }
+
+    // `filter` restricted to the rows of the discriminator
+    fn scoped(&self, filter: SqlFilter) -> SqlFilter {
+        self.discriminator
+            .iter()
+            .fold(filter, |filter, (column, value)| {
+                filter.with(vec![
+                    SqlPart::Sql(format!("{} = ", column.name)),
+                    SqlPart::Value(value.clone()),
+                ])
+            })
+    }
+
+    // `filter` restricted to the rows of the discriminator not soft deleted
+    fn live(&self, filter: SqlFilter) -> Result<SqlFilter, InternalError> {
+        let filter = self.scoped(filter);
+        if !T::SOFT_DELETE {
+            return Ok(filter);
+        }
+        let deleted_at = Self::column(DELETED_AT)?;
+        Ok(filter.with(vec![SqlPart::Sql(format!("{} IS NULL", deleted_at.name))]))
+    }
+
+    fn select(filter: &SqlFilter) -> SqlQuery {
+        let mut query = SqlQuery::new(format!(
+            "SELECT row_to_json(t)::text FROM {} AS t",
+            T::TABLE
+        ));
+        query.push_filter(filter);
+        query
+    }
+
+    async fn fetch_optional(&self, query: SqlQuery) -> Result<Option<T>, InternalError> {
+        let (sql, arguments) = query.arguments();
+        let row: Option<String> = sqlx::query_scalar_with(&sql, arguments)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_deref().map(Self::entity).transpose()
+    }
+
+    async fn fetch_all(&self, query: SqlQuery) -> Result<Vec<T>, InternalError> {
+        let (sql, arguments) = query.arguments();
+        let rows: Vec<String> = sqlx::query_scalar_with(&sql, arguments)
+            .fetch_all(&self.pool)
+            .await?;
+        Self::entities(rows)
+    }
+
+    async fn execute(&self, query: SqlQuery) -> Result<u64, InternalError> {
+        let (sql, arguments) = query.arguments();
+        let res = sqlx::query_with(&sql, arguments)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+
+    pub async fn find_by_id(&self, id: &T::Id) -> Result<Option<T>, InternalError> {
+        let filter = self.live(Self::id_filter(id)?)?;
+        self.fetch_optional(Self::select(&filter)).await
+    }
+
+    /// The first entity matching `filter`, by id.
+    pub async fn find_one_matching(&self, filter: SqlFilter) -> Result<Option<T>, InternalError> {
+        let mut query = Self::select(&self.live(filter)?);
+        query.push(&format!(" ORDER BY {} LIMIT 1", Self::column(T::ID)?.name));
+        self.fetch_optional(query).await
+    }
+
+    /// The entities matching `filter`, ordered by id.
+    pub async fn find_all_matching(&self, filter: SqlFilter) -> Result<Vec<T>, InternalError> {
+        let mut query = Self::select(&self.live(filter)?);
+        query.push(&format!(" ORDER BY {}", Self::column(T::ID)?.name));
+        self.fetch_all(query).await
+    }
+
+    /// The page `page_request` of the entities matching `filter`, in the
+    /// requested order among `T::SORT_FIELDS`, else by id. The page starts
+    /// after `page_request.cursor` when given, else at `page_request.page`.
+    pub async fn find_paginated_matching(
+        &self,
+        filter: SqlFilter,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<T>, InternalError> {
+        let sort = page_request.sort_order(T::SORT_FIELDS, SortOrder::asc(T::ID))?;
+        let filter = self.live(filter)?;
+
+        let mut count = SqlQuery::new(format!("SELECT count(*) FROM {} AS t", T::TABLE));
+        count.push_filter(&filter);
+        let (sql, arguments) = count.arguments();
+        let total: i64 = sqlx::query_scalar_with(&sql, arguments)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let (filter, offset) = match &page_request.cursor {
+            Some(cursor) => (filter.with(Self::cursor_condition(cursor, &sort)?), 0),
+            None => (filter, i64::try_from(page_request.skip())?),
+        };
+
+        let direction = match sort.direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        let sort_column = Self::column(&sort.field)?.name;
+        let id_column = Self::column(T::ID)?.name;
+        let mut query = Self::select(&filter);
+        query.push(&format!(
+            " ORDER BY {sort_column} {direction}, {id_column} {direction} LIMIT "
+        ));
+        // one more row tells whether there is a following page
+        query
+            .bind(SqlValue::BigInt(page_request.limit().saturating_add(1)))
+            .push(" OFFSET ")
+            .bind(SqlValue::BigInt(offset));
+        let mut data = self.fetch_all(query).await?;
+
+        let next_cursor = if data.len() as u64 > page_request.page_size {
+            data.truncate(page_request.page_size as usize);
+            data.last()
+                .map(|last| Self::cursor_after(last, &sort))
+                .transpose()?
+        } else {
+            None
+        };
+
+        Ok(PageResponse {
+            page_info: Pagination::new(page_request, data.len(), u64::try_from(total)?),
+            data,
+            next_cursor,
+            truncated: false,
+        })
+    }
+
+    fn cursor_after(element: &T, sort: &SortOrder) -> Result<String, InternalError> {
+        let fields = Self::fields(element)?;
+        let cursor = PageCursor {
+            sort: sort.to_string(),
+            value: fields.get(&sort.field).cloned().unwrap_or(Value::Null),
+            id: fields.get(T::ID).cloned().unwrap_or(Value::Null),
+        };
+        Ok(base64::encode_config(
+            serde_json::to_vec(&cursor)?,
+            URL_SAFE_NO_PAD,
+        ))
+    }
+
+    // The condition of the rows following the cursor `token`
+    fn cursor_condition(token: &str, sort: &SortOrder) -> Result<Vec<SqlPart>, InternalError> {
+        let invalid = || InternalError::RequestFormatError {
+            reason: "invalid cursor".to_string(),
+        };
+        let cursor = base64::decode_config(token, URL_SAFE_NO_PAD).map_err(|_| invalid())?;
+        let cursor: PageCursor = serde_json::from_slice(&cursor).map_err(|_| invalid())?;
+        // a cursor is only valid in the order it was issued for
+        if cursor.sort != sort.to_string() {
+            return Err(invalid());
+        }
+
+        let sort_column = Self::column(&sort.field)?;
+        let id_column = Self::column(T::ID)?;
+        let value = SqlValue::from_json(sort_column, &cursor.value)
+            .ok()
+            .flatten()
+            .ok_or_else(invalid)?;
+        let id = SqlValue::from_json(id_column, &cursor.id)
+            .ok()
+            .flatten()
+            .ok_or_else(invalid)?;
+
+        let after = match sort.direction {
+            SortDirection::Asc => ">",
+            SortDirection::Desc => "<",
+        };
+        let (sort_name, id_name) = (sort_column.name, id_column.name);
+        if sort.field == T::ID {
+            return Ok(vec![
+                SqlPart::Sql(format!("{id_name} {after} ")),
+                SqlPart::Value(id),
+            ]);
+        }
+        Ok(vec![
+            SqlPart::Sql(format!("{sort_name} {after} ")),
+            SqlPart::Value(value.clone()),
+            SqlPart::Sql(format!(" OR ({sort_name} = ")),
+            SqlPart::Value(value),
+            SqlPart::Sql(format!(" AND {id_name} {after} ")),
+            SqlPart::Value(id),
+            SqlPart::Sql(")".to_string()),
+        ])
+    }
+
+    /// The page `page_request` of the entities within `scope` matching the
+    /// search, most relevant first. Candidates hold a term, or a spelling of
+    /// it one edit away, at the start of a word of a search field. They are
+    /// ranked on the quality and weight of their matches, as in Mongo.
+    ///
+    /// Only the first `MAX_SEARCH_CANDIDATES` candidates by id are ranked, the
+    /// response being flagged `truncated` when there may be more.
+    pub async fn search(
+        &self,
+        scope: SqlFilter,
+        search_request: &SearchRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<SearchHit<T>>, InternalError> {
+        let terms = search_terms(&search_request.q);
+        let mut hits = vec![];
+        let mut truncated = false;
+
+        if !terms.is_empty() && !T::SEARCH_FIELDS.is_empty() {
+            let pattern = SqlValue::Text(fuzzy_pattern(&terms));
+            let mut condition = vec![];
+            for (i, field) in T::SEARCH_FIELDS.iter().enumerate() {
+                let separator = if i == 0 { "" } else { " OR " };
+                condition.push(SqlPart::Sql(format!(
+                    "{separator}{} ~* ",
+                    Self::column(field.name)?.name
+                )));
+                condition.push(SqlPart::Value(pattern.clone()));
+            }
+
+            let mut query = Self::select(&self.live(scope)?.with(condition));
+            query
+                .push(&format!(" ORDER BY {} LIMIT ", Self::column(T::ID)?.name))
+                .bind(SqlValue::BigInt(MAX_SEARCH_CANDIDATES));
+            let candidates = self.fetch_all(query).await?;
+            truncated = candidates.len() as i64 == MAX_SEARCH_CANDIDATES;
+            for entity in candidates {
+                let document = bson::to_document(&entity)?;
+                if let Some((score, highlights)) = rank(&document, &terms, T::SEARCH_FIELDS) {
+                    hits.push(SearchHit {
+                        entity,
+                        score,
+                        highlights,
+                    });
+                }
+            }
+        }
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let total = hits.len() as u64;
+        let data = hits
+            .into_iter()
+            .skip(usize::try_from(page_request.skip()).unwrap_or(usize::MAX))
+            .take(page_request.page_size as usize)
+            .collect::<Vec<_>>();
+
+        Ok(PageResponse {
+            page_info: Pagination::new(page_request, data.len(), total),
+            data,
+            next_cursor: None,
+            truncated,
+        })
+    }
+
+    /// Insert `entity` and return it as stored, at version 1 when
+    /// `T::VERSIONED` and created now when `T::TIMESTAMPED`.
+    pub async fn insert_one(&self, entity: &T) -> Result<T, InternalError> {
+        let mut fields = Self::fields(entity)?;
+        if T::VERSIONED {
+            fields.insert(VERSION.to_string(), Value::from(1_i64));
+        }
+        if T::TIMESTAMPED {
+            let now = serde_json::to_value(Utc::now())?;
+            fields.insert(CREATED_AT.to_string(), now.clone());
+            fields.insert(UPDATED_AT.to_string(), now);
+        }
+
+        for (column, _) in &self.discriminator {
+            fields.remove(column.field);
+        }
+
+        let mut columns = vec![];
+        let mut values = vec![];
+        for (field, value) in &fields {
+            let column = Self::column(field)?;
+            if let Some(value) = SqlValue::from_json(column, value)? {
+                columns.push(column.name);
+                values.push(value);
+            }
+        }
+        for (column, value) in &self.discriminator {
+            columns.push(column.name);
+            values.push(value.clone());
+        }
+
+        let mut query = SqlQuery::new(format!(
+            "INSERT INTO {} AS t ({}) VALUES (",
+            T::TABLE,
+            columns.join(", ")
+        ));
+        for (i, value) in values.into_iter().enumerate() {
+            if i > 0 {
+                query.push(", ");
+            }
+            query.bind(value);
+        }
+        query.push(") RETURNING row_to_json(t)::text");
+
+        self.fetch_optional(query)
+            .await?
+            .ok_or_else(|| InternalError::DbError {
+                cause: format!("no row returned on insert into `{}`", T::TABLE),
+            })
+    }
+
+    // The assignments bumping the version of the versioned entities and the
+    // update time of the timestamped ones, following another assignment
+    fn push_stamps(query: &mut SqlQuery) -> Result<(), InternalError> {
+        if T::VERSIONED {
+            let version = Self::column(VERSION)?.name;
+            query.push(&format!(", {version} = {version} + 1"));
+        }
+        if T::TIMESTAMPED {
+            let updated_at = Self::column(UPDATED_AT)?.name;
+            query
+                .push(&format!(", {updated_at} = "))
+                .bind(SqlValue::TimestampTz(Utc::now()));
+        }
+        Ok(())
+    }
+
+    fn required_id(entity: &T) -> Result<&T::Id, InternalError> {
+        entity.id().ok_or(InternalError::RequestFormatError {
+            reason: format!("require fields: `{}`", T::ID),
+        })
+    }
+
+    /// Set the fields of `entity` on the stored entity with the same id,
+    /// provided it is still at `expected_version` when given, and return the
+    /// updated entity. `None` when there is no entity of this id, and a
+    /// `PreconditionFailed` error when it is at another version.
+    pub async fn update_by_id_checked(
+        &self,
+        entity: &T,
+        expected_version: Option<i64>,
+    ) -> Result<Option<T>, InternalError> {
+        let id = Self::required_id(entity)?;
+        let mut fields = Self::fields(entity)?;
+        fields.remove(T::ID);
+        for (column, _) in &self.discriminator {
+            fields.remove(column.field);
+        }
+        // deleting, restoring, versioning and timestamps go through their own
+        // operations
+        fields.remove(DELETED_AT);
+        fields.remove(VERSION);
+        if T::TIMESTAMPED {
+            fields.remove(CREATED_AT);
+            fields.remove(UPDATED_AT);
+        }
+        if fields.is_empty() {
+            return Err(InternalError::DbUpdateEmpty);
+        }
+
+        let mut query = SqlQuery::new(format!("UPDATE {} AS t SET ", T::TABLE));
+        for (i, (field, value)) in fields.iter().enumerate() {
+            let column = Self::column(field)?;
+            if i > 0 {
+                query.push(", ");
+            }
+            query.push(&format!("{} = ", column.name));
+            match SqlValue::from_json(column, value)? {
+                Some(value) => query.bind(value),
+                None => query.push("NULL"),
+            };
+        }
+        Self::push_stamps(&mut query)?;
+        query.push_filter(&self.version_filter(id, expected_version)?);
+        query.push(" RETURNING row_to_json(t)::text");
+
+        match self.fetch_optional(query).await? {
+            Some(updated) => Ok(Some(updated)),
+            None => self.check_version(id, expected_version).await.map(|_| None),
+        }
+    }
+
+    /// Delete the entity `id`, only marking it deleted when `T::SOFT_DELETE`,
+    /// provided it is still at `expected_version` when given. `false` when
+    /// there is no entity of this id, and a `PreconditionFailed` error when it
+    /// is at another version.
+    pub async fn delete_by_id_checked(
+        &self,
+        id: &T::Id,
+        expected_version: Option<i64>,
+    ) -> Result<bool, InternalError> {
+        let filter = self.version_filter(id, expected_version)?;
+        let query = if T::SOFT_DELETE {
+            let mut query = SqlQuery::new(format!(
+                "UPDATE {} AS t SET {} = ",
+                T::TABLE,
+                Self::column(DELETED_AT)?.name
+            ));
+            query.bind(SqlValue::TimestampTz(Utc::now()));
+            Self::push_stamps(&mut query)?;
+            query.push_filter(&filter);
+            query
+        } else {
+            let mut query = SqlQuery::new(format!("DELETE FROM {} AS t", T::TABLE));
+            query.push_filter(&filter);
+            query
+        };
+
+        if self.execute(query).await? > 0 {
+            return Ok(true);
+        }
+        self.check_version(id, expected_version).await?;
+        Ok(false)
+    }
+
+    // The live entity `id`, at `expected_version` when given
+    fn version_filter(
+        &self,
+        id: &T::Id,
+        expected_version: Option<i64>,
+    ) -> Result<SqlFilter, InternalError> {
+        let mut filter = Self::id_filter(id)?;
+        if let Some(expected_version) = expected_version {
+            filter = filter.and(Self::field_filter(VERSION, &Value::from(expected_version))?);
+        }
+        self.live(filter)
+    }
+
+    // After a conditional write matched nothing, fail if the entity `id` is
+    // there at another version than expected, else tell whether it exists.
+    async fn check_version(
+        &self,
+        id: &T::Id,
+        expected_version: Option<i64>,
+    ) -> Result<bool, InternalError> {
+        let current = self.find_by_id(id).await?;
+        match (current, expected_version) {
+            (Some(current), Some(expected_version)) => Err(InternalError::PreconditionFailed {
+                cause: format!(
+                    "expected version {}, found {}",
+                    expected_version,
+                    current.version().unwrap_or_default()
+                ),
+            }),
+            (current, _) => Ok(current.is_some()),
+        }
+    }
+
+    /// Bring the soft deleted entity `id` back, returning whether there was
+    /// one to restore.
+    pub async fn restore_by_id(&self, id: &T::Id) -> Result<bool, InternalError> {
+        let deleted_at = Self::column(DELETED_AT)?.name;
+        let mut query = SqlQuery::new(format!("UPDATE {} AS t SET {deleted_at} = NULL", T::TABLE));
+        Self::push_stamps(&mut query)?;
+        query.push_filter(&self.scoped(
+            Self::id_filter(id)?.with(vec![SqlPart::Sql(format!("{deleted_at} IS NOT NULL"))]),
+        ));
+        Ok(self.execute(query).await? > 0)
+    }
+
+    /// Up to `limit` of the entities soft deleted before `deleted_before`,
+    /// oldest deletion first.
+    pub async fn find_deleted_before(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<T>, InternalError> {
+        let deleted_at = Self::column(DELETED_AT)?.name;
+        let mut query = Self::select(&self.scoped(Self::deleted_before(deleted_before)?));
+        query
+            .push(&format!(" ORDER BY {deleted_at} LIMIT "))
+            .bind(SqlValue::BigInt(limit));
+        self.fetch_all(query).await
+    }
+
+    /// Permanently delete the entity `id` if it is still soft deleted since
+    /// before `deleted_before`. An entity restored in the meantime is kept.
+    pub async fn purge_by_id(
+        &self,
+        id: &T::Id,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<u64, InternalError> {
+        let filter = self.scoped(Self::id_filter(id)?.and(Self::deleted_before(deleted_before)?));
+        let mut query = SqlQuery::new(format!("DELETE FROM {} AS t", T::TABLE));
+        query.push_filter(&filter);
+        self.execute(query).await
+    }
+
+    fn deleted_before(deleted_before: DateTime<Utc>) -> Result<SqlFilter, InternalError> {
+        Ok(SqlFilter::default().with(vec![
+            SqlPart::Sql(format!("{} < ", Self::column(DELETED_AT)?.name)),
+            SqlPart::Value(SqlValue::TimestampTz(deleted_before)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mongo_filter::{FilterField, FilterFieldType};
+    use chrono::TimeZone;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Element {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        id: Option<uuid::Uuid>,
+        #[serde(rename = "CREATED_AT", skip_serializing_if = "Option::is_none")]
+        created_at: Option<DateTime<Utc>>,
+    }
+
+    impl MongoEntity for Element {
+        type Id = uuid::Uuid;
+
+        const COLLECTION: &'static str = "elements";
+        const FILTER_FIELDS: &'static [FilterField] =
+            &[FilterField::new(CREATED_AT, FilterFieldType::DateTime)];
+
+        fn id(&self) -> Option<&Self::Id> {
+            self.id.as_ref()
+        }
+
+        fn set_id(&mut self, id: Self::Id) {
+            self.id = Some(id);
+        }
+    }
+
+    impl PostgresEntity for Element {
+        const TABLE: &'static str = "elements";
+        const COLUMNS: &'static [Column] = &[
+            Column::new("_id", "id", ColumnType::Uuid),
+            Column::new(CREATED_AT, "created_at", ColumnType::TimestampTz),
+        ];
+    }
+
+    #[test]
+    fn renders_date_ranges() {
+        let filter = PostgresRepository::<Element>::filter_with_expression(
+            &Element {
+                id: None,
+                created_at: None,
+            },
+            &FilterRequest {
+                filter: Some(
+                    "CREATED_AT:gte:2026-01-01;CREATED_AT:lt:2026-02-01T01:00:00+01:00".to_string(),
+                ),
+            },
+        )
+        .unwrap();
+        let mut query = SqlQuery::new("SELECT *");
+        query.push_filter(&filter);
+
+        assert_eq!(
+            query.sql,
+            "SELECT * WHERE (created_at >= $1) AND (created_at < $2)"
+        );
+        assert_eq!(
+            query.values,
+            vec![
+                SqlValue::TimestampTz(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()),
+                SqlValue::TimestampTz(Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap()),
+            ]
+        );
+    }
+}
@@ -53,3 +53,12 @@ where
     kv2::set(client, &kv_config.mount, &kv_config.path, secret).await?;
     Ok(())
 }
+
+/// Permanently delete every version of the secret at `kv_config`.
+pub async fn delete_secret_value(
+    client: &VaultClient,
+    kv_config: &VaultKvPath,
+) -> Result<(), InternalError> {
+    kv2::delete_metadata(client, &kv_config.mount, &kv_config.path).await?;
+    Ok(())
+}
@@ -0,0 +1,271 @@
+use super::{
+    db_mongo::{self, IndexDefinition},
+    mongo_repository::{MongoEntity, MongoRepository},
+};
+use crate::{
+    error::InternalError,
+    util::{principal::CLAIM_TENANT_ID, tenant_scope::TenantScope},
+};
+use futures::TryStreamExt;
+use lru::LruCache;
+use mongodb::{
+    bson::{self, doc},
+    options::ReplaceOptions,
+    Client,
+    Collection,
+    Database,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// The field telling apart the entities of the tenants sharing a collection.
+pub const TENANT_ID: &str = "TENANT_ID";
+
+// The collection of the database of the service registering the tenants
+// provisioned in their collections or databases
+const TENANT_REGISTRY: &str = "provisioned_tenants";
+// How many provisioned tenants an instance remembers, and for how long
+const KNOWN_TENANTS_CAPACITY: usize = 10_000;
+const KNOWN_TENANT_TTL: Duration = Duration::from_secs(60);
+
+/// How the entities of the tenants are kept apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TenantIsolation {
+    /// A collection shared by the tenants, every entity holding its tenant in
+    /// `TENANT_ID`.
+    #[default]
+    SharedCollection,
+    /// A `{collection}_{tenant_id}` collection per tenant.
+    CollectionPerTenant,
+    /// A `{database}_{tenant_id}` database per tenant.
+    DatabasePerTenant,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProvisionedTenant {
+    #[serde(rename = "_id")]
+    id: bson::Uuid,
+}
+
+/// Routes the entities of a tenant scope to where the isolation mode keeps
+/// them.
+///
+/// The collection or database of a tenant is set up by `provision` when the
+/// tenant is created, with the sort and search indexes of the entity and the
+/// indexes of the schema defined for its collection, and registered in the
+/// database of the service. The requests of tenants not registered are
+/// rejected rather than setting anything up. The migrations of the schema
+/// only run on the database of the service.
+#[derive(Debug, Clone)]
+pub struct TenantRouter {
+    client: Client,
+    db: Database,
+    isolation: TenantIsolation,
+    indexes: &'static [IndexDefinition],
+    // the tenants lately found registered, and until when to trust it
+    known: Arc<Mutex<LruCache<Uuid, Instant>>>,
+}
+
+impl TenantRouter {
+    /// The router of the entities of the database `database_name`.
+    pub fn new(client: Client, database_name: &str, isolation: TenantIsolation) -> Self {
+        TenantRouter {
+            db: client.database(database_name),
+            client,
+            isolation,
+            indexes: &[],
+            known: Arc::new(Mutex::new(LruCache::new(KNOWN_TENANTS_CAPACITY))),
+        }
+    }
+
+    /// Create `indexes`, usually those of the schema of the service, in the
+    /// collections of the tenants as well.
+    pub fn with_indexes(self, indexes: &'static [IndexDefinition]) -> Self {
+        TenantRouter { indexes, ..self }
+    }
+
+    pub fn isolation(&self) -> TenantIsolation {
+        self.isolation
+    }
+
+    /// The database of the service, shared by the tenants unless isolated in
+    /// databases of their own.
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    /// The repository of the entities `T` of `scope`. In a shared collection,
+    /// they are restricted to the tenant by its `TENANT_ID`. The collections
+    /// and databases of the tenants are only reachable for a provisioned
+    /// tenant.
+    pub async fn repository<T: MongoEntity>(
+        &self,
+        scope: &TenantScope,
+    ) -> Result<MongoRepository<T>, InternalError> {
+        match (self.isolation, scope.tenant_id()) {
+            (TenantIsolation::SharedCollection, None) => Ok(MongoRepository::new(&self.db)),
+            (TenantIsolation::SharedCollection, Some(tenant_id)) => {
+                Ok(MongoRepository::new(&self.db)
+                    .with_discriminator(TENANT_ID, bson::Uuid::from_uuid_0_8(tenant_id)))
+            }
+            (_, None) => Err(InternalError::InvalidClaim {
+                claim: CLAIM_TENANT_ID.to_string(),
+            }),
+            (_, Some(tenant_id)) => {
+                self.require_provisioned(tenant_id).await?;
+                Ok(self.tenant_repository(tenant_id))
+            }
+        }
+    }
+
+    /// Set up the collection or the database of the new tenant `tenant_id`
+    /// and register it. Nothing is needed in a shared collection.
+    pub async fn provision<T: MongoEntity>(&self, tenant_id: Uuid) -> Result<(), InternalError> {
+        if self.isolation == TenantIsolation::SharedCollection {
+            return Ok(());
+        }
+
+        let repository = self.tenant_repository::<T>(tenant_id);
+        repository.create_sort_indexes().await?;
+        repository.create_search_index().await?;
+        let collection = repository.collection().clone_with_type();
+        for index in self
+            .indexes
+            .iter()
+            .filter(|index| index.collection == T::COLLECTION)
+        {
+            db_mongo::create_index(&collection, index).await?;
+        }
+
+        self.register(tenant_id).await
+    }
+
+    /// Drop the collection or the database of the deleted tenant `tenant_id`
+    /// along with its registration.
+    pub async fn deprovision<T: MongoEntity>(&self, tenant_id: Uuid) -> Result<(), InternalError> {
+        match self.isolation {
+            TenantIsolation::SharedCollection => return Ok(()),
+            TenantIsolation::CollectionPerTenant => {
+                self.tenant_repository::<T>(tenant_id)
+                    .collection()
+                    .drop(None)
+                    .await?
+            }
+            TenantIsolation::DatabasePerTenant => {
+                self.tenant_database(tenant_id).drop(None).await?
+            }
+        }
+
+        self.known.lock().pop(&tenant_id);
+        self.registry()
+            .delete_one(doc! { "_id": bson::Uuid::from_uuid_0_8(tenant_id) }, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Register the tenants whose collection or database already holds
+    /// entities `T`, e.g. those provisioned on first use before the registry.
+    pub async fn register_existing<T: MongoEntity>(&self) -> Result<(), InternalError> {
+        let (names, prefix) = match self.isolation {
+            TenantIsolation::SharedCollection => return Ok(()),
+            TenantIsolation::CollectionPerTenant => (
+                self.db.list_collection_names(None).await?,
+                format!("{}_", T::COLLECTION),
+            ),
+            TenantIsolation::DatabasePerTenant => (
+                self.client.list_database_names(None, None).await?,
+                format!("{}_", self.db.name()),
+            ),
+        };
+        for tenant_id in names
+            .iter()
+            .filter_map(|name| name.strip_prefix(&prefix))
+            .filter_map(|tenant_id| Uuid::parse_str(tenant_id).ok())
+        {
+            self.register(tenant_id).await?;
+        }
+        Ok(())
+    }
+
+    /// The scopes holding entities `T`, for the jobs across the tenants: the
+    /// whole platform in a shared collection, else every registered tenant.
+    pub async fn scopes<T: MongoEntity>(&self) -> Result<Vec<TenantScope>, InternalError> {
+        if self.isolation == TenantIsolation::SharedCollection {
+            return Ok(vec![TenantScope::Platform]);
+        }
+
+        let tenants: Vec<ProvisionedTenant> = self
+            .registry()
+            .find(None, None)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(tenants
+            .into_iter()
+            .map(|tenant| TenantScope::Tenant(tenant.id.to_uuid_0_8()))
+            .collect())
+    }
+
+    fn tenant_database(&self, tenant_id: Uuid) -> Database {
+        self.client
+            .database(&format!("{}_{tenant_id}", self.db.name()))
+    }
+
+    fn tenant_repository<T: MongoEntity>(&self, tenant_id: Uuid) -> MongoRepository<T> {
+        let collection = match self.isolation {
+            TenantIsolation::DatabasePerTenant => self
+                .tenant_database(tenant_id)
+                .collection::<T>(T::COLLECTION),
+            _ => self
+                .db
+                .collection::<T>(&format!("{}_{tenant_id}", T::COLLECTION)),
+        };
+        MongoRepository::with_collection(collection)
+    }
+
+    fn registry(&self) -> Collection<ProvisionedTenant> {
+        self.db.collection(TENANT_REGISTRY)
+    }
+
+    async fn register(&self, tenant_id: Uuid) -> Result<(), InternalError> {
+        let id = bson::Uuid::from_uuid_0_8(tenant_id);
+        self.registry()
+            .replace_one(
+                doc! { "_id": id },
+                ProvisionedTenant { id },
+                ReplaceOptions::builder().upsert(true).build(),
+            )
+            .await?;
+        self.known
+            .lock()
+            .put(tenant_id, Instant::now() + KNOWN_TENANT_TTL);
+        Ok(())
+    }
+
+    // Fail unless the tenant is registered, looking it up at most once per
+    // ttl so that the deprovisioned tenants are soon rejected by every instance
+    async fn require_provisioned(&self, tenant_id: Uuid) -> Result<(), InternalError> {
+        if matches!(self.known.lock().get(&tenant_id), Some(until) if *until > Instant::now()) {
+            return Ok(());
+        }
+
+        let registered = self
+            .registry()
+            .find_one(doc! { "_id": bson::Uuid::from_uuid_0_8(tenant_id) }, None)
+            .await?;
+        if registered.is_none() {
+            self.known.lock().pop(&tenant_id);
+            return Err(InternalError::TenantNotFound { tenant_id });
+        }
+        self.known
+            .lock()
+            .put(tenant_id, Instant::now() + KNOWN_TENANT_TTL);
+        Ok(())
+    }
+}
@@ -4,7 +4,7 @@ use actix_http::{
 };
 use actix_web::{
     error::JsonPayloadError,
-    http::StatusCode,
+    http::{header::RETRY_AFTER, StatusCode},
     HttpResponse,
     HttpResponseBuilder,
     ResponseError,
@@ -18,7 +18,7 @@ use vaultrs::client::VaultClientSettingsBuilderError;
 #[cfg(feature = "mongo")]
 use mongodb::{
     bson::{self, document::ValueAccessError},
-    error::{ErrorKind, WriteFailure},
+    error::{ErrorKind, WriteFailure, TRANSIENT_TRANSACTION_ERROR},
 };
 use parking_lot::RwLock;
 use redis::RedisError;
@@ -81,6 +81,9 @@ pub enum InternalError {
     #[display(fmt = "Authentication failed: user not found")]
     AuthUserNotFound,
 
+    #[display(fmt = "Access token invalid: {}", cause)]
+    AuthTokenInvalid { cause: String },
+
     #[display(fmt = "Db error: {}", cause)]
     DbError { cause: String },
 
@@ -108,6 +111,9 @@ pub enum InternalError {
     )]
     DbDuplicateError { cause: String },
 
+    #[display(fmt = "The transaction was aborted by a transient error: {}", cause)]
+    DbTransientTransactionError { cause: String },
+
     #[display(fmt = "Vault client operation failed: {}", cause)]
     VaultClientError { cause: String },
 
@@ -126,6 +132,9 @@ pub enum InternalError {
     #[display(fmt = "Request format invalid: {}", reason)]
     RequestFormatError { reason: String },
 
+    #[display(fmt = "Too many requests, retry after {} seconds", retry_after_secs)]
+    RateLimitExceeded { retry_after_secs: u64 },
+
     #[display(fmt = "Failed to make downstream request: {}", cause)]
     SendRequestError { cause: String },
 
@@ -147,12 +156,27 @@ pub enum InternalError {
     #[display(fmt = "Request to {} failed with {}", url, cause)]
     RemoteRequestError { cause: String, url: String },
 
+    #[display(fmt = "Precondition failed: {}", cause)]
+    PreconditionFailed { cause: String },
+
     #[display(fmt = "User {} not found", user_id)]
     UserNotFound { user_id: Uuid },
 
     #[display(fmt = "Tenant {} not found", tenant_id)]
     TenantNotFound { tenant_id: Uuid },
 
+    #[display(fmt = "Notification {} not found", notification_id)]
+    NotificationNotFound { notification_id: Uuid },
+
+    #[display(fmt = "Webhook endpoint {} not found", endpoint_id)]
+    WebhookEndpointNotFound { endpoint_id: Uuid },
+
+    #[display(fmt = "Webhook delivery {} not found", delivery_id)]
+    WebhookDeliveryNotFound { delivery_id: Uuid },
+
+    #[display(fmt = "Webhooks are not available on the {} tier", tier)]
+    WebhookTierNotAllowed { tier: String },
+
     #[display(fmt = "Failed to internally notify: {}", cause)]
     SendNotificationError { cause: String },
 
@@ -176,7 +200,11 @@ impl InternalError {
             InternalError::InvalidClaim { claim: _ } => 1100,
             InternalError::RemoteRequestError { cause: _, url: _ } => 1105,
             InternalError::RequestFormatError { reason: _ } => 1110,
+            InternalError::RateLimitExceeded {
+                retry_after_secs: _,
+            } => 1120,
             InternalError::InvalidUrl { cause: _ } => 1130,
+            InternalError::PreconditionFailed { cause: _ } => 1140,
             InternalError::DbError { cause: _ } => 2001,
             InternalError::DbSchemaError {
                 code_version: _,
@@ -185,6 +213,7 @@ impl InternalError {
             InternalError::DbLockedForUpdate { cause: _ } => 2003,
             InternalError::DbUpdateEmpty => 2004,
             InternalError::DbDuplicateError { cause: _ } => 2005,
+            InternalError::DbTransientTransactionError { cause: _ } => 2006,
             InternalError::CacheClientCreationError { cause: _ } => 2100,
             InternalError::CacheClientConnectionError { cause: _ } => 2101,
             InternalError::CacheOperationError { cause: _ } => 2102,
@@ -194,11 +223,16 @@ impl InternalError {
             InternalError::VaultClientError { cause: _ } => 2300,
             InternalError::UserNotFound { user_id: _ } => 2501,
             InternalError::TenantNotFound { tenant_id: _ } => 2502,
+            InternalError::NotificationNotFound { notification_id: _ } => 2503,
+            InternalError::WebhookEndpointNotFound { endpoint_id: _ } => 2504,
+            InternalError::WebhookDeliveryNotFound { delivery_id: _ } => 2505,
             InternalError::SendNotificationError { cause: _ } => 2920,
             InternalError::SendRequestError { cause: _ } => 3000,
             InternalError::BlockingTaskExecutionError { cause: _ } => 3100,
             InternalError::AuthInvalidInvitation { cause: _ } => 4001,
             InternalError::AuthUserNotFound => 4002,
+            InternalError::AuthTokenInvalid { cause: _ } => 4003,
+            InternalError::WebhookTierNotAllowed { tier: _ } => 4101,
         }
     }
 
@@ -237,6 +271,9 @@ impl ResponseError for InternalError {
             InternalError::DbError { cause: _ } => StatusCode::INTERNAL_SERVER_ERROR,
             InternalError::DbUpdateEmpty => StatusCode::BAD_REQUEST,
             InternalError::DbDuplicateError { cause: _ } => StatusCode::BAD_REQUEST,
+            InternalError::DbTransientTransactionError { cause: _ } => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
             InternalError::VaultClientError { cause: _ } => StatusCode::INTERNAL_SERVER_ERROR,
             InternalError::CacheClientCreationError { cause: _ } => {
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -246,12 +283,19 @@ impl ResponseError for InternalError {
             }
             InternalError::CacheOperationError { cause: _ } => StatusCode::INTERNAL_SERVER_ERROR,
             InternalError::RequestFormatError { reason: _ } => StatusCode::BAD_REQUEST,
+            InternalError::RateLimitExceeded {
+                retry_after_secs: _,
+            } => StatusCode::TOO_MANY_REQUESTS,
             InternalError::InvalidUrl { cause: _ } => StatusCode::INTERNAL_SERVER_ERROR,
+            InternalError::PreconditionFailed { cause: _ } => StatusCode::PRECONDITION_FAILED,
             InternalError::InvalidJsonError { cause: _ } => StatusCode::INTERNAL_SERVER_ERROR,
             InternalError::InvalidBsonError { cause: _ } => StatusCode::INTERNAL_SERVER_ERROR,
             InternalError::BsonAccessError { cause: _ } => StatusCode::INTERNAL_SERVER_ERROR,
-            InternalError::UserNotFound { user_id: _ } => StatusCode::UNPROCESSABLE_ENTITY,
-            InternalError::TenantNotFound { tenant_id: _ } => StatusCode::UNPROCESSABLE_ENTITY,
+            InternalError::UserNotFound { user_id: _ } => StatusCode::NOT_FOUND,
+            InternalError::TenantNotFound { tenant_id: _ } => StatusCode::NOT_FOUND,
+            InternalError::NotificationNotFound { notification_id: _ } => StatusCode::NOT_FOUND,
+            InternalError::WebhookEndpointNotFound { endpoint_id: _ } => StatusCode::NOT_FOUND,
+            InternalError::WebhookDeliveryNotFound { delivery_id: _ } => StatusCode::NOT_FOUND,
             InternalError::SendNotificationError { cause: _ } => StatusCode::INTERNAL_SERVER_ERROR,
             InternalError::SendRequestError { cause: _ } => StatusCode::INTERNAL_SERVER_ERROR,
             InternalError::BlockingTaskExecutionError { cause: _ } => {
@@ -259,6 +303,8 @@ impl ResponseError for InternalError {
             }
             InternalError::AuthInvalidInvitation { cause: _ } => StatusCode::BAD_REQUEST,
             InternalError::AuthUserNotFound => StatusCode::BAD_REQUEST,
+            InternalError::AuthTokenInvalid { cause: _ } => StatusCode::UNAUTHORIZED,
+            InternalError::WebhookTierNotAllowed { tier: _ } => StatusCode::FORBIDDEN,
         }
     }
 
@@ -277,7 +323,11 @@ impl ResponseError for InternalError {
             }),
         };
 
-        HttpResponseBuilder::new(self.status_code()).json(body)
+        let mut response = HttpResponseBuilder::new(self.status_code());
+        if let InternalError::RateLimitExceeded { retry_after_secs } = self {
+            response.insert_header((RETRY_AFTER, retry_after_secs.to_string()));
+        }
+        response.json(body)
     }
 }
 
@@ -326,6 +376,50 @@ impl From<mongodb::error::Error> for InternalError {
             }
         }
 
+        // the whole transaction can be retried
+        if error.contains_label(TRANSIENT_TRANSACTION_ERROR) {
+            return InternalError::DbTransientTransactionError {
+                cause: error.to_string(),
+            };
+        }
+
+        InternalError::DbError {
+            cause: error.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<sqlx::Error> for InternalError {
+    fn from(error: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(db_error) = &error {
+            match db_error.code().as_deref() {
+                // unique_violation
+                Some("23505") => {
+                    return InternalError::DbDuplicateError {
+                        cause: error.to_string(),
+                    }
+                }
+                // serialization_failure and deadlock_detected, the whole
+                // transaction can be retried
+                Some("40001") | Some("40P01") => {
+                    return InternalError::DbTransientTransactionError {
+                        cause: error.to_string(),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        InternalError::DbError {
+            cause: error.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<sqlx::migrate::MigrateError> for InternalError {
+    fn from(error: sqlx::migrate::MigrateError) -> Self {
         InternalError::DbError {
             cause: error.to_string(),
         }
@@ -350,8 +444,8 @@ impl From<bson::de::Error> for InternalError {
     }
 }
 
-impl From<deadpool_redis::CreatePoolError> for InternalError {
-    fn from(error: deadpool_redis::CreatePoolError) -> Self {
+impl From<deadpool_redis::BuildError> for InternalError {
+    fn from(error: deadpool_redis::BuildError) -> Self {
         InternalError::CacheClientCreationError {
             cause: error.to_string(),
         }
@@ -1,3 +1,4 @@
+use crate::model::request::page_request::PageRequest;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -9,6 +10,20 @@ pub struct Pagination {
     pub total_elements: usize,
 }
 
+impl Pagination {
+    /// Describe the page `page_request` of `total_elements`, holding
+    /// `number_of_elements`.
+    pub fn new(page_request: &PageRequest, number_of_elements: usize, total_elements: u64) -> Self {
+        Pagination {
+            number_of_elements,
+            page: page_request.page as u32,
+            page_size: page_request.page_size as u32,
+            total_pages: total_elements.div_ceil(page_request.page_size.max(1)) as u32,
+            total_elements: total_elements as usize,
+        }
+    }
+}
+
 impl std::fmt::Display for Pagination {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", serde_json::to_string(self).unwrap())
@@ -0,0 +1,66 @@
+use actix::Message;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub mod prelude {
+    pub const SERVICE_AUDIT_SUBJECT: &str = "service.audit";
+
+    pub const SERVICE_AUDIT_EVENT_RECORDED: &str = "evt.audit.recorded";
+
+    // Types of the audited entities
+    pub const AUDIT_TARGET_USER: &str = "user";
+    pub const AUDIT_TARGET_TENANT: &str = "tenant";
+    pub const AUDIT_TARGET_WEBHOOK_ENDPOINT: &str = "webhook_endpoint";
+
+    // Audited actions
+    pub const AUDIT_ACTION_USER_INVITE: &str = "user.invite";
+    pub const AUDIT_ACTION_USER_CREATE: &str = "user.create";
+    pub const AUDIT_ACTION_USER_UPDATE: &str = "user.update";
+    pub const AUDIT_ACTION_USER_DELETE: &str = "user.delete";
+    pub const AUDIT_ACTION_USER_RESTORE: &str = "user.restore";
+    pub const AUDIT_ACTION_TENANT_CREATE: &str = "tenant.create";
+    pub const AUDIT_ACTION_TENANT_UPDATE: &str = "tenant.update";
+    pub const AUDIT_ACTION_TENANT_DELETE: &str = "tenant.delete";
+    pub const AUDIT_ACTION_TENANT_RESTORE: &str = "tenant.restore";
+    pub const AUDIT_ACTION_WEBHOOK_ENDPOINT_CREATE: &str = "webhook_endpoint.create";
+    pub const AUDIT_ACTION_WEBHOOK_ENDPOINT_UPDATE: &str = "webhook_endpoint.update";
+    pub const AUDIT_ACTION_WEBHOOK_ENDPOINT_DELETE: &str = "webhook_endpoint.delete";
+}
+
+/// An administrative action, recorded by the service performing it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Message)]
+#[rtype(result = "Result<(), std::io::Error>")]
+pub struct AuditRecord {
+    /// The user performing the action, none for the actions of the platform.
+    #[serde(default)]
+    pub actor_id: Option<String>,
+    #[serde(default)]
+    pub actor_tenant_id: Option<String>,
+    /// What was done, e.g. `user.update`.
+    pub action: String,
+    /// The kind of entity the action was done on, e.g. `user`.
+    pub target_type: String,
+    pub target_id: String,
+    /// The tenant the target belongs to, if any.
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// The fields of the target changed by the action.
+    #[serde(default)]
+    pub changes: Vec<AuditChange>,
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    /// The service the action was performed by.
+    pub source: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// The value of a field before and after an action, null when absent.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AuditChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
@@ -16,10 +16,14 @@ pub struct SendOtpMessage {
     pub to: String,
     pub sub: String,
     pub body: String,
+    #[serde(default)]
+    pub tenant_id: Option<String>,
 }
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Message)]
 #[rtype(result = "Result<(), std::io::Error>")]
 pub struct UserCreatedMessage {
     pub user_id: String,
     pub email: String,
+    #[serde(default)]
+    pub tenant_id: Option<String>,
 }
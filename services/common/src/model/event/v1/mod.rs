@@ -1,14 +1,15 @@
 use crate::error::InternalError;
 use actix::Message;
 use chrono::Utc;
-use cloudevents::{Data, EventBuilder};
-use serde::{Deserialize, Serialize};
+use cloudevents::{AttributesReader, Data, EventBuilder};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 
-use self::auth::prelude::*;
+use self::{audit::prelude::*, auth::prelude::*, tenant::prelude::*, user::prelude::*};
 
-use super::EventMessage;
+use super::{EventMessage, EventMetadata};
 
+pub mod audit;
 pub mod auth;
 pub mod tenant;
 pub mod user;
@@ -18,6 +19,39 @@ pub mod user;
 pub enum Event {
     AuthSendOtp(EventMessage<auth::SendOtpMessage>),
     AuthUserCreated(EventMessage<auth::UserCreatedMessage>),
+    TenantCreated(EventMessage<tenant::TenantCreatedMessage>),
+    TenantTierChanged(EventMessage<tenant::TenantTierChangedMessage>),
+    TenantDeleted(EventMessage<tenant::TenantDeletedMessage>),
+    UserDeleted(EventMessage<user::UserDeletedMessage>),
+    AuditRecorded(EventMessage<audit::AuditRecord>),
+}
+
+impl Event {
+    /// The cloud event type of this event.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Event::AuthSendOtp(_) => SERVICE_AUTH_COMMAND_SEND_OTP,
+            Event::AuthUserCreated(_) => SERVICE_AUTH_EVENT_USER_CREATED,
+            Event::TenantCreated(_) => SERVICE_TENANT_EVENT_TENANT_CREATED,
+            Event::TenantTierChanged(_) => SERVICE_TENANT_EVENT_TIER_CHANGED,
+            Event::TenantDeleted(_) => SERVICE_TENANT_EVENT_TENANT_DELETED,
+            Event::UserDeleted(_) => SERVICE_USER_EVENT_USER_DELETED,
+            Event::AuditRecorded(_) => SERVICE_AUDIT_EVENT_RECORDED,
+        }
+    }
+
+    /// The tenant this event belongs to, if any.
+    pub fn tenant_id(&self) -> Option<&str> {
+        match self {
+            Event::AuthSendOtp(_) => None,
+            Event::AuthUserCreated(EventMessage { payload, .. }) => payload.tenant_id.as_deref(),
+            Event::TenantCreated(EventMessage { payload, .. }) => Some(&payload.tenant_id),
+            Event::TenantTierChanged(EventMessage { payload, .. }) => Some(&payload.tenant_id),
+            Event::TenantDeleted(EventMessage { payload, .. }) => Some(&payload.tenant_id),
+            Event::UserDeleted(EventMessage { payload, .. }) => payload.tenant_id.as_deref(),
+            Event::AuditRecorded(EventMessage { payload, .. }) => payload.tenant_id.as_deref(),
+        }
+    }
 }
 
 impl TryFrom<Event> for cloudevents::Event {
@@ -39,6 +73,36 @@ impl TryFrom<Event> for cloudevents::Event {
                 .ty(SERVICE_AUTH_COMMAND_SEND_OTP)
                 .id(meta.trace_id)
                 .data(mime::APPLICATION_JSON.to_string(), json!(payload)),
+            Event::TenantCreated(EventMessage { meta, payload }) => builder
+                .source(meta.source)
+                .subject(SERVICE_TENANT_SUBJECT)
+                .ty(SERVICE_TENANT_EVENT_TENANT_CREATED)
+                .id(meta.trace_id)
+                .data(mime::APPLICATION_JSON.to_string(), json!(payload)),
+            Event::TenantTierChanged(EventMessage { meta, payload }) => builder
+                .source(meta.source)
+                .subject(SERVICE_TENANT_SUBJECT)
+                .ty(SERVICE_TENANT_EVENT_TIER_CHANGED)
+                .id(meta.trace_id)
+                .data(mime::APPLICATION_JSON.to_string(), json!(payload)),
+            Event::TenantDeleted(EventMessage { meta, payload }) => builder
+                .source(meta.source)
+                .subject(SERVICE_TENANT_SUBJECT)
+                .ty(SERVICE_TENANT_EVENT_TENANT_DELETED)
+                .id(meta.trace_id)
+                .data(mime::APPLICATION_JSON.to_string(), json!(payload)),
+            Event::UserDeleted(EventMessage { meta, payload }) => builder
+                .source(meta.source)
+                .subject(SERVICE_USER_SUBJECT)
+                .ty(SERVICE_USER_EVENT_USER_DELETED)
+                .id(meta.trace_id)
+                .data(mime::APPLICATION_JSON.to_string(), json!(payload)),
+            Event::AuditRecorded(EventMessage { meta, payload }) => builder
+                .source(meta.source)
+                .subject(SERVICE_AUDIT_SUBJECT)
+                .ty(SERVICE_AUDIT_EVENT_RECORDED)
+                .id(meta.trace_id)
+                .data(mime::APPLICATION_JSON.to_string(), json!(payload)),
         };
 
         builder.build().map_err(|_| InternalError::EventBuilder)
@@ -48,17 +112,72 @@ impl TryFrom<Event> for cloudevents::Event {
 impl TryFrom<cloudevents::Event> for Event {
     type Error = InternalError;
 
+    /// The event carried by `event`, its payload being the data of the type of
+    /// the cloud event.
     fn try_from(event: cloudevents::Event) -> Result<Event, Self::Error> {
-        event
-            .data()
-            .and_then(|data| match data {
-                Data::Json(json) => serde_json::from_value(json.clone()).ok(),
-                _ => None,
-            })
-            .and_then(|data| match serde_json::from_value::<Event>(data) {
-                Ok(e) => Some(e),
-                _ => None,
-            })
-            .ok_or_else(|| InternalError::EventParse)
+        let data = match event.data() {
+            Some(Data::Json(json)) => json.clone(),
+            _ => return Err(InternalError::EventParse),
+        };
+        let meta = EventMetadata::new(event.source().to_string(), event.id());
+
+        Ok(match event.ty() {
+            SERVICE_AUTH_COMMAND_SEND_OTP => Event::AuthSendOtp(message(meta, data)?),
+            SERVICE_AUTH_EVENT_USER_CREATED => Event::AuthUserCreated(message(meta, data)?),
+            SERVICE_TENANT_EVENT_TENANT_CREATED => Event::TenantCreated(message(meta, data)?),
+            SERVICE_TENANT_EVENT_TIER_CHANGED => Event::TenantTierChanged(message(meta, data)?),
+            SERVICE_TENANT_EVENT_TENANT_DELETED => Event::TenantDeleted(message(meta, data)?),
+            SERVICE_USER_EVENT_USER_DELETED => Event::UserDeleted(message(meta, data)?),
+            SERVICE_AUDIT_EVENT_RECORDED => Event::AuditRecorded(message(meta, data)?),
+            _ => return Err(InternalError::EventParse),
+        })
+    }
+}
+
+fn message<T: Clone + DeserializeOwned>(
+    meta: EventMetadata,
+    data: serde_json::Value,
+) -> Result<EventMessage<T>, InternalError> {
+    let payload = serde_json::from_value(data).map_err(|_| InternalError::EventParse)?;
+    Ok(EventMessage { meta, payload })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the subscribers, e.g. the webhook dispatcher, read the events back from
+    // the cloud events published on NATS
+    #[test]
+    fn decodes_published_events() {
+        let event = Event::AuthUserCreated(EventMessage {
+            meta: EventMetadata::new(SERVICE_AUTH_SUBJECT.into(), "trace_id"),
+            payload: auth::UserCreatedMessage {
+                user_id: "6f1f7c1c-0a7e-4b5e-9a43-3c1d2b0d6f10".to_string(),
+                email: "jane@example.com".to_string(),
+                tenant_id: Some("0b9e1f3a-3e6c-4d7e-8f5a-2c4b6d8e0f12".to_string()),
+            },
+        });
+
+        let published: cloudevents::Event = event.clone().try_into().unwrap();
+        let received: cloudevents::Event =
+            serde_json::from_slice(&serde_json::to_vec(&published).unwrap()).unwrap();
+        let decoded: Event = received.try_into().unwrap();
+
+        assert_eq!(decoded, event);
+        assert_eq!(decoded.tenant_id(), event.tenant_id());
+    }
+
+    #[test]
+    fn rejects_unknown_event_types() {
+        let event = cloudevents::event::EventBuilderV10::new()
+            .id("trace_id")
+            .source("test")
+            .ty("evt.unknown")
+            .data(mime::APPLICATION_JSON.to_string(), json!({}))
+            .build()
+            .unwrap();
+
+        assert!(Event::try_from(event).is_err());
     }
 }
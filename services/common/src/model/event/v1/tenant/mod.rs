@@ -1 +1,35 @@
+use actix::Message;
+use serde::{Deserialize, Serialize};
 
+pub mod prelude {
+    pub const SERVICE_TENANT_SUBJECT: &str = "service.tenant";
+
+    pub const SERVICE_TENANT_EVENT_TENANT_CREATED: &str = "evt.tenant.created";
+
+    pub const SERVICE_TENANT_EVENT_TIER_CHANGED: &str = "evt.tenant.tier.changed";
+
+    pub const SERVICE_TENANT_EVENT_TENANT_DELETED: &str = "evt.tenant.deleted";
+}
+
+/// A tenant created, for the services to set up where they keep its entities.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Message)]
+#[rtype(result = "Result<(), std::io::Error>")]
+pub struct TenantCreatedMessage {
+    pub tenant_id: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Message)]
+#[rtype(result = "Result<(), std::io::Error>")]
+pub struct TenantTierChangedMessage {
+    pub tenant_id: String,
+    pub previous_tier: Option<String>,
+    pub tier: String,
+}
+
+/// A tenant permanently deleted, along with its webhooks, once its retention
+/// period was over.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Message)]
+#[rtype(result = "Result<(), std::io::Error>")]
+pub struct TenantDeletedMessage {
+    pub tenant_id: String,
+}
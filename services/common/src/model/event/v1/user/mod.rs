@@ -1 +1,17 @@
+use actix::Message;
+use serde::{Deserialize, Serialize};
 
+pub mod prelude {
+    pub const SERVICE_USER_SUBJECT: &str = "service.user";
+
+    pub const SERVICE_USER_EVENT_USER_DELETED: &str = "evt.user.deleted";
+}
+
+/// A user permanently deleted once its retention period was over.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Message)]
+#[rtype(result = "Result<(), std::io::Error>")]
+pub struct UserDeletedMessage {
+    pub user_id: String,
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use validator::Validate;
+
+/// Conditions on the elements of a listing, in the filter expression syntax
+/// of the listed entity's storage, e.g. `STATUS:in:ACTIVE,INACTIVE`.
+#[derive(Debug, Deserialize, Clone, Default, Validate)]
+pub struct FilterRequest {
+    #[validate(length(max = 2048))]
+    pub filter: Option<String>,
+}
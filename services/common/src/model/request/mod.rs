@@ -1 +1,3 @@
+pub mod filter_request;
 pub mod page_request;
+pub mod search_request;
@@ -1,15 +1,123 @@
+use crate::error::InternalError;
 use serde::Deserialize;
+use std::{fmt, str::FromStr};
 use validator::Validate;
 
+/// A page of a listing. Pages are numbered from 0, and are at most 100
+/// elements long. Deep pages are refused as skipping to them is costly, cursor
+/// based listings are meant for walking through a whole collection: the
+/// `next_cursor` of a page is passed back as `cursor` (or `after`) to get the
+/// following one, in which case `page` is ignored.
 #[derive(Debug, Deserialize, Clone, Validate)]
 pub struct PageRequest {
-    #[validate(range(min = 0))]
+    #[validate(range(max = 10000))]
     #[serde(default = "default_page")]
     pub page: u64,
 
-    #[validate(range(min = 1))]
+    #[validate(range(min = 1, max = 100))]
     #[serde(default = "default_page_size", rename = "pageSize")]
     pub page_size: u64,
+
+    #[validate(length(max = 1024))]
+    #[serde(alias = "after")]
+    pub cursor: Option<String>,
+
+    /// `field:asc` or `field:desc`, ascending when the direction is omitted.
+    pub sort: Option<String>,
+}
+
+impl PageRequest {
+    /// Number of elements preceding the page.
+    pub fn skip(&self) -> u64 {
+        self.page.saturating_mul(self.page_size)
+    }
+
+    pub fn limit(&self) -> i64 {
+        i64::try_from(self.page_size).unwrap_or(i64::MAX)
+    }
+
+    /// The requested order, provided it is on `default` or one of `allowed`,
+    /// else `default`.
+    pub fn sort_order(
+        &self,
+        allowed: &[&str],
+        default: SortOrder,
+    ) -> Result<SortOrder, InternalError> {
+        let sort = match &self.sort {
+            Some(sort) => sort.parse::<SortOrder>()?,
+            None => return Ok(default),
+        };
+        if sort.field != default.field && !allowed.contains(&sort.field.as_str()) {
+            return Err(InternalError::RequestFormatError {
+                reason: format!("unsupported sort field: `{}`", sort.field),
+            });
+        }
+        Ok(sort)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortOrder {
+    pub field: String,
+    pub direction: SortDirection,
+}
+
+impl SortOrder {
+    pub fn asc(field: &str) -> Self {
+        SortOrder {
+            field: field.to_string(),
+            direction: SortDirection::Asc,
+        }
+    }
+
+    pub fn desc(field: &str) -> Self {
+        SortOrder {
+            field: field.to_string(),
+            direction: SortDirection::Desc,
+        }
+    }
+}
+
+impl FromStr for SortOrder {
+    type Err = InternalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, direction) = s.split_once(':').unwrap_or((s, "asc"));
+        let direction = match direction.to_ascii_lowercase().as_str() {
+            "asc" => SortDirection::Asc,
+            "desc" => SortDirection::Desc,
+            _ => {
+                return Err(InternalError::RequestFormatError {
+                    reason: format!("invalid sort: `{s}`"),
+                })
+            }
+        };
+        if field.is_empty() {
+            return Err(InternalError::RequestFormatError {
+                reason: format!("invalid sort: `{s}`"),
+            });
+        }
+        Ok(SortOrder {
+            field: field.to_string(),
+            direction,
+        })
+    }
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.direction {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        };
+        write!(f, "{}:{}", self.field, direction)
+    }
 }
 
 fn default_page_size() -> u64 {
@@ -19,3 +127,70 @@ fn default_page_size() -> u64 {
 fn default_page() -> u64 {
     0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page_request(sort: Option<&str>) -> PageRequest {
+        PageRequest {
+            page: 2,
+            page_size: 25,
+            cursor: None,
+            sort: sort.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parses_sort_orders() {
+        assert_eq!(
+            "EMAIL".parse::<SortOrder>().unwrap(),
+            SortOrder::asc("EMAIL")
+        );
+        assert_eq!(
+            "EMAIL:ASC".parse::<SortOrder>().unwrap(),
+            SortOrder::asc("EMAIL")
+        );
+        assert_eq!(
+            "EMAIL:desc".parse::<SortOrder>().unwrap(),
+            SortOrder::desc("EMAIL")
+        );
+        assert_eq!(SortOrder::desc("EMAIL").to_string(), "EMAIL:desc");
+
+        assert!("EMAIL:down".parse::<SortOrder>().is_err());
+        assert!(":asc".parse::<SortOrder>().is_err());
+    }
+
+    #[test]
+    fn sorts_on_the_allowed_fields_only() {
+        let default = || SortOrder::asc("_id");
+
+        assert_eq!(
+            page_request(None)
+                .sort_order(&["EMAIL"], default())
+                .unwrap(),
+            default()
+        );
+        assert_eq!(
+            page_request(Some("EMAIL:desc"))
+                .sort_order(&["EMAIL"], default())
+                .unwrap(),
+            SortOrder::desc("EMAIL")
+        );
+        assert_eq!(
+            page_request(Some("_id:desc"))
+                .sort_order(&[], default())
+                .unwrap(),
+            SortOrder::desc("_id")
+        );
+        assert!(page_request(Some("PASSWORD"))
+            .sort_order(&["EMAIL"], default())
+            .is_err());
+    }
+
+    #[test]
+    fn skips_the_preceding_pages() {
+        assert_eq!(page_request(None).skip(), 50);
+        assert_eq!(page_request(None).limit(), 25);
+    }
+}
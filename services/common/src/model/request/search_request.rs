@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use validator::Validate;
+
+/// Free text searched for in the text fields of an entity. Words match in
+/// full, as prefixes, or with a typo when at least 4 characters long.
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct SearchRequest {
+    #[validate(length(min = 2, max = 100))]
+    pub q: String,
+}
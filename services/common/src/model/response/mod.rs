@@ -1 +1,2 @@
 pub mod page_response;
+pub mod search_hit;
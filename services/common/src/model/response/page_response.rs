@@ -6,6 +6,13 @@ use std::fmt;
 pub struct PageResponse<T> {
     pub data: Vec<T>,
     pub page_info: Pagination,
+    /// Cursor of the following page, absent on the last one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Set when a search ranked only part of its matches, `total_elements`
+    /// then being a lower bound.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
 }
 
 impl<T> fmt::Display for PageResponse<T>
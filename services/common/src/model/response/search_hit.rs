@@ -0,0 +1,11 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// An entity found by a search, with its relevance and the values of its
+/// matching fields where the matching words are wrapped in `<em>` tags.
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchHit<T> {
+    pub entity: T,
+    pub score: f64,
+    pub highlights: BTreeMap<String, String>,
+}
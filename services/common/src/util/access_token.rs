@@ -0,0 +1,160 @@
+use chrono::{Duration, Utc};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{principal::Principal, signature};
+use crate::error::InternalError;
+
+#[derive(Debug, Deserialize)]
+pub struct AccessTokenSecrets {
+    pub signing_key: Secret<String>,
+}
+
+// The claims signed in an access token
+#[derive(Debug, Serialize, Deserialize)]
+struct AccessTokenClaims {
+    sub: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tenant_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+    // expiry, in seconds since the epoch
+    exp: i64,
+}
+
+/// The key the auth service signs the access tokens with on login, and every
+/// service verifies them with. A token is the base64url encoded json claims
+/// and their HMAC-SHA256 signature, joined by a dot.
+#[derive(Debug, Clone)]
+pub struct AccessTokenKey {
+    key: Secret<String>,
+}
+
+impl AccessTokenKey {
+    pub fn new(key: Secret<String>) -> Self {
+        AccessTokenKey { key }
+    }
+
+    /// A token asserting `principal` for `ttl`.
+    pub fn issue(&self, principal: &Principal, ttl: Duration) -> Result<String, InternalError> {
+        let claims = AccessTokenClaims {
+            sub: principal.user_id,
+            tenant_id: principal.tenant_id,
+            role: principal.role.clone(),
+            email: principal.email.clone(),
+            exp: (Utc::now() + ttl).timestamp(),
+        };
+        let claims = base64::encode_config(serde_json::to_vec(&claims)?, base64::URL_SAFE_NO_PAD);
+        let signature = signature::sign(self.key.expose_secret().as_bytes(), claims.as_bytes());
+        Ok(format!("{claims}.{signature}"))
+    }
+
+    /// The principal asserted by `token`, provided it was signed with this key
+    /// and has not expired.
+    pub fn verify(&self, token: &str) -> Result<Principal, InternalError> {
+        let invalid = |cause: &str| InternalError::AuthTokenInvalid {
+            cause: cause.to_string(),
+        };
+
+        let (claims, signature) = token.split_once('.').ok_or_else(|| invalid("malformed"))?;
+        if !signature::verify(
+            self.key.expose_secret().as_bytes(),
+            claims.as_bytes(),
+            signature,
+        ) {
+            return Err(invalid("bad signature"));
+        }
+
+        let claims: AccessTokenClaims = base64::decode_config(claims, base64::URL_SAFE_NO_PAD)
+            .ok()
+            .and_then(|claims| serde_json::from_slice(&claims).ok())
+            .ok_or_else(|| invalid("malformed claims"))?;
+        if claims.exp <= Utc::now().timestamp() {
+            return Err(invalid("expired"));
+        }
+
+        Ok(Principal {
+            user_id: claims.sub,
+            tenant_id: claims.tenant_id,
+            role: claims.role,
+            email: claims.email,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: &str) -> AccessTokenKey {
+        AccessTokenKey::new(Secret::new(key.to_string()))
+    }
+
+    fn principal() -> Principal {
+        Principal {
+            user_id: Uuid::new_v4(),
+            tenant_id: Some(Uuid::new_v4()),
+            role: Some("ADMIN".to_string()),
+            email: Some("admin@example.com".to_string()),
+        }
+    }
+
+    #[test]
+    fn verifies_issued_tokens() {
+        let principal = principal();
+        let token = key("secret")
+            .issue(&principal, Duration::minutes(5))
+            .unwrap();
+
+        let verified = key("secret").verify(&token).unwrap();
+        assert_eq!(verified.user_id, principal.user_id);
+        assert_eq!(verified.tenant_id, principal.tenant_id);
+        assert_eq!(verified.role, principal.role);
+        assert_eq!(verified.email, principal.email);
+    }
+
+    #[test]
+    fn rejects_expired_tokens() {
+        let token = key("secret")
+            .issue(&principal(), Duration::seconds(-1))
+            .unwrap();
+
+        assert!(key("secret").verify(&token).is_err());
+    }
+
+    #[test]
+    fn rejects_tokens_of_other_keys() {
+        let token = key("other")
+            .issue(&principal(), Duration::minutes(5))
+            .unwrap();
+
+        assert!(key("secret").verify(&token).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_claims() {
+        let token = key("secret")
+            .issue(&principal(), Duration::minutes(5))
+            .unwrap();
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = AccessTokenClaims {
+            sub: Uuid::new_v4(),
+            tenant_id: None,
+            role: Some("ADMIN".to_string()),
+            email: None,
+            exp: i64::MAX,
+        };
+        let forged = base64::encode_config(
+            serde_json::to_vec(&forged).unwrap(),
+            base64::URL_SAFE_NO_PAD,
+        );
+
+        assert!(key("secret")
+            .verify(&format!("{forged}.{signature}"))
+            .is_err());
+        assert!(key("secret").verify("not a token").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    error::InternalError,
+    model::event::v1::audit::{AuditChange, AuditRecord},
+    util::{client_ip::client_ip, principal::Principal},
+};
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use chrono::Utc;
+use futures::future::{ready, Ready};
+use opentelemetry::trace::TraceContextExt;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+// Fields bumped by every write, which would only clutter the changes
+const UNAUDITED_FIELDS: &[&str] = &["UPDATED_AT", "VERSION"];
+
+/// Who performs an action and from where, for its audit record. The actor is
+/// the principal of the request, if any, and its ip that of the client as
+/// seen by the service or its trusted proxies.
+#[derive(Debug, Clone)]
+pub struct AuditActor {
+    pub principal: Option<Principal>,
+    pub ip: Option<String>,
+    pub trace_id: Option<String>,
+}
+
+impl AuditActor {
+    /// The record of `action` done now by this actor, through the service
+    /// `source`, on the `target_type` entity `target_id`.
+    pub fn record(
+        &self,
+        source: &str,
+        action: &str,
+        target_type: &str,
+        target_id: impl ToString,
+    ) -> AuditRecord {
+        AuditRecord {
+            actor_id: self
+                .principal
+                .as_ref()
+                .map(|principal| principal.user_id.to_string()),
+            actor_tenant_id: self
+                .principal
+                .as_ref()
+                .and_then(|principal| principal.tenant_id)
+                .map(|tenant_id| tenant_id.to_string()),
+            action: action.to_string(),
+            target_type: target_type.to_string(),
+            target_id: target_id.to_string(),
+            tenant_id: None,
+            changes: Vec::new(),
+            ip: self.ip.clone(),
+            trace_id: self.trace_id.clone(),
+            source: source.to_string(),
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+impl FromRequest for AuditActor {
+    type Error = InternalError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let span_context = tracing::Span::current()
+            .context()
+            .span()
+            .span_context()
+            .clone();
+        ready(Ok(AuditActor {
+            principal: Principal::authenticate(req).ok(),
+            ip: client_ip(req).map(|ip| ip.to_string()),
+            trace_id: span_context
+                .is_valid()
+                .then(|| span_context.trace_id().to_hex()),
+        }))
+    }
+}
+
+impl AuditRecord {
+    /// This record, of an action on an entity of the tenant `tenant_id`.
+    pub fn with_tenant(mut self, tenant_id: Option<impl ToString>) -> Self {
+        self.tenant_id = tenant_id.map(|tenant_id| tenant_id.to_string());
+        self
+    }
+
+    /// This record, of an action changing its target from `before` to
+    /// `after`, either being absent when the target is created or deleted.
+    pub fn with_changes<T: Serialize>(
+        mut self,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Result<Self, InternalError> {
+        self.changes = diff(before, after)?;
+        Ok(self)
+    }
+}
+
+/// The top level fields differing between the serialized `before` and
+/// `after`, in the order of their names.
+pub fn diff<T: Serialize>(
+    before: Option<&T>,
+    after: Option<&T>,
+) -> Result<Vec<AuditChange>, InternalError> {
+    let before = fields(before)?;
+    let after = fields(after)?;
+
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+    Ok(names
+        .into_iter()
+        .filter(|name| !UNAUDITED_FIELDS.contains(&name.as_str()))
+        .filter_map(|name| {
+            let before = before.get(name).cloned().unwrap_or(Value::Null);
+            let after = after.get(name).cloned().unwrap_or(Value::Null);
+            (before != after).then(|| AuditChange {
+                field: name.clone(),
+                before,
+                after,
+            })
+        })
+        .collect())
+}
+
+fn fields<T: Serialize>(entity: Option<&T>) -> Result<Map<String, Value>, InternalError> {
+    match entity.map(serde_json::to_value).transpose()? {
+        Some(Value::Object(fields)) => Ok(fields),
+        _ => Ok(Map::new()),
+    }
+}
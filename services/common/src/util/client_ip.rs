@@ -0,0 +1,90 @@
+use actix_web::{http::header::HeaderName, HttpRequest};
+use serde::Deserialize;
+use std::net::IpAddr;
+
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// The reverse proxies trusted to report the address of the clients in
+/// `X-Forwarded-For`, registered as app data. Without any, the address of a
+/// client is the peer address of its connection.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TrustedProxies {
+    #[serde(default)]
+    pub addresses: Vec<IpAddr>,
+}
+
+/// The address of the client of `req`, which it cannot spoof: the peer
+/// address, or behind trusted proxies the last address forwarded by them.
+pub fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    let trusted = req
+        .app_data::<TrustedProxies>()
+        .map(|proxies| proxies.addresses.as_slice())
+        .unwrap_or_default();
+    let forwarded = req
+        .headers()
+        .get(X_FORWARDED_FOR)
+        .and_then(|value| value.to_str().ok());
+    Some(forwarded_client(peer, forwarded, trusted))
+}
+
+// Walk the proxies back from the peer, the first address not trusted being
+// the client. The addresses before it could have been made up by the client.
+fn forwarded_client(peer: IpAddr, forwarded: Option<&str>, trusted: &[IpAddr]) -> IpAddr {
+    let mut client = peer;
+    if !trusted.contains(&client) {
+        return client;
+    }
+    for address in forwarded.unwrap_or_default().rsplit(',') {
+        match address.trim().parse() {
+            Ok(address) => client = address,
+            Err(_) => break,
+        }
+        if !trusted.contains(&client) {
+            break;
+        }
+    }
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn ignores_forwarded_addresses_of_untrusted_peers() {
+        let client = forwarded_client(ip("203.0.113.7"), Some("10.0.0.1"), &[]);
+
+        assert_eq!(client, ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn takes_the_address_forwarded_by_trusted_proxies() {
+        let trusted = [ip("10.0.0.2"), ip("10.0.0.3")];
+        let client = forwarded_client(
+            ip("10.0.0.3"),
+            Some("192.0.2.1, 203.0.113.7, 10.0.0.2"),
+            &trusted,
+        );
+
+        assert_eq!(client, ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn stops_at_malformed_forwarded_addresses() {
+        let trusted = [ip("10.0.0.2")];
+
+        assert_eq!(
+            forwarded_client(ip("10.0.0.2"), Some("203.0.113.7, unknown"), &trusted),
+            ip("10.0.0.2")
+        );
+        assert_eq!(
+            forwarded_client(ip("10.0.0.2"), None, &trusted),
+            ip("10.0.0.2")
+        );
+    }
+}
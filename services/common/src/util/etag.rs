@@ -0,0 +1,74 @@
+use crate::error::InternalError;
+use actix_web::http::header::{ETag, EntityTag, IfMatch};
+
+/// The `ETag` of the version of an entity, for the clients to send it back in
+/// `If-Match` when writing the entity.
+pub fn etag(version: Option<i64>) -> ETag {
+    ETag(EntityTag::new_strong(
+        version.unwrap_or_default().to_string(),
+    ))
+}
+
+/// The version an `If-Match` precondition requires, `None` when there is no
+/// precondition or any version will do. Weak tags never match, as the
+/// comparison of `If-Match` is strong.
+pub fn expected_version(if_match: Option<&IfMatch>) -> Result<Option<i64>, InternalError> {
+    let tags = match if_match {
+        None | Some(IfMatch::Any) => return Ok(None),
+        Some(IfMatch::Items(tags)) => tags,
+    };
+
+    let versions = tags
+        .iter()
+        .filter(|tag| !tag.weak)
+        .filter_map(|tag| tag.tag().parse::<i64>().ok())
+        .collect::<Vec<_>>();
+    match versions.as_slice() {
+        [version] => Ok(Some(*version)),
+        _ => Err(InternalError::PreconditionFailed {
+            cause: "`If-Match` must hold the single entity tag of a version".to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        http::header::{Header, IF_MATCH},
+        test::TestRequest,
+    };
+
+    fn if_match(value: &str) -> IfMatch {
+        let req = TestRequest::default()
+            .insert_header((IF_MATCH, value))
+            .to_http_request();
+        IfMatch::parse(&req).unwrap()
+    }
+
+    #[test]
+    fn tags_the_version() {
+        assert_eq!(etag(Some(3)).0.to_string(), r#""3""#);
+        assert_eq!(etag(None).0.to_string(), r#""0""#);
+    }
+
+    #[test]
+    fn requires_the_version_of_the_tag() {
+        assert_eq!(
+            expected_version(Some(&if_match(r#""3""#))).unwrap(),
+            Some(3)
+        );
+        assert_eq!(expected_version(Some(&if_match("*"))).unwrap(), None);
+        assert_eq!(expected_version(None).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_tags_not_naming_a_single_version() {
+        for value in [r#"W/"3""#, r#""v3""#, r#""3", "4""#] {
+            assert!(
+                expected_version(Some(&if_match(value))).is_err(),
+                "accepted `{value}`"
+            );
+        }
+    }
+}
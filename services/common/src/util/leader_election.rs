@@ -0,0 +1,144 @@
+use crate::{
+    client::{cache_lock::Lock, cache_redis::Cache},
+    error::InternalError,
+};
+use actix::{
+    Actor,
+    ActorFutureExt,
+    AsyncContext,
+    Context,
+    Handler,
+    Message,
+    Recipient,
+    WrapFuture,
+};
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Sent to the subscriber of a `LeaderElection` when this replica becomes
+/// or stops being the leader.
+#[derive(Message, Debug, Clone, Copy)]
+#[rtype(result = "()")]
+pub struct LeadershipChanged {
+    /// The fencing token of the leadership, `None` when not leading.
+    pub fencing_token: Option<u64>,
+}
+
+/// Ask for the fencing token of the current leadership, `None` when this
+/// replica is not leading.
+#[derive(Message, Debug, Clone, Copy)]
+#[rtype(result = "Option<u64>")]
+pub struct IsLeader;
+
+/// Campaigns for the lock `name` so that a single replica of a service runs
+/// singleton tasks. The leader renews its lease every third of it and steps
+/// down as soon as a renewal fails, before the lease can be taken over.
+pub struct LeaderElection {
+    cache: Cache,
+    name: String,
+    lease: Duration,
+    lock: Option<Lock>,
+    campaigning: bool,
+    subscriber: Option<Recipient<LeadershipChanged>>,
+}
+
+impl LeaderElection {
+    pub fn new(cache: Cache, name: &str, lease: Duration) -> Self {
+        LeaderElection {
+            cache,
+            name: name.to_string(),
+            lease,
+            lock: None,
+            campaigning: false,
+            subscriber: None,
+        }
+    }
+
+    /// Notify `subscriber` of every change of leadership.
+    pub fn with_subscriber(self, subscriber: Recipient<LeadershipChanged>) -> Self {
+        LeaderElection {
+            subscriber: Some(subscriber),
+            ..self
+        }
+    }
+
+    fn campaign(&mut self, ctx: &mut Context<Self>) {
+        // a slow round trip must not overlap with the next one
+        if self.campaigning {
+            return;
+        }
+        self.campaigning = true;
+
+        let cache = self.cache.clone();
+        let name = self.name.clone();
+        let lease = self.lease;
+        let held = self.lock.clone();
+
+        let round = async move {
+            match held {
+                Some(lock) => {
+                    let renewed = cache.renew_lock(&lock).await?;
+                    Ok::<_, InternalError>(if renewed { Some(lock) } else { None })
+                }
+                None => cache.try_lock(&name, lease).await,
+            }
+        };
+
+        ctx.spawn(round.into_actor(self).map(|result, act, _| {
+            act.campaigning = false;
+            let lock = result.unwrap_or_else(|err| {
+                error!("Failed to campaign for {}: {}", act.name, err);
+                None
+            });
+            act.update(lock);
+        }));
+    }
+
+    fn update(&mut self, lock: Option<Lock>) {
+        let previous = self.lock.as_ref().map(|lock| lock.fencing_token);
+        let current = lock.as_ref().map(|lock| lock.fencing_token);
+        self.lock = lock;
+        if previous == current {
+            return;
+        }
+
+        match current {
+            Some(fencing_token) => info!("Leading {} with token {}", self.name, fencing_token),
+            None => info!("Stepped down from {}", self.name),
+        }
+        if let Some(subscriber) = &self.subscriber {
+            subscriber.do_send(LeadershipChanged {
+                fencing_token: current,
+            });
+        }
+    }
+}
+
+impl Actor for LeaderElection {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.campaign(ctx);
+        ctx.run_interval(self.lease / 3, |act, ctx| act.campaign(ctx));
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        // hand the leadership over without waiting for the lease to expire
+        if let Some(lock) = self.lock.take() {
+            let cache = self.cache.clone();
+            actix::spawn(async move {
+                if let Err(err) = cache.release_lock(lock).await {
+                    error!("Failed to release leadership: {}", err);
+                }
+            });
+        }
+    }
+}
+
+impl Handler<IsLeader> for LeaderElection {
+    type Result = Option<u64>;
+
+    fn handle(&mut self, _: IsLeader, _: &mut Context<Self>) -> Self::Result {
+        self.lock.as_ref().map(|lock| lock.fencing_token)
+    }
+}
@@ -1,4 +1,14 @@
+pub mod access_token;
 pub mod actix_json_config;
 pub mod app_env;
+pub mod audit;
+pub mod client_ip;
 pub mod configuration;
+pub mod etag;
+pub mod leader_election;
+pub mod principal;
+pub mod rate_limit;
+pub mod retention;
+pub mod signature;
 pub mod telemetry;
+pub mod tenant_scope;
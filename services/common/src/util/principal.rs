@@ -0,0 +1,88 @@
+use actix_web::{dev::Payload, http::header::AUTHORIZATION, FromRequest, HttpRequest};
+use futures::future::{ready, Ready};
+use uuid::Uuid;
+
+use super::access_token::AccessTokenKey;
+use crate::error::InternalError;
+
+pub const CLAIM_USER_ID: &str = "sub";
+pub const CLAIM_TENANT_ID: &str = "tenant_id";
+pub const CLAIM_ROLE: &str = "role";
+pub const CLAIM_EMAIL: &str = "email";
+
+pub const ROLE_ADMIN: &str = "ADMIN";
+
+/// Identity of the caller, as asserted by the access token the auth service
+/// issued it on login. The token is sent as a bearer token in the
+/// `Authorization` header and verified with the [`AccessTokenKey`] registered
+/// as app data of the service.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub user_id: Uuid,
+    pub tenant_id: Option<Uuid>,
+    pub role: Option<String>,
+    pub email: Option<String>,
+}
+
+impl Principal {
+    pub(crate) fn authenticate(req: &HttpRequest) -> Result<Self, InternalError> {
+        let invalid = |cause: &str| InternalError::AuthTokenInvalid {
+            cause: cause.to_string(),
+        };
+
+        let key = req
+            .app_data::<AccessTokenKey>()
+            .ok_or_else(|| invalid("no key to verify it with"))?;
+        let token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| invalid("require a bearer token"))?;
+        key.verify(token.trim())
+    }
+
+    /// Fail unless the caller is an administrator.
+    pub fn require_admin(&self) -> Result<(), InternalError> {
+        match &self.role {
+            Some(role) if role.eq_ignore_ascii_case(ROLE_ADMIN) => Ok(()),
+            _ => Err(InternalError::InvalidClaim {
+                claim: CLAIM_ROLE.to_string(),
+            }),
+        }
+    }
+
+    /// Fail unless the caller owns the address `email`, or is an administrator
+    /// of the platform.
+    pub fn require_email_or_platform_admin(&self, email: &str) -> Result<(), InternalError> {
+        match &self.email {
+            Some(own) if own.eq_ignore_ascii_case(email) => Ok(()),
+            _ => self
+                .require_platform_admin()
+                .map_err(|_| InternalError::InvalidClaim {
+                    claim: CLAIM_EMAIL.to_string(),
+                }),
+        }
+    }
+
+    /// Fail unless the caller is an administrator of the platform rather than
+    /// of a tenant.
+    pub fn require_platform_admin(&self) -> Result<(), InternalError> {
+        self.require_admin()?;
+        match self.tenant_id {
+            None => Ok(()),
+            Some(_) => Err(InternalError::InvalidClaim {
+                claim: CLAIM_TENANT_ID.to_string(),
+            }),
+        }
+    }
+}
+
+impl FromRequest for Principal {
+    type Error = InternalError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Principal::authenticate(req))
+    }
+}
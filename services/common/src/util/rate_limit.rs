@@ -0,0 +1,281 @@
+use crate::{
+    client::{cache_key::CacheEntity, cache_redis::Cache},
+    error::InternalError,
+    util::{client_ip::client_ip, principal::Principal},
+};
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web,
+    Error,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use serde::Deserialize;
+use serde_aux::field_attributes::deserialize_number_from_string;
+use std::{collections::HashMap, rc::Rc, time::Duration};
+use tracing::{error, warn};
+
+// Sliding windows of the requests made to the limited routes
+const CACHE_ENTITY_HTTP_RATE: CacheEntity = CacheEntity::new("http_rate", 1);
+
+/// What the requests to a route are counted by. Requests without a verified
+/// principal or tenant, or without an email, are counted by ip.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitKey {
+    /// The address of the client, as reported by the trusted proxies.
+    Ip,
+    Principal,
+    Tenant,
+    /// The `email` query parameter, limiting the attempts on an account
+    /// whatever the ip they come from.
+    Email,
+}
+
+/// A limit of `limit` requests per `window_secs` on a route.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RouteRateLimit {
+    /// Full path of the route, e.g. `/auth/v1.0/identify`.
+    pub path: String,
+    /// Limit only this method, every method when absent.
+    pub method: Option<String>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub limit: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub window_secs: u64,
+    pub key: RateLimitKey,
+}
+
+impl RouteRateLimit {
+    fn matches(&self, req: &ServiceRequest) -> bool {
+        req.path() == self.path
+            && self
+                .method
+                .as_ref()
+                .is_none_or(|method| method.eq_ignore_ascii_case(req.method().as_str()))
+    }
+
+    fn subject(&self, req: &ServiceRequest) -> String {
+        let subject = match self.key {
+            RateLimitKey::Ip => None,
+            RateLimitKey::Principal => Principal::authenticate(req.request())
+                .ok()
+                .map(|principal| format!("principal_{}", principal.user_id)),
+            RateLimitKey::Tenant => Principal::authenticate(req.request())
+                .ok()
+                .and_then(|principal| principal.tenant_id)
+                .map(|tenant_id| format!("tenant_{tenant_id}")),
+            RateLimitKey::Email => {
+                web::Query::<HashMap<String, String>>::from_query(req.query_string())
+                    .ok()
+                    .and_then(|query| query.into_inner().remove("email"))
+                    .map(|email| format!("email_{}", email.trim().to_lowercase()))
+            }
+        };
+        subject.unwrap_or_else(|| match client_ip(req.request()) {
+            Some(ip) => format!("ip_{ip}"),
+            None => "ip_unknown".to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct HttpRateLimitSettings {
+    #[serde(default)]
+    pub routes: Vec<RouteRateLimit>,
+}
+
+/// Middleware rejecting the requests over any limit of their route with a
+/// `429 Too Many Requests`. The windows are kept in Redis so that the limits
+/// hold across replicas.
+///
+/// The limits fail open: requests are let through, and the failure logged,
+/// when Redis is unavailable, so that an outage of the cache does not take
+/// the routes down with it. The routes relying on the limits against brute
+/// force must not rely on them alone.
+pub struct RateLimiter {
+    cache: Cache,
+    routes: Rc<Vec<RouteRateLimit>>,
+}
+
+impl RateLimiter {
+    pub fn new(cache: Cache, settings: &HttpRateLimitSettings) -> Self {
+        RateLimiter {
+            cache,
+            routes: Rc::new(settings.routes.clone()),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RateLimiterMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimiterMiddleware {
+            service: Rc::new(service),
+            cache: self.cache.clone(),
+            routes: Rc::clone(&self.routes),
+        }))
+    }
+}
+
+pub struct RateLimiterMiddleware<S> {
+    service: Rc<S>,
+    cache: Cache,
+    routes: Rc<Vec<RouteRateLimit>>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimiterMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let cache = self.cache.clone();
+        let limited: Vec<_> = self
+            .routes
+            .iter()
+            .filter(|route| route.matches(&req))
+            .map(|route| {
+                let key = cache.key(CACHE_ENTITY_HTTP_RATE).id(format!(
+                    "{}_{}",
+                    route.path,
+                    route.subject(&req)
+                ));
+                (key, route.limit, Duration::from_secs(route.window_secs))
+            })
+            .collect();
+
+        Box::pin(async move {
+            for (key, limit, window) in limited {
+                match cache.sliding_window_hit(&key, limit, window).await {
+                    Ok(hit) if !hit.allowed => {
+                        warn!("Rejecting request: rate limit of {} reached", key);
+                        return Err(InternalError::RateLimitExceeded {
+                            retry_after_secs: hit.retry_after.as_secs_f64().ceil() as u64,
+                        }
+                        .into());
+                    }
+                    Ok(_) => {}
+                    Err(err) => error!("Failed to check rate limit of {}: {}", key, err),
+                }
+            }
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{access_token::AccessTokenKey, client_ip::TrustedProxies};
+    use actix_web::{http::header::AUTHORIZATION, test::TestRequest};
+    use secrecy::Secret;
+
+    fn route(key: RateLimitKey) -> RouteRateLimit {
+        RouteRateLimit {
+            path: "/auth/v1.0/verify".to_string(),
+            method: Some("post".to_string()),
+            limit: 5,
+            window_secs: 300,
+            key,
+        }
+    }
+
+    fn request(uri: &str) -> TestRequest {
+        TestRequest::post()
+            .uri(uri)
+            .peer_addr("203.0.113.7:4321".parse().unwrap())
+    }
+
+    #[test]
+    fn matches_the_path_and_the_method() {
+        let route = route(RateLimitKey::Ip);
+
+        assert!(route.matches(&request("/auth/v1.0/verify?id=1").to_srv_request()));
+        assert!(!route.matches(&request("/auth/v1.0/identify").to_srv_request()));
+        assert!(!route.matches(&TestRequest::get().uri("/auth/v1.0/verify").to_srv_request()));
+    }
+
+    #[test]
+    fn counts_by_the_peer_unless_forwarded_by_trusted_proxies() {
+        let route = route(RateLimitKey::Ip);
+        let spoofed = request("/auth/v1.0/verify")
+            .insert_header(("x-forwarded-for", "192.0.2.1"))
+            .to_srv_request();
+        let proxied = request("/auth/v1.0/verify")
+            .insert_header(("x-forwarded-for", "192.0.2.1"))
+            .app_data(TrustedProxies {
+                addresses: vec!["203.0.113.7".parse().unwrap()],
+            })
+            .to_srv_request();
+
+        assert_eq!(route.subject(&spoofed), "ip_203.0.113.7");
+        assert_eq!(route.subject(&proxied), "ip_192.0.2.1");
+    }
+
+    #[test]
+    fn counts_by_email_else_ip() {
+        let route = route(RateLimitKey::Email);
+
+        assert_eq!(
+            route.subject(&request("/auth/v1.0/verify?email=Jane%40Example.com").to_srv_request()),
+            "email_jane@example.com"
+        );
+        assert_eq!(
+            route.subject(&request("/auth/v1.0/verify").to_srv_request()),
+            "ip_203.0.113.7"
+        );
+    }
+
+    #[test]
+    fn counts_by_verified_principal_else_ip() {
+        let key = AccessTokenKey::new(Secret::new("secret".to_string()));
+        let principal = Principal {
+            user_id: uuid::Uuid::new_v4(),
+            tenant_id: None,
+            role: None,
+            email: None,
+        };
+        let token = key.issue(&principal, chrono::Duration::minutes(5)).unwrap();
+        let route = route(RateLimitKey::Principal);
+
+        let authenticated = request("/auth/v1.0/verify")
+            .insert_header((AUTHORIZATION, format!("Bearer {token}")))
+            .app_data(key.clone())
+            .to_srv_request();
+        let forged = request("/auth/v1.0/verify")
+            .insert_header((AUTHORIZATION, "Bearer forged.token"))
+            .app_data(key)
+            .to_srv_request();
+
+        assert_eq!(
+            route.subject(&authenticated),
+            format!("principal_{}", principal.user_id)
+        );
+        assert_eq!(route.subject(&forged), "ip_203.0.113.7");
+        // a principal without a tenant is counted by ip on the tenant routes
+        assert_eq!(
+            RouteRateLimit {
+                key: RateLimitKey::Tenant,
+                ..route
+            }
+            .subject(&authenticated),
+            "ip_203.0.113.7"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{error::InternalError, util::leader_election::LeadershipChanged};
+use actix::{Actor, ActorFutureExt, AsyncContext, Context, Handler, WrapFuture};
+use chrono::{DateTime, Duration, Utc};
+use futures::future::LocalBoxFuture;
+use serde::Deserialize;
+use serde_aux::field_attributes::deserialize_number_from_string;
+use tracing::{error, info};
+
+/// How long soft deleted entities are kept before being purged for good.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RetentionSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub purge_after_days: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub poll_interval_secs: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub batch_size: i64,
+}
+
+/// Purges up to a batch of the entities soft deleted before a time, returning
+/// the number purged.
+pub type PurgeJob =
+    Box<dyn Fn(DateTime<Utc>, i64) -> LocalBoxFuture<'static, Result<u64, InternalError>>>;
+
+/// Runs the `PurgeJob` of a service periodically, on the replica leading the
+/// `LeaderElection` it subscribes to only.
+pub struct RetentionPurger {
+    settings: RetentionSettings,
+    purge: PurgeJob,
+    leading: bool,
+    purging: bool,
+}
+
+impl RetentionPurger {
+    pub fn new(settings: RetentionSettings, purge: PurgeJob) -> Self {
+        RetentionPurger {
+            settings,
+            purge,
+            leading: false,
+            purging: false,
+        }
+    }
+
+    fn run(&mut self, ctx: &mut Context<Self>) {
+        // a slow batch must not overlap with the next one
+        if !self.leading || self.purging {
+            return;
+        }
+        self.purging = true;
+
+        let deleted_before = Utc::now() - Duration::days(self.settings.purge_after_days);
+        let batch = (self.purge)(deleted_before, self.settings.batch_size);
+
+        ctx.spawn(batch.into_actor(self).map(|result, act, _| {
+            act.purging = false;
+            match result {
+                Ok(0) => {}
+                Ok(purged) => info!("Purged {} soft deleted entities", purged),
+                Err(err) => error!("Failed to purge soft deleted entities: {}", err),
+            }
+        }));
+    }
+}
+
+impl Actor for RetentionPurger {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let poll_interval = std::time::Duration::from_secs(self.settings.poll_interval_secs);
+        ctx.run_interval(poll_interval, |act, ctx| act.run(ctx));
+    }
+}
+
+impl Handler<LeadershipChanged> for RetentionPurger {
+    type Result = ();
+
+    fn handle(&mut self, msg: LeadershipChanged, _: &mut Context<Self>) -> Self::Result {
+        self.leading = msg.fencing_token.is_some();
+    }
+}
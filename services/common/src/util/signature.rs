@@ -0,0 +1,26 @@
+use base64::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Compute the HMAC-SHA256 signature of `payload` and return it base64url
+/// encoded (no padding), ready to be embedded in urls or http headers.
+pub fn sign(key: &[u8], payload: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(payload);
+    base64::encode_config(mac.finalize().into_bytes(), URL_SAFE_NO_PAD)
+}
+
+/// Verify a base64url encoded signature produced by [`sign`]. The comparison
+/// is done in constant time.
+pub fn verify(key: &[u8], payload: &[u8], signature: &str) -> bool {
+    let signature = match base64::decode_config(signature, URL_SAFE_NO_PAD) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(payload);
+    mac.verify_slice(&signature).is_ok()
+}
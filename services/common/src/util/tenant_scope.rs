@@ -0,0 +1,75 @@
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use futures::future::{ready, Ready};
+use uuid::Uuid;
+
+use super::principal::{Principal, CLAIM_TENANT_ID};
+use crate::error::InternalError;
+
+/// The tenant whose data a request reads and writes, resolved from the tenant
+/// of its verified principal, so that a caller acting for a tenant never
+/// reaches the data of another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TenantScope {
+    /// Across the tenants, for the administrators not acting for a tenant.
+    Platform,
+    Tenant(Uuid),
+}
+
+impl TenantScope {
+    /// The scope of `principal`: its tenant, else the whole platform for an
+    /// administrator. Other callers must act for a tenant.
+    pub fn of(principal: &Principal) -> Result<Self, InternalError> {
+        match principal.tenant_id {
+            Some(tenant_id) => Ok(TenantScope::Tenant(tenant_id)),
+            None => principal
+                .require_admin()
+                .map(|_| TenantScope::Platform)
+                .map_err(|_| InternalError::InvalidClaim {
+                    claim: CLAIM_TENANT_ID.to_string(),
+                }),
+        }
+    }
+
+    pub fn tenant_id(&self) -> Option<Uuid> {
+        match self {
+            TenantScope::Platform => None,
+            TenantScope::Tenant(tenant_id) => Some(*tenant_id),
+        }
+    }
+
+    /// Whether the data of `tenant_id` is within this scope.
+    pub fn covers(&self, tenant_id: Uuid) -> bool {
+        match self {
+            TenantScope::Platform => true,
+            TenantScope::Tenant(scope) => *scope == tenant_id,
+        }
+    }
+
+    /// The tenant owning an entity created in this scope for the tenant
+    /// `requested`, if any: the tenant of the scope, or the requested one
+    /// across the platform. A tenant cannot create entities for another.
+    pub fn owner(&self, requested: Option<Uuid>) -> Result<Uuid, InternalError> {
+        match (self, requested) {
+            (TenantScope::Tenant(tenant_id), None) => Ok(*tenant_id),
+            (TenantScope::Tenant(tenant_id), Some(requested)) if requested == *tenant_id => {
+                Ok(*tenant_id)
+            }
+            (TenantScope::Tenant(_), Some(_)) => Err(InternalError::InvalidClaim {
+                claim: CLAIM_TENANT_ID.to_string(),
+            }),
+            (TenantScope::Platform, Some(requested)) => Ok(requested),
+            (TenantScope::Platform, None) => Err(InternalError::RequestFormatError {
+                reason: "require fields: `TENANT_ID`".to_string(),
+            }),
+        }
+    }
+}
+
+impl FromRequest for TenantScope {
+    type Error = InternalError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Principal::authenticate(req).and_then(|principal| Self::of(&principal)))
+    }
+}
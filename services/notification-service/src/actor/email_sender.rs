@@ -1,30 +1,131 @@
 use std::sync::Arc;
 
 use actix::{prelude::*, Actor};
-use common::model::event::v1::auth::SendOtpMessage;
-use lettre::{Message as LettreMessage, SmtpTransport, Transport};
+use lettre::{
+    message::header::{Header, HeaderName, HeaderValue},
+    Message as LettreMessage,
+    SmtpTransport,
+    Transport,
+};
+
+use crate::{
+    context::AppContext,
+    model::domain::{
+        notification::EmailNotification,
+        suppression::{Suppression, SuppressionReason},
+    },
+    repository::suppression_repository,
+};
+use common::error::InternalError;
+use tracing::error;
 
 /// Email Sender
 pub struct EmailSender {
+    pub context: Arc<AppContext>,
     pub smtp_mailer: Arc<SmtpTransport>,
 }
 impl Actor for EmailSender {
     type Context = Context<Self>;
 }
 
-impl Handler<SendOtpMessage> for EmailSender {
+impl Handler<EmailNotification> for EmailSender {
     type Result = Result<(), std::io::Error>;
-    fn handle(&mut self, msg: SendOtpMessage, ctx: &mut Self::Context) -> Self::Result {
-        let email = LettreMessage::builder()
-            .from(msg.from.parse().unwrap())
-            .to(msg.to.parse().unwrap())
-            .subject(msg.sub)
-            .body(msg.body)
-            .unwrap();
+    fn handle(&mut self, msg: EmailNotification, ctx: &mut Self::Context) -> Self::Result {
+        let to = msg.to.clone();
+        let email = match build_email(msg) {
+            Ok(email) => email,
+            Err(err) => {
+                error!("Failed to build email to {}: {}", to, err);
+                return Ok(());
+            }
+        };
 
         // #TODO return response and error
-        self.smtp_mailer.send(&email);
+        if let Err(err) = self.smtp_mailer.send(&email) {
+            error!("Failed to send email to {}: {}", to, err);
+
+            // a permanent rejection is a hard bounce: stop emailing the address
+            if err.is_permanent() {
+                let context = Arc::clone(&self.context);
+                let suppression =
+                    Suppression::new(&to, SuppressionReason::HardBounce, Some(err.to_string()));
+                actix::spawn(async move {
+                    if let Err(err) =
+                        suppression_repository::upsert(&suppression, context.db()).await
+                    {
+                        error!("Failed to suppress {}: {}", suppression.email, err);
+                    }
+                });
+            }
+        }
 
         Ok(())
     }
 }
+
+/// The email of `msg`, failing on malformed addresses rather than panicking
+/// the sender.
+fn build_email(msg: EmailNotification) -> Result<LettreMessage, InternalError> {
+    let invalid = |err: &dyn std::fmt::Display| InternalError::InvalidFormatError {
+        cause: err.to_string(),
+    };
+    let builder = LettreMessage::builder()
+        .from(msg.from.parse().map_err(|err| invalid(&err))?)
+        .to(msg.to.parse().map_err(|err| invalid(&err))?)
+        .subject(msg.subject);
+
+    match msg.unsubscribe_url {
+        Some(url) => builder
+            .header(ListUnsubscribe(url.clone()))
+            .header(ListUnsubscribePost)
+            .body(format!(
+                "{}\n\n--\nTo stop receiving these emails, unsubscribe here: {}",
+                msg.body, url
+            )),
+        None => builder.body(msg.body),
+    }
+    .map_err(|err| invalid(&err))
+}
+
+/// `List-Unsubscribe` header (RFC 2369) pointing at the signed unsubscribe
+/// link.
+#[derive(Clone)]
+struct ListUnsubscribe(String);
+
+impl Header for ListUnsubscribe {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe")
+    }
+
+    fn parse(s: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(ListUnsubscribe(
+            s.trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .into(),
+        ))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), format!("<{}>", self.0))
+    }
+}
+
+/// `List-Unsubscribe-Post` header (RFC 8058) advertising one-click
+/// unsubscription.
+#[derive(Clone)]
+struct ListUnsubscribePost;
+
+impl Header for ListUnsubscribePost {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe-Post")
+    }
+
+    fn parse(_: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(ListUnsubscribePost)
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), "List-Unsubscribe=One-Click".into())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::context::AppContext;
+use crate::{context::AppContext, model::domain::notification::EmailNotification};
 use actix::{Actor, Context, Handler, Recipient};
 use common::model::event::{
     v1::{auth::SendOtpMessage, Event},
@@ -9,14 +9,14 @@ use tracing::info;
 
 pub struct EventStreamHandler {
     pub context: Arc<AppContext>,
-    pub email_sender: Recipient<SendOtpMessage>,
+    pub dispatcher: Recipient<EmailNotification>,
 }
 
 impl EventStreamHandler {
     // Define handler for `SendOtp` message
     fn process_send_otp(&self, event_message: EventMessage<SendOtpMessage>) {
         info!("Processing SendOtp command...: {:?}", event_message);
-        self.email_sender.do_send(event_message.payload);
+        self.dispatcher.do_send(event_message.payload.into());
     }
 }
 
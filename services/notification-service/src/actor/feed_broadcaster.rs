@@ -0,0 +1,86 @@
+use std::{collections::HashMap, time::Duration};
+
+use actix::{prelude::*, Actor};
+use actix_web::web::Bytes;
+use bson::Uuid;
+use futures::channel::mpsc::UnboundedSender;
+
+use crate::model::domain::feed::InAppNotification;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Format a Server-Sent Events frame.
+pub fn sse_event(event: &str, data: &str) -> Bytes {
+    Bytes::from(format!("event: {event}\ndata: {data}\n\n"))
+}
+
+/// Register a live SSE connection of a user acting for the tenant
+/// `tenant_id`.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Connect {
+    pub user_id: Uuid,
+    pub tenant_id: Option<Uuid>,
+    pub sender: UnboundedSender<Bytes>,
+}
+
+/// Push a notification to the live connections of its recipient held by this
+/// replica.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Broadcast(pub InAppNotification);
+
+/// Keeps track of the SSE connections opened against this replica. The
+/// notifications are fanned out to every replica through Redis pub/sub, each
+/// one delivering to the connections it holds.
+#[derive(Default)]
+pub struct FeedBroadcaster {
+    sessions: HashMap<Uuid, Vec<(Option<Uuid>, UnboundedSender<Bytes>)>>,
+}
+
+impl FeedBroadcaster {
+    /// Send a comment frame to every connection to keep proxies from timing
+    /// out idle streams, dropping the connections which have been closed.
+    fn heartbeat(&mut self) {
+        let frame = Bytes::from_static(b": heartbeat\n\n");
+        self.sessions.retain(|_, senders| {
+            senders.retain(|(_, sender)| sender.unbounded_send(frame.clone()).is_ok());
+            !senders.is_empty()
+        });
+    }
+}
+
+impl Actor for FeedBroadcaster {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, _| act.heartbeat());
+    }
+}
+
+impl Handler<Connect> for FeedBroadcaster {
+    type Result = ();
+
+    fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> Self::Result {
+        self.sessions
+            .entry(msg.user_id)
+            .or_insert_with(Vec::new)
+            .push((msg.tenant_id, msg.sender));
+    }
+}
+
+impl Handler<Broadcast> for FeedBroadcaster {
+    type Result = ();
+
+    fn handle(&mut self, msg: Broadcast, _: &mut Context<Self>) -> Self::Result {
+        let Broadcast(notification) = msg;
+
+        if let Some(senders) = self.sessions.get_mut(&notification.recipient_id) {
+            let frame = sse_event("notification", &notification.to_string());
+            senders.retain(|(tenant_id, sender)| {
+                !notification.is_visible_in(*tenant_id)
+                    || sender.unbounded_send(frame.clone()).is_ok()
+            });
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod email_sender;
 pub mod event_stream_handler;
+pub mod feed_broadcaster;
+pub mod notification_dispatcher;
+pub mod notification_scheduler;
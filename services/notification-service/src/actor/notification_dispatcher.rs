@@ -0,0 +1,173 @@
+use crate::{
+    context::AppContext,
+    model::domain::{
+        notification::{EmailNotification, NotificationChannel, NotificationPriority},
+        preference::{DeliveryDecision, NotificationPreference},
+        schedule::ScheduledNotification,
+        unsubscribe::UnsubscribeToken,
+    },
+    repository::{preference_repository, schedule_repository, suppression_repository},
+    settings::{RateLimitSettings, WindowLimit},
+};
+use actix::{Actor, Context, Handler, Recipient};
+use chrono::Utc;
+use common::{client::cache_key::CacheEntity, error::InternalError};
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info, warn};
+
+/// Enforces the recipient preferences before handing emails over to the
+/// `EmailSender`: opted out notifications are dropped, the ones falling into
+/// quiet hours are rescheduled and low priority ones are queued into digests.
+/// Suppressed addresses and send limits are enforced for every notification,
+/// mandatory ones included.
+pub struct NotificationDispatcher {
+    pub context: Arc<AppContext>,
+    pub email_sender: Recipient<EmailNotification>,
+    pub rate_limits: RateLimitSettings,
+}
+
+impl Actor for NotificationDispatcher {
+    type Context = Context<Self>;
+}
+
+impl Handler<EmailNotification> for NotificationDispatcher {
+    type Result = Result<(), std::io::Error>;
+
+    fn handle(&mut self, notification: EmailNotification, _: &mut Context<Self>) -> Self::Result {
+        let context = Arc::clone(&self.context);
+        let email_sender = self.email_sender.clone();
+        let rate_limits = self.rate_limits.clone();
+
+        actix::spawn(async move {
+            if let Err(err) = dispatch(&context, &email_sender, &rate_limits, notification).await {
+                error!("Failed to dispatch email: {}", err);
+            }
+        });
+
+        Ok(())
+    }
+}
+
+async fn dispatch(
+    ctx: &AppContext,
+    email_sender: &Recipient<EmailNotification>,
+    rate_limits: &RateLimitSettings,
+    mut notification: EmailNotification,
+) -> Result<(), InternalError> {
+    if suppression_repository::is_suppressed(&notification.to, ctx.db()).await? {
+        info!(
+            "Skipping {} email to {}: address is suppressed",
+            notification.category, notification.to
+        );
+        return Ok(());
+    }
+
+    if notification.category.is_mandatory() {
+        if within_limits(ctx, rate_limits, &notification).await? {
+            email_sender.do_send(notification);
+        }
+        return Ok(());
+    }
+
+    let preference = preference_repository::find_by_email(&notification.to, ctx.db())
+        .await?
+        .unwrap_or_else(|| NotificationPreference::new(&notification.to));
+
+    match preference.delivery(
+        NotificationChannel::Email,
+        notification.category,
+        Utc::now(),
+    ) {
+        DeliveryDecision::Send => match (notification.priority, preference.digest) {
+            (NotificationPriority::Low, Some(frequency)) => {
+                let scheduled = ScheduledNotification::digest(notification, frequency);
+                schedule_repository::insert_one(&scheduled, ctx.db()).await?;
+            }
+            _ => {
+                let token = UnsubscribeToken {
+                    email: notification.to.clone(),
+                    channel: NotificationChannel::Email,
+                    category: notification.category,
+                }
+                .sign(ctx.unsubscribe_signing_key())?;
+                notification.unsubscribe_url =
+                    Some(format!("{}?token={}", ctx.unsubscribe_base_url(), token));
+                if within_limits(ctx, rate_limits, &notification).await? {
+                    email_sender.do_send(notification);
+                }
+            }
+        },
+        DeliveryDecision::QuietHours { until } => {
+            info!(
+                "Deferring {} email to {} until {}",
+                notification.category, notification.to, until
+            );
+            let scheduled = ScheduledNotification::new(notification, until);
+            schedule_repository::insert_one(&scheduled, ctx.db()).await?;
+        }
+        DeliveryDecision::OptedOut => {
+            info!(
+                "Skipping {} email to {}: recipient opted out",
+                notification.category, notification.to
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// Sliding windows of the emails sent per recipient and per tenant
+const CACHE_ENTITY_RATE_RECIPIENT: CacheEntity = CacheEntity::new("rate_recipient", 1);
+const CACHE_ENTITY_RATE_TENANT: CacheEntity = CacheEntity::new("rate_tenant", 1);
+
+/// Record the send against the recipient and tenant windows of its category,
+/// checking both before recording it in either. Notifications over a limit are
+/// dropped.
+async fn within_limits(
+    ctx: &AppContext,
+    rate_limits: &RateLimitSettings,
+    notification: &EmailNotification,
+) -> Result<bool, InternalError> {
+    let category = notification.category.to_string().to_lowercase();
+
+    let recipient = ctx
+        .cache()
+        .key(CACHE_ENTITY_RATE_RECIPIENT)
+        .id(format!("{category}_{}", notification.to.to_lowercase()));
+    let mut windows = vec![(
+        "recipient",
+        recipient,
+        rate_limits.recipient.get(notification.category),
+    )];
+    if let Some(tenant_id) = &notification.tenant_id {
+        let tenant = ctx
+            .cache()
+            .key(CACHE_ENTITY_RATE_TENANT)
+            .tenant(tenant_id)
+            .id(&category);
+        windows.push((
+            "tenant",
+            tenant,
+            rate_limits.tenant.get(notification.category),
+        ));
+    }
+    let windows: Vec<(&str, &str, WindowLimit)> = windows
+        .iter()
+        .filter_map(|(name, key, limit)| limit.map(|limit| (*name, key.as_str(), limit)))
+        .collect();
+
+    let hits: Vec<(&str, u64, Duration)> = windows
+        .iter()
+        .map(|(_, key, limit)| (*key, limit.limit, Duration::from_secs(limit.window_secs)))
+        .collect();
+    match ctx.cache().sliding_windows_hit(&hits).await? {
+        Some(rejection) => {
+            warn!(
+                "Dropping {} email to {}: {} send limit reached",
+                notification.category, notification.to, windows[rejection.index].0
+            );
+            Ok(false)
+        }
+        None => Ok(true),
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    context::AppContext,
+    model::domain::{notification::EmailNotification, schedule::render_digest},
+    repository::{schedule_repository, suppression_repository},
+};
+use actix::{Actor, AsyncContext, Context, Recipient};
+use chrono::Utc;
+use common::error::InternalError;
+use std::{sync::Arc, time::Duration};
+use tracing::error;
+
+/// Polls the scheduled notifications collection and releases the due ones:
+/// single notifications go back through the `NotificationDispatcher`, digest
+/// entries are aggregated per recipient and sent straight away.
+pub struct NotificationScheduler {
+    pub context: Arc<AppContext>,
+    pub dispatcher: Recipient<EmailNotification>,
+    pub email_sender: Recipient<EmailNotification>,
+    pub poll_interval: Duration,
+    pub batch_size: usize,
+    pub lease: chrono::Duration,
+}
+
+impl Actor for NotificationScheduler {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(self.poll_interval, |act, _| {
+            let context = Arc::clone(&act.context);
+            let dispatcher = act.dispatcher.clone();
+            let email_sender = act.email_sender.clone();
+            let batch_size = act.batch_size;
+            let lease = act.lease;
+
+            actix::spawn(async move {
+                if let Err(err) =
+                    release_due(&context, &dispatcher, &email_sender, batch_size, lease).await
+                {
+                    error!("Failed to release scheduled notifications: {}", err);
+                }
+            });
+        });
+    }
+}
+
+async fn release_due(
+    ctx: &AppContext,
+    dispatcher: &Recipient<EmailNotification>,
+    email_sender: &Recipient<EmailNotification>,
+    batch_size: usize,
+    lease: chrono::Duration,
+) -> Result<(), InternalError> {
+    for _ in 0..batch_size {
+        let now = Utc::now();
+        let claim_id = bson::Uuid::new();
+
+        let scheduled =
+            match schedule_repository::claim_next_due(&claim_id, now, now + lease, ctx.db()).await?
+            {
+                Some(scheduled) => scheduled,
+                None => break,
+            };
+
+        match scheduled.digest {
+            Some(frequency) => {
+                let entries = schedule_repository::claim_digest(
+                    &claim_id,
+                    &scheduled.recipient,
+                    frequency,
+                    now,
+                    now + lease,
+                    ctx.db(),
+                )
+                .await?;
+                let notifications = entries
+                    .into_iter()
+                    .map(|entry| entry.notification)
+                    .collect();
+                if let Some(digest) = render_digest(frequency, notifications) {
+                    if !suppression_repository::is_suppressed(&digest.to, ctx.db()).await? {
+                        email_sender.do_send(digest);
+                    }
+                }
+            }
+            None => dispatcher.do_send(scheduled.notification),
+        }
+
+        schedule_repository::mark_sent(&claim_id, ctx.db()).await?;
+    }
+
+    Ok(())
+}
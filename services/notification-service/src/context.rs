@@ -1,12 +1,52 @@
+use actix::Addr;
 use common::client::cache_redis::Cache;
+use mongodb::Database;
+use secrecy::Secret;
 use std::sync::Arc;
+
+use crate::actor::feed_broadcaster::FeedBroadcaster;
+
+/// The AppContext contains all the global data commonly used in the vast
+/// majority of request handlers.
 #[derive(Debug)]
 pub struct AppContext {
+    pub(crate) db: Arc<Database>,
     pub(crate) cache: Arc<Cache>,
+    pub(crate) feed_broadcaster: Addr<FeedBroadcaster>,
+    pub(crate) unsubscribe_base_url: String,
+    pub(crate) unsubscribe_signing_key: Secret<String>,
+    pub(crate) schedule_senders: Vec<String>,
 }
 
 impl AppContext {
+    /// A MongoDB reference to the underlying database. Used to interract with
+    /// collections, etc.
+    pub fn db(&self) -> &Database {
+        &self.db
+    }
+
+    /// A Redis cache pool.
     pub fn cache(&self) -> &Cache {
         &self.cache
     }
+
+    /// Live in-app feed connections held by this replica.
+    pub fn feed_broadcaster(&self) -> &Addr<FeedBroadcaster> {
+        &self.feed_broadcaster
+    }
+
+    /// Url of the unsubscribe endpoint advertised in emails.
+    pub fn unsubscribe_base_url(&self) -> &str {
+        &self.unsubscribe_base_url
+    }
+
+    /// Key used to sign and verify unsubscribe tokens.
+    pub fn unsubscribe_signing_key(&self) -> &Secret<String> {
+        &self.unsubscribe_signing_key
+    }
+
+    /// Addresses the scheduled notifications may be sent from.
+    pub fn schedule_senders(&self) -> &[String] {
+        &self.schedule_senders
+    }
 }
@@ -1,5 +1,9 @@
 mod health_controller;
-mod router;
 mod notification_controller;
+mod preference_controller;
+mod router;
+mod schedule_controller;
+mod suppression_controller;
+mod unsubscribe_controller;
 
 pub use router::global_router;
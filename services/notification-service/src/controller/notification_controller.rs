@@ -1,30 +1,174 @@
 use actix_web::{
+    http::header,
     web::{self},
     HttpResponse,
     Scope,
 };
-
+use bson::Uuid;
 use chrono::Utc;
-use common::error::{ApiResult, InternalError};
+use common::{
+    error::{ApiResult, InternalError},
+    model::request::page_request::PageRequest,
+    util::{principal::Principal, tenant_scope::TenantScope},
+};
+use futures::{channel::mpsc, StreamExt};
+use serde_json::json;
+use validator::Validate;
 
-use crate::{context::AppContext, repository::notification_repository};
+use crate::{
+    actor::feed_broadcaster::{sse_event, Connect},
+    context::AppContext,
+    model::{
+        domain::feed::{prelude::CHANNEL_IN_APP_FEED, InAppNotification},
+        request::feed_request::{CreateInAppNotification, FeedQuery, MarkRead},
+    },
+    repository::notification_repository,
+};
 
 pub fn router() -> Scope {
-    web::scope("notification").service(
-        web::resource("")
-            .route(web::get().to(query))
-            .route(web::post().to(create)),
+    web::scope("notification")
+        .service(
+            web::resource("")
+                .route(web::get().to(query))
+                .route(web::post().to(create)),
+        )
+        .service(web::resource("/stream").route(web::get().to(stream)))
+        .service(web::resource("/unread_count").route(web::get().to(unread_count)))
+        .service(web::resource("/read").route(web::put().to(mark_read)))
+}
+
+/// Http handler for querying the in-app notifications of the caller.
+#[tracing::instrument(name = "query", skip(feed_query, page_request), level = "info")]
+pub async fn query(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(feed_query): web::Query<FeedQuery>,
+    web::Query(page_request): web::Query<PageRequest>,
+) -> ApiResult {
+    page_request.validate()?;
+
+    let notifications = notification_repository::find_paginated_by_recipient(
+        &Uuid::from_uuid_0_8(principal.user_id),
+        principal.tenant_id.map(Uuid::from_uuid_0_8).as_ref(),
+        feed_query.unread,
+        &page_request,
+        ctx.db(),
     )
+    .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(notifications))
 }
 
-/// Http handler for querying a resource.
-#[tracing::instrument(name = "query", level = "info")]
-pub async fn query(ctx: web::Data<AppContext>) -> ApiResult {
-    todo!()
+/// Http handler for creating an in-app notification. The notification is
+/// persisted and then fanned out to the replicas holding a live connection of
+/// the recipient. Only administrators send in-app notifications, shown to the
+/// recipient if of their tenant.
+#[tracing::instrument(name = "create", skip(principal, request), level = "info")]
+pub async fn create(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    request: web::Json<CreateInAppNotification>,
+) -> ApiResult {
+    principal.require_admin()?;
+    request.validate()?;
+    let request = request.into_inner();
+
+    let tenant_id = match (TenantScope::of(&principal)?, request.tenant_id) {
+        (TenantScope::Platform, requested) => requested,
+        (scope, requested) => Some(Uuid::from_uuid_0_8(
+            scope.owner(requested.map(|tenant_id| tenant_id.to_uuid_0_8()))?,
+        )),
+    };
+
+    let notification = InAppNotification {
+        id: Uuid::new(),
+        recipient_id: request
+            .recipient_id
+            .ok_or(InternalError::RequestFormatError {
+                reason: "require fields: `RECIPIENT_ID`".to_string(),
+            })?,
+        tenant_id,
+        title: request.title.unwrap_or_default(),
+        body: request.body.unwrap_or_default(),
+        category: request.category.ok_or(InternalError::RequestFormatError {
+            reason: "require fields: `CATEGORY`".to_string(),
+        })?,
+        read_at: None,
+        created_at: Utc::now(),
+    };
+
+    notification_repository::insert_one(&notification, ctx.db()).await?;
+    ctx.cache()
+        .publish(CHANNEL_IN_APP_FEED, &notification.to_string())
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(notification))
 }
 
-/// Http handler for creating a resource.
-#[tracing::instrument(name = "create", level = "info")]
-pub async fn create(ctx: web::Data<AppContext>) -> ApiResult {
-    todo!()
+/// Http handler streaming the in-app notifications of the caller as
+/// Server-Sent Events. The current unread count is sent upon connection.
+#[tracing::instrument(name = "stream", level = "info")]
+pub async fn stream(ctx: web::Data<AppContext>, principal: Principal) -> ApiResult {
+    let user_id = Uuid::from_uuid_0_8(principal.user_id);
+    let tenant_id = principal.tenant_id.map(Uuid::from_uuid_0_8);
+    let unread =
+        notification_repository::count_unread(&user_id, tenant_id.as_ref(), ctx.db()).await?;
+
+    let (sender, receiver) = mpsc::unbounded();
+    sender
+        .unbounded_send(sse_event(
+            "unread_count",
+            &json!({ "count": unread }).to_string(),
+        ))
+        .map_err(|err| InternalError::SendNotificationError {
+            cause: err.to_string(),
+        })?;
+    ctx.feed_broadcaster().do_send(Connect {
+        user_id,
+        tenant_id,
+        sender,
+    });
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, "text/event-stream"))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(receiver.map(Ok::<_, InternalError>)))
+}
+
+/// Http handler for the number of unread in-app notifications of the caller.
+#[tracing::instrument(name = "unread_count", level = "info")]
+pub async fn unread_count(ctx: web::Data<AppContext>, principal: Principal) -> ApiResult {
+    let unread = notification_repository::count_unread(
+        &Uuid::from_uuid_0_8(principal.user_id),
+        principal.tenant_id.map(Uuid::from_uuid_0_8).as_ref(),
+        ctx.db(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(json!({ "count": unread })))
+}
+
+/// Http handler for marking one, or all, in-app notifications of the caller as
+/// read.
+#[tracing::instrument(name = "mark_read", skip(mark_read), level = "info")]
+pub async fn mark_read(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(mark_read): web::Query<MarkRead>,
+) -> ApiResult {
+    let _: u64 = notification_repository::mark_read(
+        &Uuid::from_uuid_0_8(principal.user_id),
+        principal.tenant_id.map(Uuid::from_uuid_0_8).as_ref(),
+        mark_read.id.as_ref(),
+        ctx.db(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().finish())
 }
@@ -0,0 +1,80 @@
+use actix_web::{
+    web::{self},
+    HttpResponse,
+    Scope,
+};
+use chrono::Utc;
+use common::{
+    error::{ApiResult, InternalError},
+    util::principal::Principal,
+};
+use validator::Validate;
+
+use crate::{
+    context::AppContext,
+    model::{
+        domain::preference::NotificationPreference,
+        request::preference_request::PreferenceQuery,
+    },
+    repository::preference_repository,
+};
+
+pub fn router() -> Scope {
+    web::scope("/preference").service(
+        web::resource("")
+            .route(web::get().to(query))
+            .route(web::put().to(update)),
+    )
+}
+
+/// Http handler for querying the notification preferences of a recipient.
+/// Recipients who never changed their preferences get the defaults. Callers
+/// only query their own preferences, unless administrators of the platform.
+#[tracing::instrument(name = "query", skip(principal, query), level = "info")]
+pub async fn query(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(query): web::Query<PreferenceQuery>,
+) -> ApiResult {
+    query.validate()?;
+
+    let email = query.email.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `EMAIL`".to_string(),
+    })?;
+    principal.require_email_or_platform_admin(&email)?;
+
+    let preference = preference_repository::find_by_email(&email, ctx.db())
+        .await?
+        .unwrap_or_else(|| NotificationPreference::new(&email));
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(preference))
+}
+
+/// Http handler for replacing the notification preferences of a recipient,
+/// with the restrictions of [`query`]. Recipients without an account opt out
+/// through the signed links of their emails instead.
+#[tracing::instrument(name = "update", skip(principal, preference), level = "info")]
+pub async fn update(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    preference: web::Json<NotificationPreference>,
+) -> ApiResult {
+    PreferenceQuery {
+        email: preference.email.clone(),
+    }
+    .validate()?;
+    principal.require_email_or_platform_admin(preference.email.as_deref().unwrap_or_default())?;
+
+    let to_update = NotificationPreference {
+        updated_at: Some(Utc::now()),
+        ..preference.0
+    };
+
+    preference_repository::upsert(&to_update, ctx.db()).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(to_update))
+}
@@ -2,5 +2,9 @@ pub fn global_router(cfg: &mut actix_web::web::ServiceConfig) {
     use super::*;
 
     cfg.service(notification_controller::router());
+    cfg.service(preference_controller::router());
+    cfg.service(schedule_controller::router());
+    cfg.service(suppression_controller::router());
+    cfg.service(unsubscribe_controller::router());
     cfg.service(health_controller::router());
 }
@@ -0,0 +1,145 @@
+use actix_web::{
+    web::{self},
+    HttpResponse,
+    Scope,
+};
+use bson::Uuid;
+use chrono::Utc;
+use common::{
+    error::{ApiResult, InternalError},
+    util::{
+        principal::{Principal, CLAIM_TENANT_ID},
+        tenant_scope::TenantScope,
+    },
+};
+use validator::Validate;
+
+use crate::{
+    context::AppContext,
+    model::{
+        domain::schedule::ScheduledNotification,
+        request::schedule_request::{ScheduleNotification, ScheduleQuery},
+    },
+    repository::schedule_repository,
+};
+
+pub fn router() -> Scope {
+    web::scope("/schedule").service(
+        web::resource("")
+            .route(web::get().to(query))
+            .route(web::post().to(create))
+            .route(web::delete().to(cancel)),
+    )
+}
+
+/// Http handler for querying a scheduled notification. Administrators acting
+/// for a tenant only find the notifications of their tenant.
+#[tracing::instrument(name = "query", skip(query), level = "info")]
+pub async fn query(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(query): web::Query<ScheduleQuery>,
+) -> ApiResult {
+    principal.require_admin()?;
+    let id = required_id(query)?;
+
+    let scheduled = find_in_scope(&ctx, &TenantScope::of(&principal)?, &id).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(scheduled))
+}
+
+/// Http handler for scheduling a notification to be sent at a future time,
+/// for administrators only. The notification is sent for the tenant of the
+/// caller, from one of the configured senders.
+#[tracing::instrument(name = "create", skip(request), level = "info")]
+pub async fn create(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Json(mut request): web::Json<ScheduleNotification>,
+) -> ApiResult {
+    principal.require_admin()?;
+    request.validate()?;
+    if request.send_at <= Utc::now() {
+        return Err(InternalError::ParameterValidationError {
+            cause: "`send_at` must be in the future".to_string(),
+        });
+    }
+    if !ctx
+        .schedule_senders()
+        .iter()
+        .any(|sender| sender.eq_ignore_ascii_case(&request.from))
+    {
+        return Err(InternalError::ParameterValidationError {
+            cause: "`from` is not an allowed sender".to_string(),
+        });
+    }
+
+    let scope = TenantScope::of(&principal)?;
+    // security notifications bypass the preferences of the recipients, they
+    // are not sent for a tenant
+    if request.category.is_mandatory() && scope != TenantScope::Platform {
+        return Err(InternalError::InvalidClaim {
+            claim: CLAIM_TENANT_ID.to_string(),
+        });
+    }
+    if scope != TenantScope::Platform {
+        let requested = request.tenant_id.map(|tenant_id| tenant_id.to_uuid_0_8());
+        request.tenant_id = Some(Uuid::from_uuid_0_8(scope.owner(requested)?));
+    }
+
+    let send_at = request.send_at;
+    let scheduled = ScheduledNotification::new(request.into(), send_at);
+    schedule_repository::insert_one(&scheduled, ctx.db()).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(scheduled))
+}
+
+/// Http handler for cancelling a pending scheduled notification, among those
+/// the caller can query.
+#[tracing::instrument(name = "cancel", skip(query), level = "info")]
+pub async fn cancel(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(query): web::Query<ScheduleQuery>,
+) -> ApiResult {
+    principal.require_admin()?;
+    let id = required_id(query)?;
+
+    find_in_scope(&ctx, &TenantScope::of(&principal)?, &id).await?;
+    match schedule_repository::cancel(&id, ctx.db()).await? {
+        0 => Err(InternalError::NotificationNotFound {
+            notification_id: id.to_uuid_0_8(),
+        }),
+        _ => Ok(HttpResponse::Ok().finish()),
+    }
+}
+
+fn required_id(query: ScheduleQuery) -> Result<Uuid, InternalError> {
+    query.validate()?;
+    query.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })
+}
+
+// The scheduled notification `id`, unless sent for another tenant than that
+// of `scope`
+async fn find_in_scope(
+    ctx: &AppContext,
+    scope: &TenantScope,
+    id: &Uuid,
+) -> Result<ScheduledNotification, InternalError> {
+    schedule_repository::find_by_id(id, ctx.db())
+        .await?
+        .filter(|scheduled| match scope.tenant_id() {
+            Some(tenant_id) => {
+                scheduled.notification.tenant_id.as_deref() == Some(tenant_id.to_string().as_str())
+            }
+            None => true,
+        })
+        .ok_or(InternalError::NotificationNotFound {
+            notification_id: id.to_uuid_0_8(),
+        })
+}
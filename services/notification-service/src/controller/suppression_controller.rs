@@ -0,0 +1,101 @@
+use actix_web::{
+    web::{self},
+    HttpResponse,
+    Scope,
+};
+use common::{
+    error::{ApiResult, InternalError},
+    util::principal::Principal,
+};
+use validator::Validate;
+
+use crate::{
+    context::AppContext,
+    model::{
+        domain::suppression::Suppression,
+        request::suppression_request::{CreateSuppression, SuppressionQuery},
+    },
+    repository::suppression_repository,
+};
+
+pub fn router() -> Scope {
+    web::scope("/suppression").service(
+        web::resource("")
+            .route(web::get().to(query))
+            .route(web::post().to(create))
+            .route(web::delete().to(delete_by_email)),
+    )
+}
+
+/// Http handler for querying the suppression list, or a single address. The
+/// suppression list spans the tenants, and is only managed by the
+/// administrators of the platform.
+#[tracing::instrument(name = "query", skip(principal, query), level = "info")]
+pub async fn query(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(query): web::Query<SuppressionQuery>,
+) -> ApiResult {
+    principal.require_platform_admin()?;
+    query.validate()?;
+
+    match query.email {
+        Some(email) => {
+            let suppression = suppression_repository::find_by_email(&email, ctx.db()).await?;
+            Ok(HttpResponse::Ok()
+                .content_type("application/json")
+                .json(suppression))
+        }
+        None => {
+            let suppressions = suppression_repository::find_all(ctx.db()).await?;
+            Ok(HttpResponse::Ok()
+                .content_type("application/json")
+                .json(suppressions))
+        }
+    }
+}
+
+/// Http handler for suppressing an address, e.g. from a bounce or complaint
+/// callback of the email provider.
+#[tracing::instrument(name = "create", skip(principal, request), level = "info")]
+pub async fn create(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    request: web::Json<CreateSuppression>,
+) -> ApiResult {
+    principal.require_platform_admin()?;
+    request.validate()?;
+    let request = request.into_inner();
+
+    let email = request.email.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `EMAIL`".to_string(),
+    })?;
+    let reason = request.reason.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `REASON`".to_string(),
+    })?;
+
+    let suppression = Suppression::new(&email, reason, request.detail);
+    suppression_repository::upsert(&suppression, ctx.db()).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(suppression))
+}
+
+/// Http handler for lifting the suppression of an address.
+#[tracing::instrument(name = "delete_by_email", skip(principal, query), level = "info")]
+pub async fn delete_by_email(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(query): web::Query<SuppressionQuery>,
+) -> ApiResult {
+    principal.require_platform_admin()?;
+    query.validate()?;
+
+    let email = query.email.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `EMAIL`".to_string(),
+    })?;
+
+    let _: u64 = suppression_repository::delete_by_email(&email, ctx.db()).await?;
+    Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,87 @@
+use actix_web::{
+    http::header::ContentType,
+    web::{self},
+    HttpResponse,
+    Scope,
+};
+use common::error::{ApiResult, InternalError};
+use validator::Validate;
+
+use crate::{
+    context::AppContext,
+    model::{domain::unsubscribe::UnsubscribeToken, request::preference_request::Unsubscribe},
+    repository::preference_repository,
+};
+
+pub fn router() -> Scope {
+    web::scope("/unsubscribe").service(
+        web::resource("")
+            // the link only asks for a confirmation, as mail scanners follow it
+            .route(web::get().to(confirm_unsubscribe))
+            // the confirmation, or the RFC 8058 one-click unsubscribe of the
+            // mail clients posting `List-Unsubscribe=One-Click` to the link
+            .route(web::post().to(unsubscribe)),
+    )
+}
+
+/// Http handler for the signed unsubscribe links embedded in emails, showing
+/// the form confirming the unsubscription.
+#[tracing::instrument(name = "confirm_unsubscribe", skip(unsubscribe), level = "info")]
+pub async fn confirm_unsubscribe(
+    ctx: web::Data<AppContext>,
+    web::Query(unsubscribe): web::Query<Unsubscribe>,
+) -> ApiResult {
+    let (token, claims) = verify(&ctx, unsubscribe)?;
+
+    // a verified token only holds base64url characters and a dot
+    Ok(html(format!(
+        "<p>Stop receiving {} emails at {}?</p><form method=\"post\" \
+         action=\"?token={token}\"><input type=\"hidden\" name=\"List-Unsubscribe\" \
+         value=\"One-Click\"><button type=\"submit\">Unsubscribe</button></form>",
+        claims.category.to_string().to_lowercase(),
+        escape_html(&claims.email)
+    )))
+}
+
+/// Http handler unsubscribing the recipient of a signed unsubscribe link.
+#[tracing::instrument(name = "unsubscribe", skip(unsubscribe), level = "info")]
+pub async fn unsubscribe(
+    ctx: web::Data<AppContext>,
+    web::Query(unsubscribe): web::Query<Unsubscribe>,
+) -> ApiResult {
+    let (_, token) = verify(&ctx, unsubscribe)?;
+
+    preference_repository::opt_out(&token.email, token.channel, token.category, ctx.db()).await?;
+
+    Ok(html("<p>You have been unsubscribed.</p>".to_string()))
+}
+
+fn verify(
+    ctx: &AppContext,
+    unsubscribe: Unsubscribe,
+) -> Result<(String, UnsubscribeToken), InternalError> {
+    unsubscribe.validate()?;
+
+    let token = unsubscribe.token.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `token`".to_string(),
+    })?;
+    let claims = UnsubscribeToken::verify(&token, ctx.unsubscribe_signing_key())?;
+    Ok((token, claims))
+}
+
+fn html(body: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            "<!DOCTYPE html><html><head><meta \
+             charset=\"utf-8\"><title>Unsubscribe</title></head><body>{body}</body></html>"
+        ))
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -10,26 +10,41 @@ use crate::{context::AppContext, settings::Settings};
 use actix::Actor;
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 use actix_web_opentelemetry::RequestTracing;
-use actor::{email_sender::EmailSender, event_stream_handler::EventStreamHandler};
+use actor::{
+    email_sender::EmailSender,
+    event_stream_handler::EventStreamHandler,
+    feed_broadcaster::{Broadcast, FeedBroadcaster},
+    notification_dispatcher::NotificationDispatcher,
+    notification_scheduler::NotificationScheduler,
+};
 use common::{
-    client::cache_redis::{self, Cache, CachePool},
-    error::REDACTED_ERRORS,
+    client::{
+        cache_redis::{self, Cache, CachePool},
+        db_mongo,
+    },
+    error::{InternalError, REDACTED_ERRORS},
     model::event::v1::{auth::prelude::SERVICE_AUTH_SUBJECT, Event},
-    util::{actix_json_config::json_extractor_config, telemetry},
+    util::{access_token::AccessTokenKey, actix_json_config::json_extractor_config, telemetry},
 };
+use futures::StreamExt;
 use lettre::{
     transport::smtp::{authentication::Credentials, PoolConfig},
     SmtpTransport,
 };
+use model::domain::feed::{prelude::CHANNEL_IN_APP_FEED, InAppNotification};
 use nats_actor::subscriber::{NatsStreamMessage, NatsSubscriberConfig};
 use secrecy::ExposeSecret;
 use secrets::Secrets;
-use std::sync::Arc;
-use tracing::info;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info};
 use tracing_actix_web::TracingLogger;
 
 use nats_actor::{subscriber::subscribe_to_nats, NatsClientSettings};
 
+// Bounds of the delay before subscribing again to the in-app feed
+const FEED_RESUBSCRIBE_MIN_DELAY: Duration = Duration::from_secs(1);
+const FEED_RESUBSCRIBE_MAX_DELAY: Duration = Duration::from_secs(60);
+
 pub async fn server() -> Result<(), std::io::Error> {
     // configure tracing subscriber
     let app_name = concat!(env!("CARGO_PKG_NAME"), "-", env!("CARGO_PKG_VERSION")).to_string();
@@ -62,9 +77,19 @@ pub async fn start_web_service(
     );
 
     let secrets: Secrets = secrets::read(&configuration).await?;
+    // Verifies the access tokens asserting the principal of the requests
+    let access_token_key = AccessTokenKey::new(secrets.access_token.signing_key.clone());
+
+    let db_client = db_mongo::connect(app_name, &configuration.db, &secrets.db)
+        .await
+        .expect("db client connection failure");
+    db_mongo::migrate(&db_client, &repository::migrations::SCHEMA)
+        .await
+        .expect("db schema migration failure");
 
-    let cache_pool: CachePool = cache_redis::connect(&configuration.cache, &secrets.cache)?;
-    let cache_client: Cache = cache_redis::Cache::new(cache_pool);
+    let cache_pool: CachePool = cache_redis::connect(&configuration.cache, &secrets.cache).await?;
+    let cache_client: Cache = cache_redis::Cache::new(cache_pool, env!("CARGO_PKG_NAME"));
+    let pubsub_client = cache_redis::connect_pubsub(&configuration.cache, &secrets.cache).await?;
 
     // Open a remote connection pool to SMTP server
     let smtp_mailer = SmtpTransport::relay(&configuration.smtp.server)
@@ -81,23 +106,87 @@ pub async fn start_web_service(
         )
         .build();
 
-    // Start mail sendor actor
-    let email_sender = EmailSender {
-        smtp_mailer: Arc::new(smtp_mailer),
-    }
-    .start()
-    .recipient();
+    // Start the broadcaster actor holding the live in-app feed connections and
+    // feed it with the notifications fanned out through Redis pub/sub
+    let feed_broadcaster = FeedBroadcaster::default().start();
+    let broadcaster = feed_broadcaster.clone();
+    // The subscription is renewed, with a growing delay, whenever it cannot be
+    // set up or is lost
+    actix::spawn(async move {
+        let mut retry_delay = FEED_RESUBSCRIBE_MIN_DELAY;
+        loop {
+            match cache_redis::subscribe(&pubsub_client, CHANNEL_IN_APP_FEED).await {
+                Ok(mut messages) => {
+                    retry_delay = FEED_RESUBSCRIBE_MIN_DELAY;
+                    while let Some(msg) = messages.next().await {
+                        match msg
+                            .get_payload::<String>()
+                            .map_err(InternalError::from)
+                            .and_then(|payload| {
+                                Ok(serde_json::from_str::<InAppNotification>(&payload)?)
+                            }) {
+                            Ok(notification) => broadcaster.do_send(Broadcast(notification)),
+                            Err(err) => error!("Failed to read in-app notification: {}", err),
+                        }
+                    }
+                    error!(
+                        "In-app feed subscription closed, resubscribing in {:?}",
+                        retry_delay
+                    );
+                }
+                Err(err) => error!(
+                    "Failed to subscribe to the in-app feed, retrying in {:?}: {}",
+                    retry_delay, err
+                ),
+            }
+            actix::clock::sleep(retry_delay).await;
+            retry_delay = (retry_delay * 2).min(FEED_RESUBSCRIBE_MAX_DELAY);
+        }
+    });
 
     // Instantiate the application context. This application state will be
     // cloned for each Actix thread but the Arc of the DbContext will be
     // reused in each Actix thread.
     let app_context = web::Data::new(AppContext {
+        db: Arc::new(db_client),
         cache: Arc::new(cache_client),
+        feed_broadcaster,
+        unsubscribe_base_url: configuration.unsubscribe.base_url.clone(),
+        unsubscribe_signing_key: secrets.unsubscribe.signing_key,
+        schedule_senders: configuration.scheduler.senders.clone(),
     });
 
-    let nats_stream_handler = EventStreamHandler {
+    // Start mail sendor actor
+    let email_sender = EmailSender {
         context: Arc::clone(&app_context),
+        smtp_mailer: Arc::new(smtp_mailer),
+    }
+    .start()
+    .recipient();
+
+    // Start the dispatcher actor enforcing recipient preferences
+    let dispatcher = NotificationDispatcher {
+        context: Arc::clone(&app_context),
+        email_sender: email_sender.clone(),
+        rate_limits: configuration.rate_limit.clone(),
+    }
+    .start()
+    .recipient();
+
+    // Start the scheduler actor releasing scheduled notifications and digests
+    NotificationScheduler {
+        context: Arc::clone(&app_context),
+        dispatcher: dispatcher.clone(),
         email_sender,
+        poll_interval: Duration::from_secs(configuration.scheduler.poll_interval_secs),
+        batch_size: configuration.scheduler.batch_size,
+        lease: chrono::Duration::seconds(configuration.scheduler.lease_secs as i64),
+    }
+    .start();
+
+    let nats_stream_handler = EventStreamHandler {
+        context: Arc::clone(&app_context),
+        dispatcher,
     }
     .start();
 
@@ -129,6 +218,7 @@ pub async fn start_web_service(
     let server = HttpServer::new(move || {
         App::new()
             .app_data(app_context.clone())
+            .app_data(access_token_key.clone())
             .app_data(json_extractor_config(4096))
             .wrap(TracingLogger::default())
             .wrap(RequestTracing::new())
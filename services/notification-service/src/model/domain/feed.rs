@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::{fmt, fmt::Formatter};
+
+use super::notification::NotificationCategory;
+
+pub mod prelude {
+    // Collection name
+    pub const COLLECTION_NOTIFICATIONS: &str = "notifications";
+
+    // In-app notification fields.
+    pub const ID: &str = "_id";
+    pub const RECIPIENT_ID: &str = "RECIPIENT_ID";
+    pub const TENANT_ID: &str = "TENANT_ID";
+    pub const TITLE: &str = "TITLE";
+    pub const BODY: &str = "BODY";
+    pub const CATEGORY: &str = "CATEGORY";
+    pub const READ_AT: &str = "READ_AT";
+    pub const CREATED_AT: &str = "CREATED_AT";
+
+    // Redis pub/sub channel used to fan out in-app notifications to every
+    // replica holding a live connection of the recipient.
+    pub const CHANNEL_IN_APP_FEED: &str = "notification.in_app_feed";
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InAppNotification {
+    #[serde(rename = "_id")]
+    pub id: bson::Uuid,
+    #[serde(rename = "RECIPIENT_ID")]
+    pub recipient_id: bson::Uuid,
+    // the tenant the notification was sent in, none when sent by the platform
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: Option<bson::Uuid>,
+    #[serde(rename = "TITLE")]
+    pub title: String,
+    #[serde(rename = "BODY")]
+    pub body: String,
+    #[serde(rename = "CATEGORY")]
+    pub category: NotificationCategory,
+    #[serde(rename = "READ_AT")]
+    pub read_at: Option<DateTime<Utc>>,
+    #[serde(rename = "CREATED_AT")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl InAppNotification {
+    /// Whether the notification is shown to its recipient when acting for the
+    /// tenant `tenant_id`.
+    pub fn is_visible_in(&self, tenant_id: Option<bson::Uuid>) -> bool {
+        self.tenant_id.is_none() || self.tenant_id == tenant_id
+    }
+}
+
+impl fmt::Display for InAppNotification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", serde_json::to_string(&self).unwrap())
+    }
+}
@@ -1 +1,6 @@
-
+pub mod feed;
+pub mod notification;
+pub mod preference;
+pub mod schedule;
+pub mod suppression;
+pub mod unsubscribe;
@@ -0,0 +1,76 @@
+use actix::Message;
+use common::model::event::v1::auth::SendOtpMessage;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug, Display, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum NotificationChannel {
+    Email,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug, Display, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum NotificationCategory {
+    Security,
+    Account,
+    Product,
+    Marketing,
+}
+
+impl NotificationCategory {
+    /// Security critical notifications (e.g. OTP codes) are always delivered,
+    /// whatever the recipient preferences are.
+    pub fn is_mandatory(&self) -> bool {
+        matches!(self, NotificationCategory::Security)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug, Display, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum NotificationPriority {
+    High,
+    Normal,
+    Low,
+}
+
+impl Default for NotificationPriority {
+    fn default() -> Self {
+        NotificationPriority::Normal
+    }
+}
+
+/// A rendered email ready to be handed over to the `EmailSender`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Message)]
+#[rtype(result = "Result<(), std::io::Error>")]
+pub struct EmailNotification {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    pub category: NotificationCategory,
+    /// Low priority notifications are batched into digests when the
+    /// recipient asked for it.
+    #[serde(default)]
+    pub priority: NotificationPriority,
+    pub unsubscribe_url: Option<String>,
+    /// Tenant on behalf of which the notification is sent, used to enforce
+    /// the per-tenant send limits.
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+}
+
+impl From<SendOtpMessage> for EmailNotification {
+    fn from(msg: SendOtpMessage) -> Self {
+        EmailNotification {
+            from: msg.from,
+            to: msg.to,
+            subject: msg.sub,
+            body: msg.body,
+            category: NotificationCategory::Security,
+            priority: NotificationPriority::High,
+            unsubscribe_url: None,
+            tenant_id: msg.tenant_id,
+        }
+    }
+}
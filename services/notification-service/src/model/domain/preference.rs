@@ -0,0 +1,188 @@
+use chrono::{DateTime, Duration, NaiveTime, Timelike, Utc};
+use mongodb::bson::Bson;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::{fmt, fmt::Formatter};
+use strum::{Display, EnumString};
+
+use super::notification::{NotificationCategory, NotificationChannel};
+
+pub mod prelude {
+    // Collection name
+    pub const COLLECTION_PREFERENCES: &str = "notification_preferences";
+
+    // Preference fields.
+    pub const EMAIL: &str = "EMAIL";
+    pub const CHANNELS: &str = "CHANNELS";
+    pub const QUIET_HOURS: &str = "QUIET_HOURS";
+    pub const DIGEST: &str = "DIGEST";
+    pub const UPDATED_AT: &str = "UPDATED_AT";
+}
+
+/// Outcome of checking a notification against the recipient preferences.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DeliveryDecision {
+    Send,
+    OptedOut,
+    QuietHours { until: DateTime<Utc> },
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Display, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum DigestFrequency {
+    Hourly,
+    Daily,
+}
+
+impl DigestFrequency {
+    /// Start of the next digest window (UTC) following `at`.
+    pub fn next_window(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            DigestFrequency::Hourly => at.date().and_hms(at.hour(), 0, 0) + Duration::hours(1),
+            DigestFrequency::Daily => at.date().and_hms(0, 0, 0) + Duration::days(1),
+        }
+    }
+}
+
+impl From<DigestFrequency> for Bson {
+    fn from(frequency: DigestFrequency) -> Self {
+        match frequency {
+            DigestFrequency::Hourly => Bson::String("Hourly".to_string()),
+            DigestFrequency::Daily => Bson::String("Daily".to_string()),
+        }
+    }
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChannelPreference {
+    #[serde(rename = "CHANNEL")]
+    pub channel: NotificationChannel,
+    #[serde(rename = "ENABLED", default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(rename = "OPTED_OUT_CATEGORIES", default)]
+    pub opted_out_categories: Vec<NotificationCategory>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Daily time window during which non mandatory notifications are held back.
+/// The window may wrap around midnight, e.g. 22:00 - 07:00.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuietHours {
+    #[serde(rename = "START")]
+    pub start: NaiveTime,
+    #[serde(rename = "END")]
+    pub end: NaiveTime,
+    #[serde(rename = "UTC_OFFSET_MINUTES", default)]
+    pub utc_offset_minutes: i32,
+}
+
+impl QuietHours {
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let local = (at + Duration::minutes(self.utc_offset_minutes.into())).time();
+        if self.start <= self.end {
+            self.start <= local && local < self.end
+        } else {
+            self.start <= local || local < self.end
+        }
+    }
+
+    /// End of the quiet hours window following `at`.
+    pub fn end_after(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let offset = Duration::minutes(self.utc_offset_minutes.into());
+        let local = at + offset;
+
+        let mut end = local.date().and_time(self.end).unwrap_or(local);
+        if end <= local {
+            end = end + Duration::days(1);
+        }
+        end - offset
+    }
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationPreference {
+    #[serde(rename = "EMAIL")]
+    pub email: Option<String>,
+    #[serde(rename = "CHANNELS")]
+    pub channels: Option<Vec<ChannelPreference>>,
+    #[serde(rename = "QUIET_HOURS")]
+    pub quiet_hours: Option<QuietHours>,
+    #[serde(rename = "DIGEST")]
+    pub digest: Option<DigestFrequency>,
+    // #[serde_as(as = "Option<bson::DateTime>")]
+    #[serde(rename = "UPDATED_AT")]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl NotificationPreference {
+    /// Default preferences of a recipient who never changed them: every
+    /// channel and category is enabled.
+    pub fn new(email: &str) -> Self {
+        NotificationPreference {
+            email: Some(email.to_string()),
+            channels: None,
+            quiet_hours: None,
+            digest: None,
+            updated_at: None,
+        }
+    }
+
+    pub fn delivery(
+        &self,
+        channel: NotificationChannel,
+        category: NotificationCategory,
+        at: DateTime<Utc>,
+    ) -> DeliveryDecision {
+        if category.is_mandatory() {
+            return DeliveryDecision::Send;
+        }
+
+        let channel_preference = self
+            .channels
+            .iter()
+            .flatten()
+            .find(|preference| preference.channel == channel);
+        if let Some(preference) = channel_preference {
+            if !preference.enabled || preference.opted_out_categories.contains(&category) {
+                return DeliveryDecision::OptedOut;
+            }
+        }
+
+        match &self.quiet_hours {
+            Some(quiet_hours) if quiet_hours.contains(at) => DeliveryDecision::QuietHours {
+                until: quiet_hours.end_after(at),
+            },
+            _ => DeliveryDecision::Send,
+        }
+    }
+
+    pub fn opt_out(&mut self, channel: NotificationChannel, category: NotificationCategory) {
+        let channels = self.channels.get_or_insert_with(Vec::new);
+        match channels
+            .iter_mut()
+            .find(|preference| preference.channel == channel)
+        {
+            Some(preference) => {
+                if !preference.opted_out_categories.contains(&category) {
+                    preference.opted_out_categories.push(category);
+                }
+            }
+            None => channels.push(ChannelPreference {
+                channel,
+                enabled: true,
+                opted_out_categories: vec![category],
+            }),
+        }
+    }
+}
+
+impl fmt::Display for NotificationPreference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", serde_json::to_string(&self).unwrap())
+    }
+}
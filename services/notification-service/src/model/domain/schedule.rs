@@ -0,0 +1,132 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, serde_helpers::chrono_datetime_as_bson_datetime, Bson};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::{fmt, fmt::Formatter};
+use strum::{Display, EnumString};
+
+use super::{
+    notification::{EmailNotification, NotificationPriority},
+    preference::DigestFrequency,
+};
+
+pub mod prelude {
+    // Collection name
+    pub const COLLECTION_SCHEDULED_NOTIFICATIONS: &str = "scheduled_notifications";
+
+    // Scheduled notification fields.
+    pub const ID: &str = "_id";
+    pub const RECIPIENT: &str = "RECIPIENT";
+    pub const NOTIFICATION: &str = "NOTIFICATION";
+    pub const SEND_AT: &str = "SEND_AT";
+    pub const DIGEST: &str = "DIGEST";
+    pub const STATUS: &str = "STATUS";
+    pub const CLAIM_ID: &str = "CLAIM_ID";
+    pub const CREATED_AT: &str = "CREATED_AT";
+    pub const UPDATED_AT: &str = "UPDATED_AT";
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Display, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum ScheduleStatus {
+    Pending,
+    Processing,
+    Sent,
+    Cancelled,
+}
+
+impl From<ScheduleStatus> for Bson {
+    fn from(status: ScheduleStatus) -> Self {
+        match status {
+            ScheduleStatus::Pending => Bson::String("Pending".to_string()),
+            ScheduleStatus::Processing => Bson::String("Processing".to_string()),
+            ScheduleStatus::Sent => Bson::String("Sent".to_string()),
+            ScheduleStatus::Cancelled => Bson::String("Cancelled".to_string()),
+        }
+    }
+}
+
+/// A notification waiting in the scheduler queue, either to be sent at a
+/// given time or to be aggregated into the next digest of its recipient.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledNotification {
+    #[serde(rename = "_id")]
+    pub id: bson::Uuid,
+    #[serde(rename = "RECIPIENT")]
+    pub recipient: String,
+    #[serde(rename = "NOTIFICATION")]
+    pub notification: EmailNotification,
+    // stored as a bson date so that due entries can be range queried, and
+    // pushed back to the end of the lease while processing
+    #[serde(rename = "SEND_AT", with = "chrono_datetime_as_bson_datetime")]
+    pub send_at: DateTime<Utc>,
+    #[serde(rename = "DIGEST")]
+    pub digest: Option<DigestFrequency>,
+    #[serde(rename = "STATUS")]
+    pub status: ScheduleStatus,
+    #[serde(rename = "CLAIM_ID")]
+    pub claim_id: Option<bson::Uuid>,
+    #[serde(rename = "CREATED_AT")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "UPDATED_AT")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ScheduledNotification {
+    pub fn new(notification: EmailNotification, send_at: DateTime<Utc>) -> Self {
+        let now = Utc::now();
+        ScheduledNotification {
+            id: bson::Uuid::new(),
+            recipient: notification.to.clone(),
+            notification,
+            send_at,
+            digest: None,
+            status: ScheduleStatus::Pending,
+            claim_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Queue the notification into the next digest window of its recipient.
+    pub fn digest(notification: EmailNotification, frequency: DigestFrequency) -> Self {
+        let now = Utc::now();
+        ScheduledNotification {
+            digest: Some(frequency),
+            ..ScheduledNotification::new(notification, frequency.next_window(now))
+        }
+    }
+}
+
+impl fmt::Display for ScheduledNotification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", serde_json::to_string(&self).unwrap())
+    }
+}
+
+/// Aggregate the notifications of a single recipient into one digest email.
+pub fn render_digest(
+    frequency: DigestFrequency,
+    notifications: Vec<EmailNotification>,
+) -> Option<EmailNotification> {
+    let first = notifications.first()?.clone();
+    let subject = format!(
+        "Your {} digest: {} new notification(s)",
+        frequency.to_string().to_lowercase(),
+        notifications.len()
+    );
+    let body = notifications
+        .iter()
+        .map(|notification| format!("{}\n\n{}", notification.subject, notification.body))
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n");
+
+    Some(EmailNotification {
+        subject,
+        body,
+        priority: NotificationPriority::Low,
+        unsubscribe_url: None,
+        ..first
+    })
+}
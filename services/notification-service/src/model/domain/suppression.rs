@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::Bson;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::{fmt, fmt::Formatter};
+use strum::{Display, EnumString};
+
+pub mod prelude {
+    // Collection name
+    pub const COLLECTION_SUPPRESSIONS: &str = "notification_suppressions";
+
+    // Suppression fields.
+    pub const EMAIL: &str = "EMAIL";
+    pub const REASON: &str = "REASON";
+    pub const DETAIL: &str = "DETAIL";
+    pub const CREATED_AT: &str = "CREATED_AT";
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Display, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum SuppressionReason {
+    HardBounce,
+    Complaint,
+    Manual,
+}
+
+impl From<SuppressionReason> for Bson {
+    fn from(reason: SuppressionReason) -> Self {
+        match reason {
+            SuppressionReason::HardBounce => Bson::String("HardBounce".to_string()),
+            SuppressionReason::Complaint => Bson::String("Complaint".to_string()),
+            SuppressionReason::Manual => Bson::String("Manual".to_string()),
+        }
+    }
+}
+
+/// An address no email is sent to anymore, e.g. because it hard-bounced.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Suppression {
+    #[serde(rename = "EMAIL")]
+    pub email: String,
+    #[serde(rename = "REASON")]
+    pub reason: SuppressionReason,
+    #[serde(rename = "DETAIL")]
+    pub detail: Option<String>,
+    #[serde(rename = "CREATED_AT")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Suppression {
+    pub fn new(email: &str, reason: SuppressionReason, detail: Option<String>) -> Self {
+        Suppression {
+            email: email.to_lowercase(),
+            reason,
+            detail,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl fmt::Display for Suppression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", serde_json::to_string(&self).unwrap())
+    }
+}
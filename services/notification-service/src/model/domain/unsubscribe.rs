@@ -0,0 +1,43 @@
+use base64::URL_SAFE_NO_PAD;
+use common::{error::InternalError, util::signature};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use super::notification::{NotificationCategory, NotificationChannel};
+
+/// Claims carried by the one-click unsubscribe links embedded in emails.
+///
+/// The token is `<base64url(json claims)>.<base64url(hmac)>` so that it can be
+/// verified without any server side state.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnsubscribeToken {
+    pub email: String,
+    pub channel: NotificationChannel,
+    pub category: NotificationCategory,
+}
+
+impl UnsubscribeToken {
+    pub fn sign(&self, key: &Secret<String>) -> Result<String, InternalError> {
+        let payload = base64::encode_config(serde_json::to_vec(self)?, URL_SAFE_NO_PAD);
+        let signature = signature::sign(key.expose_secret().as_bytes(), payload.as_bytes());
+        Ok(format!("{payload}.{signature}"))
+    }
+
+    pub fn verify(token: &str, key: &Secret<String>) -> Result<Self, InternalError> {
+        let invalid = || InternalError::InvalidClaim {
+            claim: "unsubscribe token".to_string(),
+        };
+
+        let (payload, token_signature) = token.split_once('.').ok_or_else(invalid)?;
+        if !signature::verify(
+            key.expose_secret().as_bytes(),
+            payload.as_bytes(),
+            token_signature,
+        ) {
+            return Err(invalid());
+        }
+
+        let payload = base64::decode_config(payload, URL_SAFE_NO_PAD).map_err(|_| invalid())?;
+        Ok(serde_json::from_slice(&payload)?)
+    }
+}
@@ -0,0 +1,37 @@
+use bson::Uuid;
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::model::domain::notification::NotificationCategory;
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct CreateInAppNotification {
+    #[validate(required)]
+    #[serde(rename = "RECIPIENT_ID")]
+    pub recipient_id: Option<Uuid>,
+    /// The tenant of the recipient, for the administrators of the platform.
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: Option<Uuid>,
+    #[validate(required, length(min = 1))]
+    #[serde(rename = "TITLE")]
+    pub title: Option<String>,
+    #[validate(required)]
+    #[serde(rename = "BODY")]
+    pub body: Option<String>,
+    #[validate(required)]
+    #[serde(rename = "CATEGORY")]
+    pub category: Option<NotificationCategory>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FeedQuery {
+    #[serde(default)]
+    pub unread: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MarkRead {
+    /// Marks every notification of the caller as read when omitted.
+    #[serde(rename = "_id")]
+    pub id: Option<Uuid>,
+}
@@ -1 +1,4 @@
-
+pub mod feed_request;
+pub mod preference_request;
+pub mod schedule_request;
+pub mod suppression_request;
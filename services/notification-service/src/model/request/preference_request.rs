@@ -0,0 +1,15 @@
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct PreferenceQuery {
+    #[validate(required, email(message = "email is not valid"))]
+    #[serde(rename = "EMAIL")]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct Unsubscribe {
+    #[validate(required)]
+    pub token: Option<String>,
+}
@@ -0,0 +1,56 @@
+use bson::Uuid;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::model::domain::notification::{
+    EmailNotification,
+    NotificationCategory,
+    NotificationPriority,
+};
+
+/// An email to send at `send_at`. The sender must be one of the configured
+/// senders, and only the platform administrators schedule `SECURITY`
+/// notifications, which bypass the preferences of the recipient.
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct ScheduleNotification {
+    #[validate(email(message = "from is not a valid email"))]
+    pub from: String,
+    #[validate(email(message = "to is not a valid email"))]
+    pub to: String,
+    #[validate(length(min = 1, max = 998))]
+    pub subject: String,
+    #[validate(length(min = 1, max = 100000))]
+    pub body: String,
+    pub category: NotificationCategory,
+    #[serde(default)]
+    pub priority: NotificationPriority,
+    /// The tenant the notification is sent for, that of the caller unless
+    /// scheduled by a platform administrator.
+    pub tenant_id: Option<Uuid>,
+    pub send_at: DateTime<Utc>,
+}
+
+impl From<ScheduleNotification> for EmailNotification {
+    fn from(request: ScheduleNotification) -> Self {
+        EmailNotification {
+            from: request.from,
+            to: request.to,
+            subject: request.subject,
+            body: request.body,
+            category: request.category,
+            priority: request.priority,
+            // unsubscribe links are generated when the notification is
+            // dispatched
+            unsubscribe_url: None,
+            tenant_id: request.tenant_id.map(|tenant_id| tenant_id.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct ScheduleQuery {
+    #[validate(required)]
+    #[serde(rename = "_id")]
+    pub id: Option<Uuid>,
+}
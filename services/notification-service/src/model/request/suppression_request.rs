@@ -0,0 +1,23 @@
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::model::domain::suppression::SuppressionReason;
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct SuppressionQuery {
+    #[validate(email(message = "email is not valid"))]
+    #[serde(rename = "EMAIL")]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct CreateSuppression {
+    #[validate(required, email(message = "email is not valid"))]
+    #[serde(rename = "EMAIL")]
+    pub email: Option<String>,
+    #[validate(required)]
+    #[serde(rename = "REASON")]
+    pub reason: Option<SuppressionReason>,
+    #[serde(rename = "DETAIL")]
+    pub detail: Option<String>,
+}
@@ -0,0 +1,59 @@
+use crate::model::domain::{
+    feed::prelude as feed,
+    preference::prelude as preference,
+    schedule::prelude as schedule,
+    suppression::prelude as suppression,
+};
+use common::{
+    client::db_mongo::{self, IndexDefinition, Migration, Schema},
+    error::InternalError,
+};
+use futures::future::BoxFuture;
+use mongodb::Database;
+
+pub const SCHEMA: Schema = Schema {
+    migrations: &[Migration::new(1, "create collections", create_collections)],
+    indexes: &[
+        // the feed of a recipient, most recent first
+        IndexDefinition::new(
+            feed::COLLECTION_NOTIFICATIONS,
+            "notifications_recipient_id",
+            &[(feed::RECIPIENT_ID, 1), (feed::CREATED_AT, -1)],
+        ),
+        IndexDefinition::new(
+            preference::COLLECTION_PREFERENCES,
+            "notification_preferences_email",
+            &[(preference::EMAIL, 1)],
+        )
+        .unique(),
+        IndexDefinition::new(
+            suppression::COLLECTION_SUPPRESSIONS,
+            "notification_suppressions_email",
+            &[(suppression::EMAIL, 1)],
+        )
+        .unique(),
+        // the due notifications claimed by the scheduler
+        IndexDefinition::new(
+            schedule::COLLECTION_SCHEDULED_NOTIFICATIONS,
+            "scheduled_notifications_due",
+            &[(schedule::STATUS, 1), (schedule::SEND_AT, 1)],
+        ),
+        IndexDefinition::new(
+            schedule::COLLECTION_SCHEDULED_NOTIFICATIONS,
+            "scheduled_notifications_claim_id",
+            &[(schedule::CLAIM_ID, 1)],
+        ),
+    ],
+};
+
+fn create_collections(db: &Database) -> BoxFuture<'_, Result<(), InternalError>> {
+    Box::pin(db_mongo::create_collections(
+        db,
+        &[
+            feed::COLLECTION_NOTIFICATIONS,
+            preference::COLLECTION_PREFERENCES,
+            suppression::COLLECTION_SUPPRESSIONS,
+            schedule::COLLECTION_SCHEDULED_NOTIFICATIONS,
+        ],
+    ))
+}
@@ -1 +1,5 @@
+pub mod migrations;
 pub mod notification_repository;
+pub mod preference_repository;
+pub mod schedule_repository;
+pub mod suppression_repository;
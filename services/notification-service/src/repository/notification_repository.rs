@@ -1 +1,92 @@
+use crate::model::domain::feed::{prelude::*, InAppNotification};
+use bson::{Bson, Document, Uuid};
+use chrono::Utc;
+use common::{
+    client::mongo_repository::find_page,
+    error::InternalError,
+    model::{
+        request::page_request::{PageRequest, SortOrder},
+        response::page_response::PageResponse,
+    },
+};
+use mongodb::{bson::doc, Database};
 
+// The notifications of the recipient, among those sent in its tenant or by the
+// platform
+fn recipient_filter(recipient_id: &Uuid, tenant_id: Option<&Uuid>, unread_only: bool) -> Document {
+    let mut filter = doc! {
+        RECIPIENT_ID: recipient_id,
+        TENANT_ID: { "$in": [tenant_id.copied(), Bson::Null] },
+    };
+    if unread_only {
+        filter.insert(READ_AT, doc! { "$exists": false });
+    }
+    filter
+}
+
+pub async fn insert_one(
+    notification: &InAppNotification,
+    db: &Database,
+) -> Result<(), InternalError> {
+    db.collection::<InAppNotification>(COLLECTION_NOTIFICATIONS)
+        .insert_one(notification, None)
+        .await?;
+    Ok(())
+}
+
+/// Most recent notifications of a recipient first.
+pub async fn find_paginated_by_recipient(
+    recipient_id: &Uuid,
+    tenant_id: Option<&Uuid>,
+    unread_only: bool,
+    page_request: &PageRequest,
+    db: &Database,
+) -> Result<PageResponse<InAppNotification>, InternalError> {
+    find_page(
+        &db.collection::<InAppNotification>(COLLECTION_NOTIFICATIONS),
+        recipient_filter(recipient_id, tenant_id, unread_only),
+        &SortOrder::desc(CREATED_AT),
+        page_request,
+    )
+    .await
+}
+
+pub async fn count_unread(
+    recipient_id: &Uuid,
+    tenant_id: Option<&Uuid>,
+    db: &Database,
+) -> Result<u64, InternalError> {
+    let total = db
+        .collection::<InAppNotification>(COLLECTION_NOTIFICATIONS)
+        .count_documents(recipient_filter(recipient_id, tenant_id, true), None)
+        .await?;
+    Ok(total)
+}
+
+/// Mark a single notification, or every notification when `id` is `None`,
+/// of the recipient as read.
+pub async fn mark_read(
+    recipient_id: &Uuid,
+    tenant_id: Option<&Uuid>,
+    id: Option<&Uuid>,
+    db: &Database,
+) -> Result<u64, InternalError> {
+    let mut filter = recipient_filter(recipient_id, tenant_id, true);
+    if let Some(id) = id {
+        filter.insert(ID, id);
+    }
+
+    let res = db
+        .collection::<InAppNotification>(COLLECTION_NOTIFICATIONS)
+        .update_many(
+            filter,
+            doc! {
+                "$set": {
+                    READ_AT: Utc::now().to_rfc3339(),
+                },
+            },
+            None,
+        )
+        .await?;
+    Ok(res.modified_count)
+}
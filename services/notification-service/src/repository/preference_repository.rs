@@ -0,0 +1,54 @@
+use crate::model::domain::{
+    notification::{NotificationCategory, NotificationChannel},
+    preference::{prelude::*, NotificationPreference},
+};
+use chrono::Utc;
+use common::error::InternalError;
+use mongodb::{bson::doc, options::ReplaceOptions, Database};
+
+pub async fn find_by_email(
+    email: &str,
+    db: &Database,
+) -> Result<Option<NotificationPreference>, InternalError> {
+    let filter = doc! { EMAIL: email };
+    let preference = db
+        .collection::<NotificationPreference>(COLLECTION_PREFERENCES)
+        .find_one(filter, None)
+        .await?;
+    Ok(preference)
+}
+
+pub async fn upsert(
+    preference: &NotificationPreference,
+    db: &Database,
+) -> Result<(), InternalError> {
+    let email = preference
+        .email
+        .as_ref()
+        .ok_or(InternalError::RequestFormatError {
+            reason: "require fields: `EMAIL`".to_string(),
+        })?;
+
+    let options = ReplaceOptions::builder().upsert(true).build();
+    db.collection::<NotificationPreference>(COLLECTION_PREFERENCES)
+        .replace_one(doc! { EMAIL: email }, preference, options)
+        .await?;
+    Ok(())
+}
+
+pub async fn opt_out(
+    email: &str,
+    channel: NotificationChannel,
+    category: NotificationCategory,
+    db: &Database,
+) -> Result<NotificationPreference, InternalError> {
+    let mut preference = find_by_email(email, db)
+        .await?
+        .unwrap_or_else(|| NotificationPreference::new(email));
+
+    preference.opt_out(channel, category);
+    preference.updated_at = Some(Utc::now());
+
+    upsert(&preference, db).await?;
+    Ok(preference)
+}
@@ -0,0 +1,149 @@
+use crate::model::domain::{
+    preference::DigestFrequency,
+    schedule::{prelude::*, ScheduleStatus, ScheduledNotification},
+};
+use bson::Uuid;
+use chrono::{DateTime, Utc};
+use common::error::InternalError;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    Database,
+};
+
+pub async fn find_by_id(
+    id: &Uuid,
+    db: &Database,
+) -> Result<Option<ScheduledNotification>, InternalError> {
+    let filter = doc! { ID: id };
+    let scheduled = db
+        .collection::<ScheduledNotification>(COLLECTION_SCHEDULED_NOTIFICATIONS)
+        .find_one(filter, None)
+        .await?;
+    Ok(scheduled)
+}
+
+pub async fn insert_one(
+    scheduled: &ScheduledNotification,
+    db: &Database,
+) -> Result<(), InternalError> {
+    db.collection::<ScheduledNotification>(COLLECTION_SCHEDULED_NOTIFICATIONS)
+        .insert_one(scheduled, None)
+        .await?;
+    Ok(())
+}
+
+/// Cancel a notification which has not been picked up by the scheduler yet.
+pub async fn cancel(id: &Uuid, db: &Database) -> Result<u64, InternalError> {
+    let res = db
+        .collection::<ScheduledNotification>(COLLECTION_SCHEDULED_NOTIFICATIONS)
+        .update_one(
+            doc! {
+                ID: id,
+                STATUS: ScheduleStatus::Pending,
+            },
+            doc! {
+                "$set": {
+                    STATUS: ScheduleStatus::Cancelled,
+                    UPDATED_AT: Utc::now().to_rfc3339(),
+                },
+            },
+            None,
+        )
+        .await?;
+    Ok(res.modified_count)
+}
+
+/// Atomically claim the next due notification so that it is processed by a
+/// single scheduler even when several replicas are running. The claim is a
+/// lease: a notification left processing by a crashed replica is due again
+/// once `lease_until` is reached.
+pub async fn claim_next_due(
+    claim_id: &Uuid,
+    now: DateTime<Utc>,
+    lease_until: DateTime<Utc>,
+    db: &Database,
+) -> Result<Option<ScheduledNotification>, InternalError> {
+    let options = FindOneAndUpdateOptions::builder()
+        .sort(doc! { SEND_AT: 1 })
+        .return_document(ReturnDocument::After)
+        .build();
+
+    let scheduled = db
+        .collection::<ScheduledNotification>(COLLECTION_SCHEDULED_NOTIFICATIONS)
+        .find_one_and_update(
+            doc! {
+                STATUS: { "$in": [ScheduleStatus::Pending, ScheduleStatus::Processing] },
+                SEND_AT: { "$lte": bson::DateTime::from_chrono(now) },
+            },
+            doc! {
+                "$set": {
+                    STATUS: ScheduleStatus::Processing,
+                    SEND_AT: bson::DateTime::from_chrono(lease_until),
+                    CLAIM_ID: claim_id,
+                    UPDATED_AT: now.to_rfc3339(),
+                },
+            },
+            options,
+        )
+        .await?;
+    Ok(scheduled)
+}
+
+/// Claim every due digest entry of `recipient` under `claim_id`, until
+/// `lease_until` as for the single notifications, and return all the entries
+/// of the claim.
+pub async fn claim_digest(
+    claim_id: &Uuid,
+    recipient: &str,
+    frequency: DigestFrequency,
+    now: DateTime<Utc>,
+    lease_until: DateTime<Utc>,
+    db: &Database,
+) -> Result<Vec<ScheduledNotification>, InternalError> {
+    let collection = db.collection::<ScheduledNotification>(COLLECTION_SCHEDULED_NOTIFICATIONS);
+
+    collection
+        .update_many(
+            doc! {
+                RECIPIENT: recipient,
+                DIGEST: frequency,
+                STATUS: { "$in": [ScheduleStatus::Pending, ScheduleStatus::Processing] },
+                SEND_AT: { "$lte": bson::DateTime::from_chrono(now) },
+            },
+            doc! {
+                "$set": {
+                    STATUS: ScheduleStatus::Processing,
+                    SEND_AT: bson::DateTime::from_chrono(lease_until),
+                    CLAIM_ID: claim_id,
+                    UPDATED_AT: now.to_rfc3339(),
+                },
+            },
+            None,
+        )
+        .await?;
+
+    let find_opts = FindOptions::builder().sort(doc! { CREATED_AT: 1 }).build();
+    let cursor = collection
+        .find(doc! { CLAIM_ID: claim_id }, find_opts)
+        .await?;
+    Ok(cursor.try_collect().await?)
+}
+
+pub async fn mark_sent(claim_id: &Uuid, db: &Database) -> Result<u64, InternalError> {
+    let res = db
+        .collection::<ScheduledNotification>(COLLECTION_SCHEDULED_NOTIFICATIONS)
+        .update_many(
+            doc! { CLAIM_ID: claim_id },
+            doc! {
+                "$set": {
+                    STATUS: ScheduleStatus::Sent,
+                    UPDATED_AT: Utc::now().to_rfc3339(),
+                },
+            },
+            None,
+        )
+        .await?;
+    Ok(res.modified_count)
+}
@@ -0,0 +1,50 @@
+use crate::model::domain::suppression::{prelude::*, Suppression};
+use common::error::InternalError;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::doc,
+    options::{FindOptions, ReplaceOptions},
+    Database,
+};
+
+pub async fn find_by_email(
+    email: &str,
+    db: &Database,
+) -> Result<Option<Suppression>, InternalError> {
+    let filter = doc! { EMAIL: email.to_lowercase() };
+    let suppression = db
+        .collection::<Suppression>(COLLECTION_SUPPRESSIONS)
+        .find_one(filter, None)
+        .await?;
+    Ok(suppression)
+}
+
+pub async fn is_suppressed(email: &str, db: &Database) -> Result<bool, InternalError> {
+    Ok(find_by_email(email, db).await?.is_some())
+}
+
+pub async fn find_all(db: &Database) -> Result<Vec<Suppression>, InternalError> {
+    let find_opts = FindOptions::builder().sort(doc! { CREATED_AT: -1 }).build();
+    let cursor = db
+        .collection::<Suppression>(COLLECTION_SUPPRESSIONS)
+        .find(doc! {}, find_opts)
+        .await?;
+    Ok(cursor.try_collect().await?)
+}
+
+/// Suppress an address, keeping a single entry per address.
+pub async fn upsert(suppression: &Suppression, db: &Database) -> Result<(), InternalError> {
+    let options = ReplaceOptions::builder().upsert(true).build();
+    db.collection::<Suppression>(COLLECTION_SUPPRESSIONS)
+        .replace_one(doc! { EMAIL: &suppression.email }, suppression, options)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_by_email(email: &str, db: &Database) -> Result<u64, InternalError> {
+    let res = db
+        .collection::<Suppression>(COLLECTION_SUPPRESSIONS)
+        .delete_one(doc! { EMAIL: email.to_lowercase() }, None)
+        .await?;
+    Ok(res.deleted_count)
+}
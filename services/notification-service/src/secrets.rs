@@ -1,7 +1,8 @@
 use crate::settings::Settings;
 use common::{
-    client::{cache_redis::RedisClientSecrets, sm_vault},
+    client::{cache_redis::RedisClientSecrets, db_mongo::MongoClientSecrets, sm_vault},
     error::InternalError,
+    util::access_token::AccessTokenSecrets,
 };
 use secrecy::Secret;
 use serde::Deserialize;
@@ -10,7 +11,10 @@ use vaultrs::client::VaultClient;
 #[derive(Debug, Deserialize)]
 pub struct Secrets {
     pub cache: RedisClientSecrets,
+    pub db: MongoClientSecrets,
     pub smtp: SmtpClientSecrets,
+    pub unsubscribe: UnsubscribeSecrets,
+    pub access_token: AccessTokenSecrets,
 }
 
 #[derive(Debug, Deserialize)]
@@ -19,16 +23,30 @@ pub struct SmtpClientSecrets {
     pub password: Secret<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UnsubscribeSecrets {
+    pub signing_key: Secret<String>,
+}
+
 pub async fn read(settings: &Settings) -> Result<Secrets, InternalError> {
     let vault_client: VaultClient = sm_vault::connect(&settings.vault)?;
 
     let cache_secrets: RedisClientSecrets =
         sm_vault::get_secret_value(&vault_client, &settings.cache_secrets_path).await?;
+    let db_secrets: MongoClientSecrets =
+        sm_vault::get_secret_value(&vault_client, &settings.db_secrets_path).await?;
     let smtp_secrets: SmtpClientSecrets =
         sm_vault::get_secret_value(&vault_client, &settings.smtp_secrets_path).await?;
+    let unsubscribe_secrets: UnsubscribeSecrets =
+        sm_vault::get_secret_value(&vault_client, &settings.unsubscribe_secrets_path).await?;
+    let access_token_secrets: AccessTokenSecrets =
+        sm_vault::get_secret_value(&vault_client, &settings.access_token_secrets_path).await?;
 
     Ok(Secrets {
         cache: cache_secrets,
+        db: db_secrets,
         smtp: smtp_secrets,
+        unsubscribe: unsubscribe_secrets,
+        access_token: access_token_secrets,
     })
 }
@@ -3,21 +3,31 @@ use std::time::Duration;
 use common::{
     client::{
         cache_redis::RedisClientSettings,
+        db_mongo::MongoClientSettings,
         sm_vault::{VaultClientConfig, VaultKvPath},
     },
     util::configuration,
 };
 use nats_actor::NatsClientSettings;
+
+use crate::model::domain::notification::NotificationCategory;
 use serde_aux::field_attributes::deserialize_number_from_string;
 
 #[derive(Debug, serde::Deserialize, Clone)]
 pub struct Settings {
     pub application: ApplicationSettings,
     pub vault: VaultClientConfig,
+    pub db: MongoClientSettings,
+    pub db_secrets_path: VaultKvPath,
     pub cache: RedisClientSettings,
     pub cache_secrets_path: VaultKvPath,
+    pub access_token_secrets_path: VaultKvPath,
     pub smtp: SmtpSettings,
     pub smtp_secrets_path: VaultKvPath,
+    pub unsubscribe: UnsubscribeSettings,
+    pub unsubscribe_secrets_path: VaultKvPath,
+    pub scheduler: SchedulerSettings,
+    pub rate_limit: RateLimitSettings,
     pub nats: NatsClientSettings,
     pub log: LogSettings,
     pub tracer: Tracer,
@@ -44,6 +54,64 @@ pub struct SmtpSettings {
     pub idle_timeout: Duration,
 }
 
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct UnsubscribeSettings {
+    pub base_url: String,
+}
+
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct SchedulerSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub poll_interval_secs: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub batch_size: usize,
+    /// How long a claimed notification is left to be sent before another
+    /// replica takes it over.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub lease_secs: u64,
+    /// The addresses the notifications scheduled through the api may be sent
+    /// from.
+    #[serde(default)]
+    pub senders: Vec<String>,
+}
+
+/// Send limits per template category, over a sliding window. Categories
+/// without a limit are not throttled.
+#[derive(Debug, serde::Deserialize, Clone, Default)]
+pub struct RateLimitSettings {
+    #[serde(default)]
+    pub recipient: CategoryLimits,
+    #[serde(default)]
+    pub tenant: CategoryLimits,
+}
+
+#[derive(Debug, serde::Deserialize, Clone, Default)]
+pub struct CategoryLimits {
+    pub security: Option<WindowLimit>,
+    pub account: Option<WindowLimit>,
+    pub product: Option<WindowLimit>,
+    pub marketing: Option<WindowLimit>,
+}
+
+impl CategoryLimits {
+    pub fn get(&self, category: NotificationCategory) -> Option<WindowLimit> {
+        match category {
+            NotificationCategory::Security => self.security,
+            NotificationCategory::Account => self.account,
+            NotificationCategory::Product => self.product,
+            NotificationCategory::Marketing => self.marketing,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, Clone, Copy)]
+pub struct WindowLimit {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub limit: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub window_secs: u64,
+}
+
 #[derive(Debug, serde::Deserialize, Clone)]
 pub struct LogSettings {
     pub level: String,
@@ -0,0 +1,34 @@
+-- The tenants, a column per field of the entity. Text columns compare byte
+-- wise, as strings do in Mongo, so that both stores list tenants alike. The
+-- webhook endpoints and deliveries of the tenants stay in Mongo.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE TABLE IF NOT EXISTS tenants (
+    id            UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    company_name  TEXT COLLATE "C",
+    account_name  TEXT COLLATE "C",
+    owner_name    TEXT COLLATE "C",
+    email         TEXT COLLATE "C",
+    phone         TEXT COLLATE "C",
+    status        TEXT,
+    tier          TEXT,
+    created_at    TIMESTAMPTZ,
+    updated_at    TIMESTAMPTZ,
+    deleted_at    TIMESTAMPTZ,
+    version       BIGINT NOT NULL DEFAULT 1
+);
+
+CREATE INDEX IF NOT EXISTS tenants_email ON tenants (email);
+-- the soft deleted tenants due for purge
+CREATE INDEX IF NOT EXISTS tenants_deleted_at ON tenants (deleted_at);
+
+-- the sorted listings, with the id as tie breaker
+CREATE INDEX IF NOT EXISTS tenants_email_id ON tenants (email, id);
+CREATE INDEX IF NOT EXISTS tenants_created_at_id ON tenants (created_at, id);
+CREATE INDEX IF NOT EXISTS tenants_updated_at_id ON tenants (updated_at, id);
+
+-- the search, matching regexes on the search fields
+CREATE INDEX IF NOT EXISTS tenants_search_company_name ON tenants USING gin (company_name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS tenants_search_account_name ON tenants USING gin (account_name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS tenants_search_owner_name ON tenants USING gin (owner_name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS tenants_search_email ON tenants USING gin (email gin_trgm_ops);
@@ -0,0 +1,255 @@
+use crate::{
+    context::AppContext,
+    model::domain::webhook::{
+        prelude::*,
+        resolve_public_endpoint,
+        retry_backoff,
+        signature_payload,
+        DeliveryAttempt,
+        DeliveryStatus,
+        WebhookDelivery,
+    },
+    repository::webhook_repository,
+    secrets,
+    settings::WebhookSettings,
+};
+use actix::{Actor, AsyncContext, Context, Handler};
+use awc::Client;
+use chrono::{Duration, Utc};
+use common::{
+    error::InternalError,
+    model::event::{v1::Event, EventMessage},
+    util::signature,
+};
+use futures::future::join_all;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use std::{sync::Arc, time::Instant};
+use tracing::{error, info};
+
+/// Fans the platform events out to the webhook endpoints of their tenant and
+/// attempts the due deliveries, retrying the failed ones with an exponential
+/// backoff until `max_attempts` is reached.
+pub struct WebhookDispatcher {
+    pub context: Arc<AppContext>,
+    pub client: Client,
+    pub settings: WebhookSettings,
+}
+
+impl Actor for WebhookDispatcher {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let poll_interval = std::time::Duration::from_secs(self.settings.poll_interval_secs);
+        ctx.run_interval(poll_interval, |act, _| {
+            let context = Arc::clone(&act.context);
+            let client = act.client.clone();
+            let settings = act.settings.clone();
+
+            actix::spawn(async move {
+                if let Err(err) = release_due(&context, &client, &settings).await {
+                    error!("Failed to release webhook deliveries: {}", err);
+                }
+            });
+        });
+    }
+}
+
+// Define handler for `Event` message
+impl Handler<Event> for WebhookDispatcher {
+    type Result = Result<(), std::io::Error>;
+
+    fn handle(&mut self, event: Event, _: &mut Context<Self>) -> Self::Result {
+        let context = Arc::clone(&self.context);
+
+        actix::spawn(async move {
+            if let Err(err) = fan_out(&context, event).await {
+                error!("Failed to queue webhook deliveries: {}", err);
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Queue a delivery of `event` for every endpoint of its tenant subscribed to
+/// it, provided the tenant tier still includes webhooks.
+async fn fan_out(ctx: &AppContext, event: Event) -> Result<(), InternalError> {
+    let tenant_id = match event.tenant_id() {
+        Some(tenant_id) => {
+            bson::Uuid::parse_str(tenant_id).map_err(|err| InternalError::InvalidFormatError {
+                cause: err.to_string(),
+            })?
+        }
+        None => return Ok(()),
+    };
+
+    let data = match &event {
+        Event::AuthUserCreated(EventMessage { payload, .. }) => json!(payload),
+        Event::TenantTierChanged(EventMessage { payload, .. }) => json!(payload),
+        Event::UserDeleted(EventMessage { payload, .. }) => json!(payload),
+        // a new tenant has no endpoints yet, those of a deleted tenant are gone
+        // along with it, and the audit trail is only exposed to administrators
+        Event::AuthSendOtp(_)
+        | Event::TenantCreated(_)
+        | Event::TenantDeleted(_)
+        | Event::AuditRecorded(_) => return Ok(()),
+    };
+
+    let eligible = ctx
+        .tenants()
+        .find_by_id(&tenant_id)
+        .await?
+        .and_then(|tenant| tenant.tier)
+        .map_or(false, |tier| tier.supports_webhooks());
+    if !eligible {
+        return Ok(());
+    }
+
+    let event_type = event.event_type();
+    let deliveries: Vec<WebhookDelivery> =
+        webhook_repository::find_subscribed_endpoints(&tenant_id, event_type, ctx.db())
+            .await?
+            .iter()
+            .map(|endpoint| WebhookDelivery::new(endpoint, event_type, data.clone()))
+            .collect();
+
+    info!(
+        "Queueing {} webhook deliveries of {} for tenant {}",
+        deliveries.len(),
+        event_type,
+        tenant_id
+    );
+    webhook_repository::insert_deliveries(&deliveries, ctx.db()).await
+}
+
+async fn release_due(
+    ctx: &AppContext,
+    client: &Client,
+    settings: &WebhookSettings,
+) -> Result<(), InternalError> {
+    // deliveries still in flight after twice the request timeout are
+    // considered lost and attempted again
+    let lease = Duration::seconds(2 * settings.request_timeout_secs as i64);
+
+    let mut due = vec![];
+    for _ in 0..settings.batch_size {
+        let now = Utc::now();
+        match webhook_repository::claim_next_due_delivery(now, now + lease, ctx.db()).await? {
+            Some(delivery) => due.push(delivery),
+            None => break,
+        }
+    }
+
+    for result in join_all(
+        due.iter()
+            .map(|delivery| attempt(ctx, client, settings, delivery)),
+    )
+    .await
+    {
+        if let Err(err) = result {
+            error!("Failed to record webhook delivery attempt: {}", err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Attempt `delivery` once and record the outcome in its log.
+async fn attempt(
+    ctx: &AppContext,
+    client: &Client,
+    settings: &WebhookSettings,
+    delivery: &WebhookDelivery,
+) -> Result<(), InternalError> {
+    let attempt = send(ctx, client, settings, delivery).await;
+    let attempts = delivery.attempts.len() as u32 + 1;
+    let now = Utc::now();
+
+    let (status, next_attempt_at) = if attempt.succeeded() {
+        (DeliveryStatus::Succeeded, now)
+    } else if attempts >= settings.max_attempts {
+        (DeliveryStatus::Failed, now)
+    } else {
+        let backoff = retry_backoff(
+            attempts,
+            Duration::seconds(settings.initial_backoff_secs),
+            Duration::seconds(settings.max_backoff_secs),
+        );
+        (DeliveryStatus::Pending, now + backoff)
+    };
+
+    webhook_repository::record_attempt(&delivery.id, &attempt, status, next_attempt_at, ctx.db())
+        .await?;
+    Ok(())
+}
+
+/// Sign and post the payload of `delivery` to its endpoint. Any failure is
+/// reported in the returned attempt rather than as an error so that it ends
+/// up in the delivery log.
+async fn send(
+    ctx: &AppContext,
+    client: &Client,
+    settings: &WebhookSettings,
+    delivery: &WebhookDelivery,
+) -> DeliveryAttempt {
+    let attempted_at = Utc::now();
+    let started = Instant::now();
+
+    let result: Result<u16, InternalError> = async {
+        let endpoint = webhook_repository::find_endpoint_by_id(&delivery.endpoint_id, ctx.db())
+            .await?
+            .filter(|endpoint| endpoint.active)
+            .ok_or(InternalError::WebhookEndpointNotFound {
+                endpoint_id: delivery.endpoint_id.to_uuid_0_8(),
+            })?;
+        // connect to the checked address rather than resolving the host again,
+        // which may have been rebound to an internal address since
+        let address = resolve_public_endpoint(&endpoint.url).await?[0];
+        let secrets = secrets::read_webhook(
+            ctx.vault(),
+            ctx.webhook_secrets_path(),
+            &delivery.endpoint_id,
+        )
+        .await?;
+
+        let timestamp = attempted_at.timestamp();
+        let signature = signature::sign(
+            secrets.signing_key.expose_secret().as_bytes(),
+            signature_payload(timestamp, &delivery.payload).as_bytes(),
+        );
+
+        let response = client
+            .post(&endpoint.url)
+            .address(address)
+            .timeout(std::time::Duration::from_secs(
+                settings.request_timeout_secs,
+            ))
+            .content_type("application/json")
+            .insert_header((HEADER_WEBHOOK_ID, delivery.id.to_string()))
+            .insert_header((HEADER_WEBHOOK_EVENT, delivery.event_type.as_str()))
+            .insert_header((HEADER_WEBHOOK_TIMESTAMP, timestamp.to_string()))
+            .insert_header((HEADER_WEBHOOK_SIGNATURE, format!("v1={signature}")))
+            .send_body(delivery.payload.clone())
+            .await?;
+
+        Ok(response.status().as_u16())
+    }
+    .await;
+
+    let duration_ms = started.elapsed().as_millis() as i64;
+    match result {
+        Ok(status_code) => DeliveryAttempt {
+            attempted_at,
+            status_code: Some(status_code),
+            error: None,
+            duration_ms,
+        },
+        Err(err) => DeliveryAttempt {
+            attempted_at,
+            status_code: None,
+            error: Some(err.to_string()),
+            duration_ms,
+        },
+    }
+}
@@ -1,24 +1,88 @@
-use common::client::cache_redis::Cache;
-use mongodb::Database;
+use crate::repository::tenant_repository::TenantRepository;
+use actix::Addr;
+use common::{
+    client::{
+        cache_redis::Cache,
+        sm_vault::{VaultClientConfig, VaultKvPath},
+    },
+    error::InternalError,
+    model::event::{
+        v1::{audit::AuditRecord, Event},
+        EventMessage,
+        EventMetadata,
+    },
+};
+use mongodb::{Client, Database};
+use nats_actor::{publisher::NatsPublisher, EventMessage as NatsEventMessage};
 use std::sync::Arc;
 
 /// The AppContext contains all the global data commonly used in the vast
 /// majority of request handlers.
 #[derive(Debug)]
 pub struct AppContext {
+    pub(crate) mongo_client: Arc<Client>,
     pub(crate) db: Arc<Database>,
+    pub(crate) tenants: Arc<dyn TenantRepository>,
     pub(crate) cache: Arc<Cache>,
+    pub(crate) event_publisher: Arc<Addr<NatsPublisher>>,
+    pub(crate) audit_publisher: Arc<Addr<NatsPublisher>>,
+    pub(crate) vault: VaultClientConfig,
+    pub(crate) webhook_secrets_path: VaultKvPath,
 }
 
 impl AppContext {
-    /// A MongoDB reference to the underlying database. Used to interract with
+    /// The MongoDB client the database belongs to. Used to run transactions.
+    pub fn mongo_client(&self) -> &Client {
+        &self.mongo_client
+    }
+
+    /// A MongoDB reference to the underlying database, holding the webhooks and
+    /// with the `mongo` db backend the tenants. Used to interract with
     /// collections, etc.
     pub fn db(&self) -> &Database {
         &self.db
     }
 
+    /// The store of the tenants, Mongo or Postgres.
+    pub fn tenants(&self) -> &dyn TenantRepository {
+        self.tenants.as_ref()
+    }
+
     /// A Redis cache pool.
     pub fn cache(&self) -> &Cache {
         &self.cache
     }
+
+    /// A NATS publisher for the tenant events.
+    pub fn event_publisher(&self) -> &Addr<NatsPublisher> {
+        &self.event_publisher
+    }
+
+    /// Publish `record` to the audit log, in the background. A failed publish
+    /// is retried while this instance runs, but the record is lost if it
+    /// stops first: the log is best effort, not written along with the action.
+    pub fn audit(&self, record: AuditRecord) -> Result<(), InternalError> {
+        let meta = EventMetadata::new(
+            record.source.clone(),
+            record.trace_id.as_deref().unwrap_or("trace_id"),
+        );
+        let audit_event = Event::AuditRecorded(EventMessage {
+            meta,
+            payload: record,
+        });
+        self.audit_publisher.do_send(NatsEventMessage {
+            event: audit_event.try_into()?,
+        });
+        Ok(())
+    }
+
+    /// Vault settings, used to read and write the webhook signing keys.
+    pub fn vault(&self) -> &VaultClientConfig {
+        &self.vault
+    }
+
+    /// Vault path under which a signing key is stored per webhook endpoint.
+    pub fn webhook_secrets_path(&self) -> &VaultKvPath {
+        &self.webhook_secrets_path
+    }
 }
@@ -21,18 +21,21 @@ struct Health {
 
 pub async fn health(ctx: web::Data<AppContext>) -> Result<HttpResponse, InternalError> {
     let mut health = HashMap::<&str, Health>::new();
-    health.insert("mongodb", mongo_health(&ctx).await);
+    health.insert("MongoDB", mongo_health(&ctx).await);
+    // the tenants may be kept in another store than the webhooks
+    let store = ctx.tenants().store_name();
+    if !health.contains_key(store) {
+        health.insert(store, tenants_health(&ctx).await);
+    }
 
     let status = match health.values().any(|health| !health.healthy) {
         true => StatusCode::SERVICE_UNAVAILABLE,
         false => StatusCode::OK,
     };
 
-    Ok(HttpResponseBuilder::new(status).json(json!(
-        {
-            "MongoDB": health["mongodb"],
-        }
-    )))
+    let mut body = json!(health);
+    body["cache"] = json!(ctx.cache().stats());
+    Ok(HttpResponseBuilder::new(status).json(body))
 }
 
 async fn mongo_health(ctx: &web::Data<AppContext>) -> Health {
@@ -47,3 +50,16 @@ async fn mongo_health(ctx: &web::Data<AppContext>) -> Health {
         },
     }
 }
+
+async fn tenants_health(ctx: &web::Data<AppContext>) -> Health {
+    match ctx.tenants().ping().await {
+        Err(err) => Health {
+            healthy: false,
+            message: Some(err.to_string()),
+        },
+        Ok(_) => Health {
+            healthy: true,
+            message: None,
+        },
+    }
+}
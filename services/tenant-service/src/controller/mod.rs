@@ -1,5 +1,6 @@
 mod health_controller;
 mod router;
 mod tenant_controller;
+mod webhook_controller;
 
 pub use router::global_router;
@@ -1,88 +1,182 @@
 use actix_web::{
+    http::header::IfMatch,
     web::{self},
     HttpResponse,
     Scope,
 };
 use bson::Uuid;
-use chrono::Utc;
 use common::{
+    client::db_mongo,
     error::{ApiResult, InternalError},
-    model::request::page_request::PageRequest,
+    model::{
+        event::{
+            v1::{
+                audit::prelude::{
+                    AUDIT_ACTION_TENANT_CREATE,
+                    AUDIT_ACTION_TENANT_DELETE,
+                    AUDIT_ACTION_TENANT_RESTORE,
+                    AUDIT_ACTION_TENANT_UPDATE,
+                    AUDIT_TARGET_TENANT,
+                },
+                tenant::{
+                    prelude::SERVICE_TENANT_SUBJECT,
+                    TenantCreatedMessage,
+                    TenantTierChangedMessage,
+                },
+                Event,
+            },
+            EventMessage,
+            EventMetadata,
+        },
+        request::{
+            filter_request::FilterRequest,
+            page_request::PageRequest,
+            search_request::SearchRequest,
+        },
+    },
+    util::{
+        audit::AuditActor,
+        etag::{etag, expected_version},
+        principal::Principal,
+        tenant_scope::TenantScope,
+    },
 };
+use nats_actor::EventMessage as NatsEventMessage;
 use validator::Validate;
 
 use crate::{
     context::AppContext,
-    model::domain::tenant::{
-        prelude::{CACHE_KEY_PREFIX_TENANT_ID, CACHE_TENANT_EXPIRY},
-        Tenant,
-        TenantStatus,
-        TenantTier,
+    model::{
+        domain::tenant::{
+            prelude::{CACHE_ENTITY_TENANT, CACHE_TAG_PREFIX_TENANT, CACHE_TENANT_EXPIRY},
+            Tenant,
+            TenantStatus,
+        },
+        request::tenant_request::{CreateTenant, UpdateTenant},
     },
-    repository::tenant_repository,
+    repository::webhook_repository,
 };
 
 pub fn router() -> Scope {
-    web::scope("/tenant").service(
-        web::resource("")
-            //.route(web::get().to(query))
-            .route(web::get().to(query_paginated))
-            .route(web::post().to(create))
-            .route(web::put().to(update_by_id))
-            .route(web::delete().to(delete_by_id)),
-    )
+    web::scope("/tenant")
+        .service(
+            web::resource("")
+                //.route(web::get().to(query))
+                .route(web::get().to(query_paginated))
+                .route(web::post().to(create))
+                .route(web::put().to(update_by_id))
+                .route(web::delete().to(delete_by_id)),
+        )
+        .service(web::resource("/search").route(web::get().to(search)))
+        .service(web::resource("/restore").route(web::post().to(restore_by_id)))
 }
 
-/// Http handler for querying tenants.
-#[tracing::instrument(name = "query", skip(tenant), level = "info")]
+/// Http handler for querying tenants. Callers acting for a tenant only find
+/// their own, and only the administrators of the platform list tenants.
+#[tracing::instrument(name = "query", skip(tenant, filter_request), level = "info")]
 pub async fn query(
     ctx: web::Data<AppContext>,
+    principal: Principal,
     web::Query(tenant): web::Query<Tenant>,
+    web::Query(filter_request): web::Query<FilterRequest>,
 ) -> ApiResult {
     match tenant.id {
-        Some(id) => get_by_id(ctx, &id).await,
-        None => get_by_condition(ctx, tenant).await,
+        Some(id) => get_by_id(ctx, &TenantScope::of(&principal)?, &id).await,
+        None => {
+            principal.require_platform_admin()?;
+            get_by_condition(ctx, tenant, filter_request).await
+        }
     }
 }
 
-/// Http handler for querying tenants with pagination.
-#[tracing::instrument(name = "query_paginated", skip(tenant, page_request), level = "info")]
+/// Http handler for querying tenants with pagination, with the restrictions
+/// of [`query`].
+#[tracing::instrument(
+    name = "query_paginated",
+    skip(tenant, filter_request, page_request),
+    level = "info"
+)]
 pub async fn query_paginated(
     ctx: web::Data<AppContext>,
+    principal: Principal,
     web::Query(tenant): web::Query<Tenant>,
+    web::Query(filter_request): web::Query<FilterRequest>,
     web::Query(page_request): web::Query<PageRequest>,
 ) -> ApiResult {
     match tenant.id {
-        Some(id) => get_by_id(ctx, &id).await,
-        None => get_paginated_by_condition(ctx, tenant, page_request).await,
+        Some(id) => get_by_id(ctx, &TenantScope::of(&principal)?, &id).await,
+        None => {
+            principal.require_platform_admin()?;
+            get_paginated_by_condition(ctx, tenant, filter_request, page_request).await
+        }
     }
 }
 
-async fn get_by_id(ctx: web::Data<AppContext>, id: &Uuid) -> ApiResult {
-    let cache_key_tenant_id = format!("{CACHE_KEY_PREFIX_TENANT_ID}_{id}");
-
-    match ctx.cache().get::<Tenant>(&cache_key_tenant_id).await? {
-        Some(tenant) => Ok(HttpResponse::Ok()
-            .content_type("application/json")
-            .json(tenant)),
-        None => match tenant_repository::find_by_id(id, ctx.db()).await? {
-            Some(tenant) => {
-                ctx.cache()
-                    .set::<Tenant>(&cache_key_tenant_id, tenant.clone(), CACHE_TENANT_EXPIRY)
-                    .await?;
-                Ok(HttpResponse::Ok()
-                    .content_type("application/json")
-                    .json(tenant))
-            }
-            None => Err(InternalError::TenantNotFound {
-                tenant_id: id.to_uuid_0_8(),
-            }),
-        },
+/// Http handler for searching tenants by company or account name. Callers acting for a tenant only
+/// find within it.
+#[tracing::instrument(name = "search", skip(search_request, page_request), level = "info")]
+pub async fn search(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(search_request): web::Query<SearchRequest>,
+    web::Query(page_request): web::Query<PageRequest>,
+) -> ApiResult {
+    search_request.validate()?;
+    page_request.validate()?;
+
+    let tenants = ctx
+        .tenants()
+        .search(
+            &search_request,
+            principal.tenant_id.map(Uuid::from_uuid_0_8),
+            &page_request,
+        )
+        .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(tenants))
+}
+
+async fn get_by_id(ctx: web::Data<AppContext>, scope: &TenantScope, id: &Uuid) -> ApiResult {
+    if !scope.covers(id.to_uuid_0_8()) {
+        return Err(InternalError::TenantNotFound {
+            tenant_id: id.to_uuid_0_8(),
+        });
     }
+
+    let cache_key_tenant_id = ctx.cache().key(CACHE_ENTITY_TENANT).id(id);
+    let cache_tag_tenant = format!("{CACHE_TAG_PREFIX_TENANT}_{id}");
+
+    let tenant = ctx
+        .cache()
+        .get_or_load_tagged(
+            &cache_key_tenant_id,
+            &[&cache_tag_tenant],
+            CACHE_TENANT_EXPIRY,
+            || ctx.tenants().find_by_id(id),
+        )
+        .await?
+        .ok_or(InternalError::TenantNotFound {
+            tenant_id: id.to_uuid_0_8(),
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header(etag(tenant.version))
+        .json(tenant))
 }
 
-async fn get_by_condition(ctx: web::Data<AppContext>, tenant: Tenant) -> ApiResult {
-    let tenants = tenant_repository::find_all_with_query(&tenant, ctx.db()).await?;
+async fn get_by_condition(
+    ctx: web::Data<AppContext>,
+    tenant: Tenant,
+    filter_request: FilterRequest,
+) -> ApiResult {
+    filter_request.validate()?;
+
+    let tenants = ctx
+        .tenants()
+        .find_all_with_query(&tenant, &filter_request)
+        .await?;
     Ok(HttpResponse::Ok()
         .content_type("application/json")
         .json(tenants))
@@ -91,71 +185,246 @@ async fn get_by_condition(ctx: web::Data<AppContext>, tenant: Tenant) -> ApiResu
 async fn get_paginated_by_condition(
     ctx: web::Data<AppContext>,
     tenant: Tenant,
+    filter_request: FilterRequest,
     page_request: PageRequest,
 ) -> ApiResult {
-    let tenants = tenant_repository::find_all_paginated_with_query(
-        &tenant,
-        page_request.page,
-        page_request.page_size,
-        ctx.db(),
-    )
-    .await?;
+    filter_request.validate()?;
+    page_request.validate()?;
+
+    let tenants = ctx
+        .tenants()
+        .find_all_paginated_with_query(&tenant, &filter_request, &page_request)
+        .await?;
     Ok(HttpResponse::Ok()
         .content_type("application/json")
         .json(tenants))
 }
 
-/// Http handler for creating an tenant.
-#[tracing::instrument(name = "create", skip(tenant), level = "info")]
-pub async fn create(ctx: web::Data<AppContext>, tenant: web::Json<Tenant>) -> ApiResult {
-    // verify necessary fields
-    if tenant.email.is_none() {
-        return Err(InternalError::RequestFormatError {
-            reason: "require fields: `EMAIL`".to_string(),
-        });
-    }
+/// Http handler for creating an tenant. Only the administrators of the
+/// platform can create tenants.
+#[tracing::instrument(name = "create", skip(actor, create_tenant), level = "info")]
+pub async fn create(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    actor: AuditActor,
+    web::Json(create_tenant): web::Json<CreateTenant>,
+) -> ApiResult {
+    principal.require_platform_admin()?;
+    create_tenant.validate()?;
 
-    let now = Some(Utc::now());
+    let tenant = Tenant::from(create_tenant);
     let to_create = Tenant {
         id: Some(bson::Uuid::new()),
-        status: Some(TenantStatus::Active),
-        created_at: now,
-        updated_at: now,
-        ..tenant.0
+        status: Some(tenant.status.unwrap_or(TenantStatus::Active)),
+        ..tenant
     };
 
-    let tenant = tenant_repository::insert_one(&to_create, ctx.db()).await?;
+    let tenant = ctx.tenants().insert_one(&to_create).await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_TENANT_SUBJECT,
+                AUDIT_ACTION_TENANT_CREATE,
+                AUDIT_TARGET_TENANT,
+                to_create.id.unwrap(),
+            )
+            .with_tenant(tenant.id)
+            .with_changes(None, Some(&tenant))?,
+    )?;
+
+    // the services set up where they keep the entities of the tenant
+    let tenant_created_event = Event::TenantCreated(EventMessage {
+        meta: EventMetadata::new(SERVICE_TENANT_SUBJECT.into(), "trace_id"),
+        payload: TenantCreatedMessage {
+            tenant_id: to_create.id.unwrap().to_string(),
+        },
+    });
+    ctx.event_publisher().do_send(NatsEventMessage {
+        event: tenant_created_event.try_into()?,
+    });
 
     Ok(HttpResponse::Ok()
         .content_type("application/json")
+        .insert_header(etag(tenant.version))
         .json(tenant))
 }
 
-/// Http handler for updating an tenant.
-#[tracing::instrument(name = "update_by_id", skip(tenant), level = "info")]
-pub async fn update_by_id(ctx: web::Data<AppContext>, tenant: web::Json<Tenant>) -> ApiResult {
-    // verify necessary fields
-    if tenant.id.is_none() {
-        return Err(InternalError::RequestFormatError {
-            reason: "require fields: `_id`".to_string(),
+/// Http handler for updating an tenant. Only the administrators of the tenant,
+/// or of the platform, can update it. With an `If-Match` header, the tenant is
+/// only updated if still at the version of its entity tag.
+#[tracing::instrument(
+    name = "update_by_id",
+    skip(actor, update_tenant, if_match),
+    level = "info"
+)]
+pub async fn update_by_id(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    actor: AuditActor,
+    web::Json(update_tenant): web::Json<UpdateTenant>,
+    if_match: Option<web::Header<IfMatch>>,
+) -> ApiResult {
+    update_tenant.validate()?;
+    let id = update_tenant.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })?;
+    require_tenant_admin(&principal, &id)?;
+    let expected_version = expected_version(if_match.as_deref())?;
+
+    let tenant = Tenant::from(update_tenant);
+
+    let previous = ctx.tenants().find_by_id(&id).await?;
+    let previous_tier = previous.as_ref().and_then(|previous| previous.tier);
+
+    let updated = ctx
+        .tenants()
+        .update_by_id(&tenant, expected_version)
+        .await?
+        .ok_or(InternalError::TenantNotFound {
+            tenant_id: id.to_uuid_0_8(),
+        })?;
+    ctx.cache()
+        .invalidate_tag(&format!("{CACHE_TAG_PREFIX_TENANT}_{id}"))
+        .await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_TENANT_SUBJECT,
+                AUDIT_ACTION_TENANT_UPDATE,
+                AUDIT_TARGET_TENANT,
+                id,
+            )
+            .with_tenant(Some(id))
+            .with_changes(previous.as_ref(), Some(&updated))?,
+    )?;
+
+    // emit an event when the tier changes
+    if let Some(tier) = tenant.tier.filter(|tier| Some(*tier) != previous_tier) {
+        let tier_changed_event = Event::TenantTierChanged(EventMessage {
+            meta: EventMetadata::new(SERVICE_TENANT_SUBJECT.into(), "trace_id"),
+            payload: TenantTierChangedMessage {
+                tenant_id: id.to_string(),
+                previous_tier: previous_tier.map(|tier| tier.to_string()),
+                tier: tier.to_string(),
+            },
+        });
+        ctx.event_publisher().do_send(NatsEventMessage {
+            event: tier_changed_event.try_into()?,
         });
     }
 
-    let _: u64 = tenant_repository::update_by_id(&tenant, ctx.db()).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(updated.version))
+        .finish())
+}
+
+/// Http handler for deleting an tenant, conditionally on `If-Match` as for the
+/// updates. The tenant is only marked deleted, and purged along with its
+/// webhooks once the retention period is over, but its pending webhook
+/// deliveries are dropped right away. Only the administrators of the
+/// tenant, or of the platform, can delete it.
+#[tracing::instrument(name = "delete_by_id", skip(actor, tenant, if_match), level = "info")]
+pub async fn delete_by_id(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    actor: AuditActor,
+    web::Query(tenant): web::Query<Tenant>,
+    if_match: Option<web::Header<IfMatch>>,
+) -> ApiResult {
+    let id = tenant.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })?;
+    require_tenant_admin(&principal, &id)?;
+    let expected_version = expected_version(if_match.as_deref())?;
 
+    let previous = ctx.tenants().find_by_id(&id).await?;
+    // the webhook deliveries not made yet go along with the tenant. Its users,
+    // kept by the user service, are deprovisioned once the tenant is purged
+    db_mongo::with_transaction(
+        ctx.mongo_client(),
+        &(ctx.db(), ctx.tenants(), id, expected_version),
+        |session, (db, tenants, id, expected_version)| {
+            Box::pin(async move {
+                let _: u64 = webhook_repository::delete_pending_deliveries_by_tenant(
+                    id,
+                    Some(&mut *session),
+                    db,
+                )
+                .await?;
+                if !tenants
+                    .delete_one_with_session(id, *expected_version, session)
+                    .await?
+                {
+                    return Err(InternalError::TenantNotFound {
+                        tenant_id: id.to_uuid_0_8(),
+                    });
+                }
+                Ok(())
+            })
+        },
+    )
+    .await?;
+    ctx.cache()
+        .invalidate_tag(&format!("{CACHE_TAG_PREFIX_TENANT}_{id}"))
+        .await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_TENANT_SUBJECT,
+                AUDIT_ACTION_TENANT_DELETE,
+                AUDIT_TARGET_TENANT,
+                id,
+            )
+            .with_tenant(Some(id))
+            .with_changes(previous.as_ref(), None)?,
+    )?;
     Ok(HttpResponse::Ok().finish())
 }
 
-/// Http handler for deleting an tenant.
-#[tracing::instrument(name = "delete_by_id", skip(tenant), level = "info")]
-pub async fn delete_by_id(
+/// Http handler for restoring a deleted tenant, before it is purged. Only
+/// administrators can restore tenants.
+#[tracing::instrument(name = "restore_by_id", skip(actor, tenant), level = "info")]
+pub async fn restore_by_id(
     ctx: web::Data<AppContext>,
+    principal: Principal,
+    actor: AuditActor,
     web::Query(tenant): web::Query<Tenant>,
 ) -> ApiResult {
+    principal.require_admin()?;
     let id = tenant.id.ok_or(InternalError::RequestFormatError {
         reason: "require fields: `_id`".to_string(),
     })?;
 
-    let _: u64 = tenant_repository::delete_one(&id, ctx.db()).await?;
+    if !ctx.tenants().restore_one(&id).await? {
+        return Err(InternalError::TenantNotFound {
+            tenant_id: id.to_uuid_0_8(),
+        });
+    }
+    ctx.cache()
+        .invalidate_tag(&format!("{CACHE_TAG_PREFIX_TENANT}_{id}"))
+        .await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_TENANT_SUBJECT,
+                AUDIT_ACTION_TENANT_RESTORE,
+                AUDIT_TARGET_TENANT,
+                id,
+            )
+            .with_tenant(Some(id)),
+    )?;
     Ok(HttpResponse::Ok().finish())
 }
+
+// Fail unless `principal` administers the tenant `id`, or the whole platform.
+// The tenants of others are reported missing.
+fn require_tenant_admin(principal: &Principal, id: &Uuid) -> Result<(), InternalError> {
+    principal.require_admin()?;
+    if TenantScope::of(principal)?.covers(id.to_uuid_0_8()) {
+        Ok(())
+    } else {
+        Err(InternalError::TenantNotFound {
+            tenant_id: id.to_uuid_0_8(),
+        })
+    }
+}
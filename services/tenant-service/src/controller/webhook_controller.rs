@@ -0,0 +1,356 @@
+use actix_web::{
+    web::{self},
+    HttpResponse,
+    Scope,
+};
+use bson::Uuid;
+use common::{
+    error::{ApiResult, InternalError},
+    model::{
+        event::v1::{
+            audit::prelude::{
+                AUDIT_ACTION_WEBHOOK_ENDPOINT_CREATE,
+                AUDIT_ACTION_WEBHOOK_ENDPOINT_DELETE,
+                AUDIT_ACTION_WEBHOOK_ENDPOINT_UPDATE,
+                AUDIT_TARGET_WEBHOOK_ENDPOINT,
+            },
+            tenant::prelude::SERVICE_TENANT_SUBJECT,
+        },
+        request::page_request::PageRequest,
+    },
+    util::{audit::AuditActor, principal::Principal, tenant_scope::TenantScope},
+};
+use serde_json::json;
+use validator::Validate;
+
+use crate::{
+    context::AppContext,
+    model::{
+        domain::webhook::{
+            prelude::WEBHOOK_EVENT_PING,
+            resolve_public_endpoint,
+            WebhookDelivery,
+            WebhookEndpoint,
+        },
+        request::webhook_request::{
+            CreateWebhookEndpoint,
+            DeliveryQuery,
+            UpdateWebhookEndpoint,
+            WebhookQuery,
+        },
+        response::webhook_response::RegisteredWebhookEndpoint,
+    },
+    repository::webhook_repository,
+    secrets,
+};
+
+pub fn router() -> Scope {
+    web::scope("/webhook")
+        .service(
+            web::resource("")
+                .route(web::get().to(query))
+                .route(web::post().to(create))
+                .route(web::put().to(update_by_id))
+                .route(web::delete().to(delete_by_id)),
+        )
+        .service(web::resource("/ping").route(web::post().to(ping)))
+        .service(web::resource("/delivery").route(web::get().to(query_deliveries)))
+        .service(web::resource("/delivery/replay").route(web::post().to(replay)))
+}
+
+/// Http handler for querying a webhook endpoint, or the endpoints of a tenant.
+#[tracing::instrument(name = "query", skip(principal, query), level = "info")]
+pub async fn query(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(query): web::Query<WebhookQuery>,
+) -> ApiResult {
+    let scope = admin_scope(&principal)?;
+    match (query.id, query.tenant_id) {
+        (Some(id), _) => {
+            let endpoint = find_endpoint(&ctx, &scope, &id).await?;
+            Ok(HttpResponse::Ok()
+                .content_type("application/json")
+                .json(endpoint))
+        }
+        (None, requested) => {
+            let tenant_id = scope.owner(requested.map(|tenant_id| tenant_id.to_uuid_0_8()))?;
+            let endpoints = webhook_repository::find_endpoints_by_tenant(
+                &Uuid::from_uuid_0_8(tenant_id),
+                ctx.db(),
+            )
+            .await?;
+            Ok(HttpResponse::Ok()
+                .content_type("application/json")
+                .json(endpoints))
+        }
+    }
+}
+
+/// Http handler for registering a webhook endpoint. A signing key is
+/// generated for the endpoint, stored in Vault and returned only once.
+#[tracing::instrument(name = "create", skip(principal, actor, request), level = "info")]
+pub async fn create(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    actor: AuditActor,
+    request: web::Json<CreateWebhookEndpoint>,
+) -> ApiResult {
+    let scope = admin_scope(&principal)?;
+    request.validate()?;
+    let request = request.into_inner();
+    if let Some(url) = &request.url {
+        resolve_public_endpoint(url).await?;
+    }
+
+    let tenant_id = Uuid::from_uuid_0_8(
+        scope.owner(request.tenant_id.map(|tenant_id| tenant_id.to_uuid_0_8()))?,
+    );
+    let tenant =
+        ctx.tenants()
+            .find_by_id(&tenant_id)
+            .await?
+            .ok_or(InternalError::TenantNotFound {
+                tenant_id: tenant_id.to_uuid_0_8(),
+            })?;
+    match tenant.tier {
+        Some(tier) if tier.supports_webhooks() => {}
+        tier => {
+            return Err(InternalError::WebhookTierNotAllowed {
+                tier: tier.map_or("unknown".to_string(), |tier| tier.to_string()),
+            })
+        }
+    }
+
+    let endpoint = WebhookEndpoint::new(
+        tenant_id,
+        request.url.unwrap_or_default(),
+        request.event_types.unwrap_or_default(),
+    );
+
+    let signing_key = format!(
+        "whsec_{}{}",
+        uuid::Uuid::new_v4().to_simple(),
+        uuid::Uuid::new_v4().to_simple()
+    );
+    secrets::write_webhook(
+        ctx.vault(),
+        ctx.webhook_secrets_path(),
+        &endpoint.id,
+        &signing_key,
+    )
+    .await?;
+    webhook_repository::insert_endpoint(&endpoint, ctx.db()).await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_TENANT_SUBJECT,
+                AUDIT_ACTION_WEBHOOK_ENDPOINT_CREATE,
+                AUDIT_TARGET_WEBHOOK_ENDPOINT,
+                endpoint.id,
+            )
+            .with_tenant(Some(endpoint.tenant_id))
+            .with_changes(None, Some(&endpoint))?,
+    )?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(RegisteredWebhookEndpoint {
+            endpoint,
+            signing_key,
+        }))
+}
+
+/// Http handler for updating the url, event types or state of a webhook
+/// endpoint.
+#[tracing::instrument(name = "update_by_id", skip(principal, actor, request), level = "info")]
+pub async fn update_by_id(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    actor: AuditActor,
+    request: web::Json<UpdateWebhookEndpoint>,
+) -> ApiResult {
+    let scope = admin_scope(&principal)?;
+    request.validate()?;
+
+    let id = request.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })?;
+    if let Some(url) = &request.url {
+        resolve_public_endpoint(url).await?;
+    }
+
+    let previous = find_endpoint(&ctx, &scope, &id).await?;
+    let _: u64 = webhook_repository::update_endpoint(
+        &id,
+        request.url.as_deref(),
+        request.event_types.as_deref(),
+        request.active,
+        ctx.db(),
+    )
+    .await?;
+    let updated = webhook_repository::find_endpoint_by_id(&id, ctx.db()).await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_TENANT_SUBJECT,
+                AUDIT_ACTION_WEBHOOK_ENDPOINT_UPDATE,
+                AUDIT_TARGET_WEBHOOK_ENDPOINT,
+                id,
+            )
+            .with_tenant(Some(previous.tenant_id))
+            .with_changes(Some(&previous), updated.as_ref())?,
+    )?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Http handler for removing a webhook endpoint.
+#[tracing::instrument(name = "delete_by_id", skip(principal, actor, query), level = "info")]
+pub async fn delete_by_id(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    actor: AuditActor,
+    web::Query(query): web::Query<WebhookQuery>,
+) -> ApiResult {
+    let scope = admin_scope(&principal)?;
+    let id = query.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })?;
+
+    let previous = find_endpoint(&ctx, &scope, &id).await?;
+    let _: u64 = webhook_repository::delete_endpoint(&id, ctx.db()).await?;
+    secrets::delete_webhook(ctx.vault(), ctx.webhook_secrets_path(), &id).await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_TENANT_SUBJECT,
+                AUDIT_ACTION_WEBHOOK_ENDPOINT_DELETE,
+                AUDIT_TARGET_WEBHOOK_ENDPOINT,
+                id,
+            )
+            .with_tenant(Some(previous.tenant_id))
+            .with_changes(Some(&previous), None)?,
+    )?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Http handler queueing a test delivery to a webhook endpoint.
+#[tracing::instrument(name = "ping", skip(principal, query), level = "info")]
+pub async fn ping(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(query): web::Query<WebhookQuery>,
+) -> ApiResult {
+    let scope = admin_scope(&principal)?;
+    let id = query.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })?;
+    let endpoint = find_endpoint(&ctx, &scope, &id).await?;
+
+    let delivery = WebhookDelivery::new(
+        &endpoint,
+        WEBHOOK_EVENT_PING,
+        json!({ "endpoint_id": endpoint.id.to_string() }),
+    );
+    webhook_repository::insert_deliveries(&[delivery.clone()], ctx.db()).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(delivery))
+}
+
+/// Http handler for querying a delivery, or the delivery log of an endpoint.
+#[tracing::instrument(
+    name = "query_deliveries",
+    skip(principal, query, page_request),
+    level = "info"
+)]
+pub async fn query_deliveries(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(query): web::Query<DeliveryQuery>,
+    web::Query(page_request): web::Query<PageRequest>,
+) -> ApiResult {
+    let scope = admin_scope(&principal)?;
+    match (query.id, query.endpoint_id) {
+        (Some(id), _) => {
+            let delivery = find_delivery(&ctx, &scope, &id).await?;
+            Ok(HttpResponse::Ok()
+                .content_type("application/json")
+                .json(delivery))
+        }
+        (None, Some(endpoint_id)) => {
+            page_request.validate()?;
+            find_endpoint(&ctx, &scope, &endpoint_id).await?;
+            let deliveries = webhook_repository::find_deliveries_paginated(
+                &endpoint_id,
+                &page_request,
+                ctx.db(),
+            )
+            .await?;
+            Ok(HttpResponse::Ok()
+                .content_type("application/json")
+                .json(deliveries))
+        }
+        (None, None) => Err(InternalError::RequestFormatError {
+            reason: "require fields: `_id` or `ENDPOINT_ID`".to_string(),
+        }),
+    }
+}
+
+/// Http handler queueing a new delivery of the payload of a past delivery.
+#[tracing::instrument(name = "replay", skip(principal, query), level = "info")]
+pub async fn replay(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    web::Query(query): web::Query<DeliveryQuery>,
+) -> ApiResult {
+    let scope = admin_scope(&principal)?;
+    let id = query.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })?;
+
+    let replay = find_delivery(&ctx, &scope, &id).await?.replay();
+    webhook_repository::insert_deliveries(&[replay.clone()], ctx.db()).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(replay))
+}
+
+// The webhooks of a tenant are managed by its administrators, or by those of
+// the platform
+fn admin_scope(principal: &Principal) -> Result<TenantScope, InternalError> {
+    principal.require_admin()?;
+    TenantScope::of(principal)
+}
+
+// The endpoint `id`, unless registered by another tenant than that of `scope`
+async fn find_endpoint(
+    ctx: &AppContext,
+    scope: &TenantScope,
+    id: &Uuid,
+) -> Result<WebhookEndpoint, InternalError> {
+    webhook_repository::find_endpoint_by_id(id, ctx.db())
+        .await?
+        .filter(|endpoint| scope.covers(endpoint.tenant_id.to_uuid_0_8()))
+        .ok_or(InternalError::WebhookEndpointNotFound {
+            endpoint_id: id.to_uuid_0_8(),
+        })
+}
+
+// The delivery `id`, unless made to the endpoint of another tenant than that
+// of `scope`
+async fn find_delivery(
+    ctx: &AppContext,
+    scope: &TenantScope,
+    id: &Uuid,
+) -> Result<WebhookDelivery, InternalError> {
+    webhook_repository::find_delivery_by_id(id, ctx.db())
+        .await?
+        .filter(|delivery| scope.covers(delivery.tenant_id.to_uuid_0_8()))
+        .ok_or(InternalError::WebhookDeliveryNotFound {
+            delivery_id: id.to_uuid_0_8(),
+        })
+}
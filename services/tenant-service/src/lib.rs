@@ -1,25 +1,61 @@
+mod actor;
 mod context;
 mod controller;
 mod model;
 mod repository;
+mod retention;
 mod secrets;
 mod settings;
 
-use crate::{context::AppContext, settings::Settings};
+use crate::{
+    context::AppContext,
+    repository::{
+        mongo_tenant_repository::MongoTenantRepository,
+        tenant_repository::TenantRepository,
+    },
+    settings::Settings,
+};
+use actix::{Actor, Addr};
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 use actix_web_opentelemetry::RequestTracing;
+use actor::webhook_dispatcher::WebhookDispatcher;
 use common::{
     client::{
         cache_redis::{self, Cache, CachePool},
         db_mongo,
+        DbBackend,
+    },
+    error::{InternalError, REDACTED_ERRORS},
+    model::event::v1::{
+        audit::prelude::SERVICE_AUDIT_SUBJECT,
+        auth::prelude::SERVICE_AUTH_SUBJECT,
+        tenant::prelude::SERVICE_TENANT_SUBJECT,
+        user::prelude::SERVICE_USER_SUBJECT,
+        Event,
     },
-    error::REDACTED_ERRORS,
-    util::{actix_json_config::json_extractor_config, telemetry},
+    util::{
+        access_token::AccessTokenKey,
+        actix_json_config::json_extractor_config,
+        leader_election::LeaderElection,
+        retention::RetentionPurger,
+        telemetry,
+    },
+};
+use futures::FutureExt;
+use mongodb::Database;
+use nats_actor::{
+    publisher::{NatsPublisher, NatsPublisherConfig},
+    subscriber::{subscribe_to_nats, NatsStreamMessage, NatsSubscriberConfig},
 };
 use secrets::Secrets;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info, warn};
 use tracing_actix_web::TracingLogger;
 
+// Lock electing the replica purging the deleted tenants
+const RETENTION_LEADER_LOCK: &str = "retention_leader";
+const RETENTION_LEADER_LEASE: Duration = Duration::from_secs(30);
+
 pub async fn server() -> Result<(), std::io::Error> {
     // configure tracing subscriber
     let app_name = concat!(env!("CARGO_PKG_NAME"), "-", env!("CARGO_PKG_VERSION")).to_string();
@@ -52,25 +88,130 @@ pub async fn start_web_service(
     );
 
     let secrets: Secrets = secrets::read(&configuration).await?;
+    // Verifies the access tokens asserting the principal of the requests
+    let access_token_key = AccessTokenKey::new(secrets.access_token.signing_key.clone());
+    let trusted_proxies = configuration.trusted_proxies.clone();
 
-    let db_client = db_mongo::connect(app_name, &configuration.db, &secrets.db)
+    let mongo_client = db_mongo::connect_client(app_name, &configuration.db, &secrets.db)
         .await
         .expect("db client connection failure");
+    let db_client = mongo_client.database(&configuration.db.database_name);
+    db_mongo::migrate(&db_client, &repository::migrations::SCHEMA)
+        .await
+        .expect("db schema migration failure");
+    let tenants = connect_tenant_repository(&configuration, &secrets, &db_client)
+        .await
+        .expect("db setup failure");
+
+    let cache_pool: CachePool = cache_redis::connect(&configuration.cache, &secrets.cache).await?;
+    let cache_client: Cache = cache_redis::Cache::new(cache_pool, env!("CARGO_PKG_NAME"))
+        .with_local_tier(configuration.cache.local.as_ref());
+    let pubsub_client = cache_redis::connect_pubsub(&configuration.cache, &secrets.cache).await?;
 
-    let cache_pool: CachePool = cache_redis::connect(&configuration.cache, &secrets.cache)?;
-    let cache_client: Cache = cache_redis::Cache::new(cache_pool);
+    // Evict the values invalidated by any replica from the in-process tier
+    let local_cache = cache_client.clone();
+    actix::spawn(async move {
+        if let Err(err) = local_cache.follow_invalidations(&pubsub_client).await {
+            error!("Failed to follow cache invalidations: {}", err);
+        }
+    });
+
+    // Start the NATS publisher actor.
+    let publisher = NatsPublisher::start_new(NatsPublisherConfig {
+        client_settings: configuration.nats.clone(),
+        subject: SERVICE_TENANT_SUBJECT.into(),
+        mailbox_size: configuration.application.nats_publisher_mailbox_size,
+    })
+    .await
+    .expect("nats connection setup failure");
+
+    // Start the NATS publisher actor of the audit records.
+    let audit_publisher = NatsPublisher::start_new(NatsPublisherConfig {
+        client_settings: configuration.nats.clone(),
+        subject: SERVICE_AUDIT_SUBJECT.into(),
+        mailbox_size: configuration.application.nats_publisher_mailbox_size,
+    })
+    .await
+    .expect("nats connection setup failure");
+
+    let election_cache = cache_client.clone();
 
     // Instantiate the application context. This application state will be
     // cloned for each Actix thread but the Arc of the DbContext will be
     // reused in each Actix thread.
     let app_context = web::Data::new(AppContext {
+        mongo_client: Arc::new(mongo_client),
         db: Arc::new(db_client),
+        tenants,
         cache: Arc::new(cache_client),
+        event_publisher: Arc::new(publisher),
+        audit_publisher: Arc::new(audit_publisher),
+        vault: configuration.vault.clone(),
+        webhook_secrets_path: configuration.webhook_secrets_path.clone(),
     });
 
+    // Purge the tenants deleted for longer than the retention period, from the
+    // leading replica only
+    let context = Arc::clone(&app_context);
+    let retention_purger = RetentionPurger::new(
+        configuration.retention.clone(),
+        Box::new(move |deleted_before, limit| {
+            let context = Arc::clone(&context);
+            async move { retention::purge_deleted_tenants(&context, deleted_before, limit).await }
+                .boxed_local()
+        }),
+    )
+    .start();
+    LeaderElection::new(
+        election_cache,
+        RETENTION_LEADER_LOCK,
+        RETENTION_LEADER_LEASE,
+    )
+    .with_subscriber(retention_purger.recipient())
+    .start();
+
+    // Start the webhook dispatcher actor delivering the platform events to
+    // the tenant endpoints. Redirects are not followed as they could lead the
+    // deliveries away from the checked endpoint url.
+    let webhook_dispatcher = WebhookDispatcher {
+        context: Arc::clone(&app_context),
+        client: awc::Client::builder()
+            .timeout(Duration::from_secs(
+                configuration.webhook.request_timeout_secs,
+            ))
+            .disable_redirects()
+            .finish(),
+        settings: configuration.webhook.clone(),
+    }
+    .start();
+
+    // start NATS subscribers for the event streams webhooks are sent for
+    for subject in [
+        SERVICE_AUTH_SUBJECT,
+        SERVICE_TENANT_SUBJECT,
+        SERVICE_USER_SUBJECT,
+    ] {
+        let config = NatsSubscriberConfig {
+            client_settings: configuration.nats.clone(),
+            subject: subject.into(),
+            mailbox_size: configuration.application.nats_subscriber_mailbox_size,
+        };
+        let dispatcher = webhook_dispatcher.clone();
+        actix::spawn(async move {
+            subscribe_to_nats(config, move |msg: NatsStreamMessage| {
+                forward_event(&dispatcher, msg);
+                Ok(())
+            })
+            .await
+            .expect("nats connection/subscriber setup failure");
+        });
+    }
+
     let server = HttpServer::new(move || {
         App::new()
             .app_data(app_context.clone())
+            .app_data(access_token_key.clone())
+            .app_data(trusted_proxies.clone())
             .app_data(json_extractor_config(4096))
             .wrap(TracingLogger::default())
             .wrap(RequestTracing::new())
@@ -87,6 +228,59 @@ pub async fn start_web_service(
     Ok(())
 }
 
+fn forward_event(dispatcher: &Addr<WebhookDispatcher>, msg: NatsStreamMessage) {
+    info!("Received event {:?}", msg);
+    let event: Result<Event, _> = serde_json::from_slice::<cloudevents::Event>(&msg.msg.data)
+        .map_err(common::error::InternalError::from)
+        .and_then(|event| event.try_into());
+    match event {
+        Ok(event) => dispatcher.do_send(event),
+        Err(err) => warn!("Skipping unreadable event: {}", err),
+    }
+}
+
+/// The store of the tenants configured by `db_backend`, connected and
+/// brought to the current schema. The Mongo one shares the database of the
+/// webhooks.
+async fn connect_tenant_repository(
+    configuration: &Settings,
+    secrets: &Secrets,
+    db: &Database,
+) -> Result<Arc<dyn TenantRepository>, InternalError> {
+    match configuration.db_backend {
+        DbBackend::Mongo => {
+            let tenants = MongoTenantRepository::new(db.clone());
+            tenants.create_indexes().await?;
+            Ok(Arc::new(tenants))
+        }
+        #[cfg(feature = "postgres")]
+        DbBackend::Postgres => {
+            use crate::repository::postgres_tenant_repository::{self, PostgresTenantRepository};
+            use common::client::db_postgres;
+
+            let missing = |what: &str| InternalError::DbError {
+                cause: format!("missing {what} of the `postgres` db backend"),
+            };
+            let db_settings = configuration
+                .postgres
+                .as_ref()
+                .ok_or_else(|| missing("settings"))?;
+            let db_secrets = secrets
+                .postgres
+                .as_ref()
+                .ok_or_else(|| missing("secrets"))?;
+            let pool = db_postgres::connect(db_settings, db_secrets).await?;
+            db_postgres::migrate(&pool, &postgres_tenant_repository::MIGRATOR).await?;
+            Ok(Arc::new(PostgresTenantRepository::new(pool)))
+        }
+        #[cfg(not(feature = "postgres"))]
+        DbBackend::Postgres => Err(InternalError::DbError {
+            cause: "the `postgres` db backend needs a build with the `postgres` feature"
+                .to_string(),
+        }),
+    }
+}
+
 pub async fn not_found() -> impl Responder {
     HttpResponse::NotFound().body("the requested resource does not exist")
 }
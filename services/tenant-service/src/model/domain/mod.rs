@@ -1 +1,2 @@
 pub mod tenant;
+pub mod webhook;
@@ -1,6 +1,11 @@
-use bson::Document;
-use mongodb::bson::{self, doc, Bson};
-use redis::{FromRedisValue, RedisResult, RedisWrite, ToRedisArgs, Value};
+#[cfg(feature = "postgres")]
+use common::client::postgres_repository::{Column, ColumnType, PostgresEntity};
+use common::client::{
+    mongo_filter::{FilterField, FilterFieldType},
+    mongo_repository::MongoEntity,
+    mongo_search::SearchField,
+};
+use mongodb::bson::{self, Bson};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::{fmt, fmt::Formatter};
@@ -9,8 +14,12 @@ use strum::{Display, EnumString};
 use self::prelude::*;
 
 pub mod prelude {
+    use common::client::cache_key::CacheEntity;
+
     // Collection name
     pub const COLLECTION_TENANTS: &str = "tenants";
+    // Table name, with the `postgres` db backend
+    pub const TABLE_TENANTS: &str = "tenants";
 
     // Tenant fields.
     pub const ID: &str = "_id";
@@ -23,10 +32,13 @@ pub mod prelude {
     pub const TIER: &str = "TIER";
     pub const CREATED_AT: &str = "CREATED_AT";
     pub const UPDATED_AT: &str = "UPDATED_AT";
+    pub use common::client::mongo_repository::{DELETED_AT, VERSION};
 
     // Cache keys
-    pub const CACHE_KEY_PREFIX_TENANT_ID: &str = "_id";
+    pub const CACHE_ENTITY_TENANT: CacheEntity = CacheEntity::new("tenant", 1);
     pub const CACHE_TENANT_EXPIRY: usize = 600;
+    // Tag attached to every cached entry of a tenant
+    pub const CACHE_TAG_PREFIX_TENANT: &str = "tenant";
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Display, EnumString)]
@@ -53,6 +65,13 @@ pub enum TenantTier {
     Premium,
 }
 
+impl TenantTier {
+    /// Outbound webhooks are a feature of the paid tiers.
+    pub fn supports_webhooks(&self) -> bool {
+        matches!(self, TenantTier::Standard | TenantTier::Premium)
+    }
+}
+
 impl From<TenantTier> for Bson {
     fn from(status: TenantTier) -> Self {
         match status {
@@ -89,105 +108,72 @@ pub struct Tenant {
     // #[serde_as(as = "Option<bson::DateTime>")]
     #[serde(rename = "UPDATED_AT")]
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "DELETED_AT")]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "VERSION")]
+    pub version: Option<i64>,
 }
 
-impl From<Tenant> for Document {
-    fn from(tenant: Tenant) -> Self {
-        let mut doc = Document::new();
-        if let Some(id) = tenant.id {
-            doc.insert(ID, id);
-        }
-        if let Some(first_name) = &tenant.company_name {
-            doc.insert(COMPANY_NAME, &first_name);
-        }
-        if let Some(last_name) = &tenant.account_name {
-            doc.insert(ACCOUNT_NAME, &last_name);
-        }
-        if let Some(last_name) = &tenant.owner_name {
-            doc.insert(OWNER_NAME, &last_name);
-        }
-        if let Some(email) = &tenant.email {
-            doc.insert(EMAIL, &email);
-        }
-        if let Some(phone_number) = &tenant.phone_number {
-            doc.insert(PHONE, &phone_number);
-        }
-        if let Some(status) = tenant.status {
-            doc.insert(STATUS, status);
-        }
-        if let Some(tier) = tenant.tier {
-            doc.insert(TIER, tier);
-        }
-        if let Some(created_at) = tenant.created_at {
-            doc.insert(CREATED_AT, created_at);
-        }
-        if let Some(updated_at) = tenant.updated_at {
-            doc.insert(UPDATED_AT, updated_at);
-        }
-        doc
+impl MongoEntity for Tenant {
+    type Id = bson::Uuid;
+
+    const COLLECTION: &'static str = COLLECTION_TENANTS;
+    const SORT_FIELDS: &'static [&'static str] = &[EMAIL, CREATED_AT, UPDATED_AT];
+    const FILTER_FIELDS: &'static [FilterField] = &[
+        FilterField::new(ID, FilterFieldType::Uuid),
+        FilterField::new(COMPANY_NAME, FilterFieldType::String),
+        FilterField::new(ACCOUNT_NAME, FilterFieldType::String),
+        FilterField::new(OWNER_NAME, FilterFieldType::String),
+        FilterField::new(EMAIL, FilterFieldType::String),
+        FilterField::new(PHONE, FilterFieldType::String),
+        FilterField::new(STATUS, FilterFieldType::Enum(&["Active", "Inactive"])),
+        FilterField::new(
+            TIER,
+            FilterFieldType::Enum(&["Free", "Standard", "Premium"]),
+        ),
+        FilterField::new(CREATED_AT, FilterFieldType::DateTime),
+        FilterField::new(UPDATED_AT, FilterFieldType::DateTime),
+    ];
+    const SEARCH_FIELDS: &'static [SearchField] = &[
+        SearchField::new(COMPANY_NAME, 3),
+        SearchField::new(ACCOUNT_NAME, 3),
+        SearchField::new(OWNER_NAME, 1),
+        SearchField::new(EMAIL, 1),
+    ];
+    const SOFT_DELETE: bool = true;
+    const VERSIONED: bool = true;
+    const TIMESTAMPED: bool = true;
+
+    fn id(&self) -> Option<&bson::Uuid> {
+        self.id.as_ref()
     }
-}
 
-impl From<&Tenant> for Document {
-    fn from(tenant: &Tenant) -> Self {
-        let mut doc = Document::new();
-        if let Some(id) = tenant.id {
-            doc.insert(ID, id);
-        }
-        if let Some(first_name) = &tenant.company_name {
-            doc.insert(COMPANY_NAME, &first_name);
-        }
-        if let Some(last_name) = &tenant.account_name {
-            doc.insert(ACCOUNT_NAME, &last_name);
-        }
-        if let Some(last_name) = &tenant.owner_name {
-            doc.insert(OWNER_NAME, &last_name);
-        }
-        if let Some(email) = &tenant.email {
-            doc.insert(EMAIL, &email);
-        }
-        if let Some(phone_number) = &tenant.phone_number {
-            doc.insert(PHONE, &phone_number);
-        }
-        if let Some(status) = tenant.status {
-            doc.insert(STATUS, status);
-        }
-        if let Some(tier) = tenant.tier {
-            doc.insert(TIER, tier);
-        }
-        if let Some(created_at) = tenant.created_at {
-            doc.insert(CREATED_AT, created_at);
-        }
-        if let Some(updated_at) = tenant.updated_at {
-            doc.insert(UPDATED_AT, updated_at);
-        }
-        doc
+    fn set_id(&mut self, id: bson::Uuid) {
+        self.id = Some(id);
     }
-}
 
-impl ToRedisArgs for Tenant {
-    fn write_redis_args<W>(&self, output: &mut W)
-    where
-        W: ?Sized + RedisWrite,
-    {
-        output.write_arg_fmt(serde_json::to_string(self).unwrap());
+    fn version(&self) -> Option<i64> {
+        self.version
     }
 }
 
-impl FromRedisValue for Tenant {
-    fn from_redis_value(value: &Value) -> RedisResult<Self> {
-        match *value {
-            redis::Value::Data(ref value_slice) => match serde_json::from_slice(value_slice) {
-                Err(_) => Err((redis::ErrorKind::TypeError, "Can't serialize value").into()),
-                Ok(user) => Ok(user),
-            },
-            _ => Err((
-                redis::ErrorKind::ResponseError,
-                "Response type not Profile compatible.",
-            )
-                .into()),
-        }
-    }
+#[cfg(feature = "postgres")]
+impl PostgresEntity for Tenant {
+    const TABLE: &'static str = TABLE_TENANTS;
+    const COLUMNS: &'static [Column] = &[
+        Column::new(ID, "id", ColumnType::Uuid),
+        Column::new(COMPANY_NAME, "company_name", ColumnType::Text),
+        Column::new(ACCOUNT_NAME, "account_name", ColumnType::Text),
+        Column::new(OWNER_NAME, "owner_name", ColumnType::Text),
+        Column::new(EMAIL, "email", ColumnType::Text),
+        Column::new(PHONE, "phone", ColumnType::Text),
+        Column::new(STATUS, "status", ColumnType::Text),
+        Column::new(TIER, "tier", ColumnType::Text),
+        Column::new(CREATED_AT, "created_at", ColumnType::TimestampTz),
+        Column::new(UPDATED_AT, "updated_at", ColumnType::TimestampTz),
+        Column::new(DELETED_AT, "deleted_at", ColumnType::TimestampTz),
+        Column::new(VERSION, "version", ColumnType::BigInt),
+    ];
 }
 
 impl fmt::Display for Tenant {
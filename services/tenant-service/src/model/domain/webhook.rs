@@ -0,0 +1,362 @@
+use actix_web::{http::Uri, web};
+use chrono::{DateTime, Duration, Utc};
+use common::error::InternalError;
+use mongodb::bson::{self, serde_helpers::chrono_datetime_as_bson_datetime, Bson};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::{
+    fmt,
+    fmt::Formatter,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+};
+use strum::{Display, EnumString};
+
+use self::prelude::*;
+
+pub mod prelude {
+    // Collection names
+    pub const COLLECTION_WEBHOOK_ENDPOINTS: &str = "webhook_endpoints";
+    pub const COLLECTION_WEBHOOK_DELIVERIES: &str = "webhook_deliveries";
+
+    // Webhook endpoint fields.
+    pub const ID: &str = "_id";
+    pub const TENANT_ID: &str = "TENANT_ID";
+    pub const URL: &str = "URL";
+    pub const EVENT_TYPES: &str = "EVENT_TYPES";
+    pub const ACTIVE: &str = "ACTIVE";
+    pub const CREATED_AT: &str = "CREATED_AT";
+    pub const UPDATED_AT: &str = "UPDATED_AT";
+
+    // Webhook delivery fields.
+    pub const ENDPOINT_ID: &str = "ENDPOINT_ID";
+    pub const EVENT_TYPE: &str = "EVENT_TYPE";
+    pub const PAYLOAD: &str = "PAYLOAD";
+    pub const STATUS: &str = "STATUS";
+    pub const ATTEMPTS: &str = "ATTEMPTS";
+    pub const NEXT_ATTEMPT_AT: &str = "NEXT_ATTEMPT_AT";
+    pub const REPLAY_OF: &str = "REPLAY_OF";
+
+    // Event type matching every event.
+    pub const WEBHOOK_EVENT_ALL: &str = "*";
+    // Event type of the deliveries sent to test an endpoint.
+    pub const WEBHOOK_EVENT_PING: &str = "webhook.ping";
+
+    // Http headers set on every delivery.
+    pub const HEADER_WEBHOOK_ID: &str = "X-Webhook-Id";
+    pub const HEADER_WEBHOOK_EVENT: &str = "X-Webhook-Event";
+    pub const HEADER_WEBHOOK_TIMESTAMP: &str = "X-Webhook-Timestamp";
+    pub const HEADER_WEBHOOK_SIGNATURE: &str = "X-Webhook-Signature";
+}
+
+/// An url registered by a tenant to receive the platform events it is
+/// subscribed to. The signing key of the endpoint is kept in Vault.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookEndpoint {
+    #[serde(rename = "_id")]
+    pub id: bson::Uuid,
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: bson::Uuid,
+    #[serde(rename = "URL")]
+    pub url: String,
+    #[serde(rename = "EVENT_TYPES")]
+    pub event_types: Vec<String>,
+    #[serde(rename = "ACTIVE")]
+    pub active: bool,
+    #[serde(rename = "CREATED_AT")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "UPDATED_AT")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookEndpoint {
+    pub fn new(tenant_id: bson::Uuid, url: String, event_types: Vec<String>) -> Self {
+        let now = Utc::now();
+        WebhookEndpoint {
+            id: bson::Uuid::new(),
+            tenant_id,
+            url,
+            event_types,
+            active: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Whether the endpoint is subscribed to `event_type`.
+    pub fn accepts(&self, event_type: &str) -> bool {
+        event_type == WEBHOOK_EVENT_PING
+            || self
+                .event_types
+                .iter()
+                .any(|accepted| accepted == WEBHOOK_EVENT_ALL || accepted == event_type)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Display, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum DeliveryStatus {
+    Pending,
+    InFlight,
+    Succeeded,
+    Failed,
+}
+
+impl From<DeliveryStatus> for Bson {
+    fn from(status: DeliveryStatus) -> Self {
+        match status {
+            DeliveryStatus::Pending => Bson::String("Pending".to_string()),
+            DeliveryStatus::InFlight => Bson::String("InFlight".to_string()),
+            DeliveryStatus::Succeeded => Bson::String("Succeeded".to_string()),
+            DeliveryStatus::Failed => Bson::String("Failed".to_string()),
+        }
+    }
+}
+
+/// The outcome of a single http call made for a delivery.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeliveryAttempt {
+    #[serde(rename = "ATTEMPTED_AT")]
+    pub attempted_at: DateTime<Utc>,
+    #[serde(rename = "STATUS_CODE")]
+    pub status_code: Option<u16>,
+    #[serde(rename = "ERROR")]
+    pub error: Option<String>,
+    #[serde(rename = "DURATION_MS")]
+    pub duration_ms: i64,
+}
+
+impl DeliveryAttempt {
+    pub fn succeeded(&self) -> bool {
+        matches!(self.status_code, Some(code) if (200..300).contains(&code))
+    }
+}
+
+/// An event to be delivered to a webhook endpoint, along with the log of every
+/// attempt made so far.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookDelivery {
+    #[serde(rename = "_id")]
+    pub id: bson::Uuid,
+    #[serde(rename = "ENDPOINT_ID")]
+    pub endpoint_id: bson::Uuid,
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: bson::Uuid,
+    #[serde(rename = "EVENT_TYPE")]
+    pub event_type: String,
+    // the json body, kept as sent so that replays are identical
+    #[serde(rename = "PAYLOAD")]
+    pub payload: String,
+    #[serde(rename = "STATUS")]
+    pub status: DeliveryStatus,
+    #[serde(rename = "ATTEMPTS")]
+    pub attempts: Vec<DeliveryAttempt>,
+    // stored as a bson date so that due deliveries can be range queried
+    #[serde(rename = "NEXT_ATTEMPT_AT", with = "chrono_datetime_as_bson_datetime")]
+    pub next_attempt_at: DateTime<Utc>,
+    #[serde(rename = "REPLAY_OF")]
+    pub replay_of: Option<bson::Uuid>,
+    #[serde(rename = "CREATED_AT")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "UPDATED_AT")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookDelivery {
+    pub fn new(endpoint: &WebhookEndpoint, event_type: &str, data: serde_json::Value) -> Self {
+        let now = Utc::now();
+        let id = bson::Uuid::new();
+        let payload = serde_json::json!({
+            "id": id.to_string(),
+            "type": event_type,
+            "created_at": now,
+            "data": data,
+        });
+
+        WebhookDelivery {
+            id,
+            endpoint_id: endpoint.id,
+            tenant_id: endpoint.tenant_id,
+            event_type: event_type.to_string(),
+            payload: payload.to_string(),
+            status: DeliveryStatus::Pending,
+            attempts: vec![],
+            next_attempt_at: now,
+            replay_of: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// A new delivery sending the very same payload again.
+    pub fn replay(&self) -> Self {
+        let now = Utc::now();
+        WebhookDelivery {
+            id: bson::Uuid::new(),
+            status: DeliveryStatus::Pending,
+            attempts: vec![],
+            next_attempt_at: now,
+            replay_of: Some(self.id),
+            created_at: now,
+            updated_at: now,
+            ..self.clone()
+        }
+    }
+}
+
+/// Exponential backoff before the attempt following `attempts` failed ones.
+pub fn retry_backoff(attempts: u32, initial: Duration, max: Duration) -> Duration {
+    let factor = 2i32.pow(attempts.saturating_sub(1).min(16));
+    std::cmp::min(initial * factor, max)
+}
+
+/// The content signed for a delivery: the timestamp and the body joined by a
+/// dot, so that receivers can also reject stale requests.
+pub fn signature_payload(timestamp: i64, body: &str) -> String {
+    format!("{timestamp}.{body}")
+}
+
+/// Resolve the host of the endpoint `url`, failing unless it is an http(s)
+/// url whose host only resolves to public addresses. Endpoints are registered
+/// by tenants, who must not reach the loopback, private or link-local networks
+/// of the platform through the deliveries. Urls are checked on registration
+/// and again before every delivery, as the records of their host may change.
+pub async fn resolve_public_endpoint(url: &str) -> Result<Vec<SocketAddr>, InternalError> {
+    let rejected = |reason: &str| InternalError::RequestFormatError {
+        reason: format!("webhook url `{url}` {reason}"),
+    };
+
+    let uri: Uri = url.parse().map_err(|_| rejected("is invalid"))?;
+    let default_port = match uri.scheme_str() {
+        Some("https") => 443,
+        Some("http") => 80,
+        _ => return Err(rejected("must be http or https")),
+    };
+    let port = uri.port_u16().unwrap_or(default_port);
+    let host = uri
+        .host()
+        .ok_or_else(|| rejected("has no host"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+
+    let addresses: Vec<SocketAddr> = web::block(move || {
+        (host.as_str(), port)
+            .to_socket_addrs()
+            .map(Iterator::collect)
+    })
+    .await?
+    .map_err(|_| rejected("cannot be resolved"))?;
+    if addresses.is_empty()
+        || addresses
+            .iter()
+            .any(|address| !is_public_address(address.ip()))
+    {
+        return Err(rejected("must only resolve to public addresses"));
+    }
+    Ok(addresses)
+}
+
+/// Whether `ip` is a public unicast address, i.e. not one of the loopback,
+/// private, link-local, shared, reserved or multicast ranges.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // "this" network
+                || a == 0
+                // carrier-grade NAT
+                || (a == 100 && (64..128).contains(&b))
+                // benchmarking
+                || (a == 198 && (b == 18 || b == 19))
+                // reserved
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_address(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    // unique local
+                    || (first & 0xfe00) == 0xfc00
+                    // link-local
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+impl fmt::Display for WebhookEndpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", serde_json::to_string(&self).unwrap())
+    }
+}
+
+impl fmt::Display for WebhookDelivery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", serde_json::to_string(&self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_internal_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn accepts_public_addresses() {
+        for ip in [
+            "93.184.216.34",
+            "8.8.8.8",
+            "2606:4700::1111",
+            "::ffff:1.1.1.1",
+        ] {
+            assert!(is_public_address(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn rejects_non_http_and_internal_urls() {
+        for url in [
+            "ftp://example.com/hook",
+            "file:///etc/passwd",
+            "http://127.0.0.1:8080/hook",
+            "https://[::1]/hook",
+            "http://169.254.169.254/latest/meta-data",
+        ] {
+            assert!(resolve_public_endpoint(url).await.is_err(), "{url}");
+        }
+    }
+}
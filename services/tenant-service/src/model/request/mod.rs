@@ -1 +1,2 @@
-
+pub mod tenant_request;
+pub mod webhook_request;
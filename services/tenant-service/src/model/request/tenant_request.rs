@@ -0,0 +1,95 @@
+use crate::model::domain::tenant::{Tenant, TenantStatus, TenantTier};
+use bson::Uuid;
+use serde::Deserialize;
+use validator::Validate;
+
+/// The fields a tenant is created with. The id and the timestamps are set by
+/// the service, the status and the tier by administrators only.
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct CreateTenant {
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "COMPANY_NAME")]
+    pub company_name: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "ACCOUNT_NAME")]
+    pub account_name: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "OWNER_NAME")]
+    pub owner_name: Option<String>,
+    #[validate(required, email)]
+    #[serde(rename = "EMAIL")]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 32))]
+    #[serde(rename = "PHONE")]
+    pub phone_number: Option<String>,
+    #[serde(rename = "STATUS")]
+    pub status: Option<TenantStatus>,
+    #[serde(rename = "TIER")]
+    pub tier: Option<TenantTier>,
+}
+
+impl From<CreateTenant> for Tenant {
+    fn from(create: CreateTenant) -> Self {
+        Tenant {
+            id: None,
+            company_name: create.company_name,
+            account_name: create.account_name,
+            owner_name: create.owner_name,
+            email: create.email,
+            phone_number: create.phone_number,
+            status: create.status,
+            tier: create.tier,
+            created_at: None,
+            updated_at: None,
+            deleted_at: None,
+            version: None,
+        }
+    }
+}
+
+/// The fields of a tenant that can be changed, the status and the tier by
+/// administrators only.
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct UpdateTenant {
+    #[validate(required)]
+    #[serde(rename = "_id")]
+    pub id: Option<Uuid>,
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "COMPANY_NAME")]
+    pub company_name: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "ACCOUNT_NAME")]
+    pub account_name: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "OWNER_NAME")]
+    pub owner_name: Option<String>,
+    #[validate(email)]
+    #[serde(rename = "EMAIL")]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 32))]
+    #[serde(rename = "PHONE")]
+    pub phone_number: Option<String>,
+    #[serde(rename = "STATUS")]
+    pub status: Option<TenantStatus>,
+    #[serde(rename = "TIER")]
+    pub tier: Option<TenantTier>,
+}
+
+impl From<UpdateTenant> for Tenant {
+    fn from(update: UpdateTenant) -> Self {
+        Tenant {
+            id: update.id,
+            company_name: update.company_name,
+            account_name: update.account_name,
+            owner_name: update.owner_name,
+            email: update.email,
+            phone_number: update.phone_number,
+            status: update.status,
+            tier: update.tier,
+            created_at: None,
+            updated_at: None,
+            deleted_at: None,
+            version: None,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use bson::Uuid;
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct CreateWebhookEndpoint {
+    #[validate(required)]
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: Option<Uuid>,
+    #[validate(required, url)]
+    #[serde(rename = "URL")]
+    pub url: Option<String>,
+    #[validate(required, length(min = 1))]
+    #[serde(rename = "EVENT_TYPES")]
+    pub event_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct UpdateWebhookEndpoint {
+    #[validate(required)]
+    #[serde(rename = "_id")]
+    pub id: Option<Uuid>,
+    #[validate(url)]
+    #[serde(rename = "URL")]
+    pub url: Option<String>,
+    #[validate(length(min = 1))]
+    #[serde(rename = "EVENT_TYPES")]
+    pub event_types: Option<Vec<String>>,
+    #[serde(rename = "ACTIVE")]
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebhookQuery {
+    #[serde(rename = "_id")]
+    pub id: Option<Uuid>,
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DeliveryQuery {
+    #[serde(rename = "_id")]
+    pub id: Option<Uuid>,
+    #[serde(rename = "ENDPOINT_ID")]
+    pub endpoint_id: Option<Uuid>,
+}
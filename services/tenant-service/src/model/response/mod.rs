@@ -1 +1 @@
-
+pub mod webhook_response;
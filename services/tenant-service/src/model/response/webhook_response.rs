@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+use crate::model::domain::webhook::WebhookEndpoint;
+
+/// Returned once, upon registration: the signing key is never exposed again.
+#[derive(Debug, Serialize, Clone)]
+pub struct RegisteredWebhookEndpoint {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    #[serde(rename = "SIGNING_KEY")]
+    pub signing_key: String,
+}
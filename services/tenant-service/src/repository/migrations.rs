@@ -0,0 +1,71 @@
+use crate::model::domain::{tenant::prelude as tenant, webhook::prelude as webhook};
+use common::{
+    client::db_mongo::{self, IndexDefinition, Migration, Schema},
+    error::InternalError,
+};
+use futures::future::BoxFuture;
+use mongodb::{
+    bson::{doc, Document},
+    Database,
+};
+
+pub const SCHEMA: Schema = Schema {
+    migrations: &[
+        Migration::new(1, "create collections", create_collections),
+        Migration::new(2, "version tenants", version_tenants),
+    ],
+    indexes: &[
+        IndexDefinition::new(
+            tenant::COLLECTION_TENANTS,
+            "tenants_email",
+            &[(tenant::EMAIL, 1)],
+        ),
+        // the soft deleted tenants due for purge
+        IndexDefinition::new(
+            tenant::COLLECTION_TENANTS,
+            "tenants_deleted_at",
+            &[(tenant::DELETED_AT, 1)],
+        ),
+        IndexDefinition::new(
+            webhook::COLLECTION_WEBHOOK_ENDPOINTS,
+            "webhook_endpoints_tenant_id",
+            &[(webhook::TENANT_ID, 1), (webhook::ACTIVE, 1)],
+        ),
+        // the delivery log of an endpoint
+        IndexDefinition::new(
+            webhook::COLLECTION_WEBHOOK_DELIVERIES,
+            "webhook_deliveries_endpoint_id",
+            &[(webhook::ENDPOINT_ID, 1), (webhook::CREATED_AT, -1)],
+        ),
+        // the due deliveries claimed by the dispatcher
+        IndexDefinition::new(
+            webhook::COLLECTION_WEBHOOK_DELIVERIES,
+            "webhook_deliveries_due",
+            &[(webhook::STATUS, 1), (webhook::NEXT_ATTEMPT_AT, 1)],
+        ),
+    ],
+};
+
+fn create_collections(db: &Database) -> BoxFuture<'_, Result<(), InternalError>> {
+    Box::pin(db_mongo::create_collections(
+        db,
+        &[
+            tenant::COLLECTION_TENANTS,
+            webhook::COLLECTION_WEBHOOK_ENDPOINTS,
+            webhook::COLLECTION_WEBHOOK_DELIVERIES,
+        ],
+    ))
+}
+
+fn version_tenants(db: &Database) -> BoxFuture<'_, Result<(), InternalError>> {
+    Box::pin(async move {
+        db.collection::<Document>(tenant::COLLECTION_TENANTS)
+            .update_many(
+                doc! { tenant::VERSION: { "$exists": false } },
+                doc! { "$set": { tenant::VERSION: 1_i64 } },
+                None,
+            )
+            .await?;
+        Ok(())
+    })
+}
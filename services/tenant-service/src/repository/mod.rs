@@ -1 +1,6 @@
+pub mod migrations;
+pub mod mongo_tenant_repository;
+#[cfg(feature = "postgres")]
+pub mod postgres_tenant_repository;
 pub mod tenant_repository;
+pub mod webhook_repository;
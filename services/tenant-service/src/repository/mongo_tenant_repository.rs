@@ -0,0 +1,148 @@
+use super::tenant_repository::TenantRepository;
+use crate::model::domain::tenant::{prelude::*, Tenant};
+use async_trait::async_trait;
+use bson::Uuid;
+use chrono::{DateTime, Utc};
+use common::{
+    client::{db_mongo, mongo_repository::MongoRepository},
+    error::InternalError,
+    model::{
+        request::{
+            filter_request::FilterRequest,
+            page_request::PageRequest,
+            search_request::SearchRequest,
+        },
+        response::{page_response::PageResponse, search_hit::SearchHit},
+    },
+};
+use mongodb::{bson::doc, ClientSession, Database};
+
+type Tenants = MongoRepository<Tenant>;
+
+/// The tenants stored in the `tenants` collection.
+#[derive(Debug, Clone)]
+pub struct MongoTenantRepository {
+    db: Database,
+}
+
+impl MongoTenantRepository {
+    pub fn new(db: Database) -> Self {
+        MongoTenantRepository { db }
+    }
+
+    /// Create the indexes the listings and the search of tenants rely on.
+    pub async fn create_indexes(&self) -> Result<(), InternalError> {
+        let repository = Tenants::new(&self.db);
+        repository.create_sort_indexes().await?;
+        repository.create_search_index().await
+    }
+}
+
+#[async_trait]
+impl TenantRepository for MongoTenantRepository {
+    fn store_name(&self) -> &'static str {
+        "MongoDB"
+    }
+
+    async fn ping(&self) -> Result<(), InternalError> {
+        db_mongo::ping(&self.db).await.map(|_| ())
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<Tenant>, InternalError> {
+        Tenants::new(&self.db).find_by_id(id).await
+    }
+
+    async fn find_all_with_query(
+        &self,
+        cond: &Tenant,
+        filter_request: &FilterRequest,
+    ) -> Result<Vec<Tenant>, InternalError> {
+        let filter = Tenants::filter_with_expression(cond, filter_request)?;
+        Tenants::new(&self.db).find_all_matching(filter).await
+    }
+
+    async fn find_all_paginated_with_query(
+        &self,
+        cond: &Tenant,
+        filter_request: &FilterRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<Tenant>, InternalError> {
+        let filter = Tenants::filter_with_expression(cond, filter_request)?;
+        Tenants::new(&self.db)
+            .find_paginated_matching(filter, page_request)
+            .await
+    }
+
+    async fn search(
+        &self,
+        search_request: &SearchRequest,
+        tenant_id: Option<Uuid>,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<SearchHit<Tenant>>, InternalError> {
+        let scope = tenant_id
+            .map(|tenant_id| doc! { ID: tenant_id })
+            .unwrap_or_default();
+        Tenants::new(&self.db)
+            .search(scope, search_request, page_request)
+            .await
+    }
+
+    async fn insert_one(&self, tenant: &Tenant) -> Result<Tenant, InternalError> {
+        Tenants::new(&self.db).insert_one(tenant).await
+    }
+
+    async fn update_by_id(
+        &self,
+        tenant: &Tenant,
+        expected_version: Option<i64>,
+    ) -> Result<Option<Tenant>, InternalError> {
+        Tenants::new(&self.db)
+            .update_by_id_checked(tenant, expected_version)
+            .await
+    }
+
+    async fn delete_one(
+        &self,
+        id: &Uuid,
+        expected_version: Option<i64>,
+    ) -> Result<bool, InternalError> {
+        Tenants::new(&self.db)
+            .delete_by_id_checked(id, expected_version)
+            .await
+    }
+
+    async fn delete_one_with_session(
+        &self,
+        id: &Uuid,
+        expected_version: Option<i64>,
+        session: &mut ClientSession,
+    ) -> Result<bool, InternalError> {
+        Tenants::new(&self.db)
+            .delete_by_id_checked_with_session(id, expected_version, Some(session))
+            .await
+    }
+
+    async fn restore_one(&self, id: &Uuid) -> Result<bool, InternalError> {
+        Tenants::new(&self.db).restore_by_id(id).await
+    }
+
+    async fn find_deleted_before(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Tenant>, InternalError> {
+        Tenants::new(&self.db)
+            .find_deleted_before(deleted_before, limit)
+            .await
+    }
+
+    async fn purge_one(
+        &self,
+        id: &Uuid,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<u64, InternalError> {
+        Tenants::new(&self.db)
+            .purge_by_id_with_session(id, deleted_before, None)
+            .await
+    }
+}
@@ -0,0 +1,147 @@
+use super::tenant_repository::TenantRepository;
+use crate::model::domain::tenant::{prelude::*, Tenant};
+use async_trait::async_trait;
+use bson::Uuid;
+use chrono::{DateTime, Utc};
+use common::{
+    client::{
+        db_postgres::{self, DatabasePool},
+        postgres_repository::PostgresRepository,
+    },
+    error::InternalError,
+    model::{
+        request::{
+            filter_request::FilterRequest,
+            page_request::PageRequest,
+            search_request::SearchRequest,
+        },
+        response::{page_response::PageResponse, search_hit::SearchHit},
+    },
+};
+use mongodb::ClientSession;
+use sqlx::migrate::Migrator;
+
+/// The schema of the `tenants` table, see `migrations`.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+type Tenants = PostgresRepository<Tenant>;
+
+/// The tenants stored in the `tenants` table.
+#[derive(Debug, Clone)]
+pub struct PostgresTenantRepository {
+    pool: DatabasePool,
+}
+
+impl PostgresTenantRepository {
+    pub fn new(pool: DatabasePool) -> Self {
+        PostgresTenantRepository { pool }
+    }
+}
+
+#[async_trait]
+impl TenantRepository for PostgresTenantRepository {
+    fn store_name(&self) -> &'static str {
+        "PostgreSQL"
+    }
+
+    async fn ping(&self) -> Result<(), InternalError> {
+        db_postgres::ping(&self.pool).await
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<Tenant>, InternalError> {
+        Tenants::new(&self.pool).find_by_id(id).await
+    }
+
+    async fn find_all_with_query(
+        &self,
+        cond: &Tenant,
+        filter_request: &FilterRequest,
+    ) -> Result<Vec<Tenant>, InternalError> {
+        let filter = Tenants::filter_with_expression(cond, filter_request)?;
+        Tenants::new(&self.pool).find_all_matching(filter).await
+    }
+
+    async fn find_all_paginated_with_query(
+        &self,
+        cond: &Tenant,
+        filter_request: &FilterRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<Tenant>, InternalError> {
+        let filter = Tenants::filter_with_expression(cond, filter_request)?;
+        Tenants::new(&self.pool)
+            .find_paginated_matching(filter, page_request)
+            .await
+    }
+
+    async fn search(
+        &self,
+        search_request: &SearchRequest,
+        tenant_id: Option<Uuid>,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<SearchHit<Tenant>>, InternalError> {
+        let scope = match tenant_id {
+            Some(tenant_id) => Tenants::field_filter(ID, &serde_json::to_value(tenant_id)?)?,
+            None => Default::default(),
+        };
+        Tenants::new(&self.pool)
+            .search(scope, search_request, page_request)
+            .await
+    }
+
+    async fn insert_one(&self, tenant: &Tenant) -> Result<Tenant, InternalError> {
+        Tenants::new(&self.pool).insert_one(tenant).await
+    }
+
+    async fn update_by_id(
+        &self,
+        tenant: &Tenant,
+        expected_version: Option<i64>,
+    ) -> Result<Option<Tenant>, InternalError> {
+        Tenants::new(&self.pool)
+            .update_by_id_checked(tenant, expected_version)
+            .await
+    }
+
+    async fn delete_one(
+        &self,
+        id: &Uuid,
+        expected_version: Option<i64>,
+    ) -> Result<bool, InternalError> {
+        Tenants::new(&self.pool)
+            .delete_by_id_checked(id, expected_version)
+            .await
+    }
+
+    async fn delete_one_with_session(
+        &self,
+        id: &Uuid,
+        expected_version: Option<i64>,
+        _: &mut ClientSession,
+    ) -> Result<bool, InternalError> {
+        self.delete_one(id, expected_version).await
+    }
+
+    async fn restore_one(&self, id: &Uuid) -> Result<bool, InternalError> {
+        Tenants::new(&self.pool).restore_by_id(id).await
+    }
+
+    async fn find_deleted_before(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Tenant>, InternalError> {
+        Tenants::new(&self.pool)
+            .find_deleted_before(deleted_before, limit)
+            .await
+    }
+
+    async fn purge_one(
+        &self,
+        id: &Uuid,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<u64, InternalError> {
+        Tenants::new(&self.pool)
+            .purge_by_id(id, deleted_before)
+            .await
+    }
+}
@@ -0,0 +1,276 @@
+use crate::model::domain::webhook::{
+    prelude::*,
+    DeliveryAttempt,
+    DeliveryStatus,
+    WebhookDelivery,
+    WebhookEndpoint,
+};
+use bson::{Document, Uuid};
+use chrono::{DateTime, Utc};
+use common::{
+    client::mongo_repository::find_page,
+    error::InternalError,
+    model::{
+        request::page_request::{PageRequest, SortOrder},
+        response::page_response::PageResponse,
+    },
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    ClientSession,
+    Database,
+};
+
+pub async fn find_endpoint_by_id(
+    id: &Uuid,
+    db: &Database,
+) -> Result<Option<WebhookEndpoint>, InternalError> {
+    let filter = doc! { ID: id };
+    let endpoint = db
+        .collection::<WebhookEndpoint>(COLLECTION_WEBHOOK_ENDPOINTS)
+        .find_one(filter, None)
+        .await?;
+    Ok(endpoint)
+}
+
+pub async fn find_endpoints_by_tenant(
+    tenant_id: &Uuid,
+    db: &Database,
+) -> Result<Vec<WebhookEndpoint>, InternalError> {
+    let find_opts = FindOptions::builder().sort(doc! { CREATED_AT: 1 }).build();
+    let cursor = db
+        .collection::<WebhookEndpoint>(COLLECTION_WEBHOOK_ENDPOINTS)
+        .find(doc! { TENANT_ID: tenant_id }, find_opts)
+        .await?;
+    Ok(cursor.try_collect().await?)
+}
+
+/// Active endpoints of `tenant_id` subscribed to `event_type`.
+pub async fn find_subscribed_endpoints(
+    tenant_id: &Uuid,
+    event_type: &str,
+    db: &Database,
+) -> Result<Vec<WebhookEndpoint>, InternalError> {
+    let cursor = db
+        .collection::<WebhookEndpoint>(COLLECTION_WEBHOOK_ENDPOINTS)
+        .find(
+            doc! {
+                TENANT_ID: tenant_id,
+                ACTIVE: true,
+                EVENT_TYPES: { "$in": [event_type, WEBHOOK_EVENT_ALL] },
+            },
+            None,
+        )
+        .await?;
+    Ok(cursor.try_collect().await?)
+}
+
+pub async fn insert_endpoint(
+    endpoint: &WebhookEndpoint,
+    db: &Database,
+) -> Result<(), InternalError> {
+    db.collection::<WebhookEndpoint>(COLLECTION_WEBHOOK_ENDPOINTS)
+        .insert_one(endpoint, None)
+        .await?;
+    Ok(())
+}
+
+pub async fn update_endpoint(
+    id: &Uuid,
+    url: Option<&str>,
+    event_types: Option<&[String]>,
+    active: Option<bool>,
+    db: &Database,
+) -> Result<u64, InternalError> {
+    let mut update = Document::new();
+    if let Some(url) = url {
+        update.insert(URL, url);
+    }
+    if let Some(event_types) = event_types {
+        update.insert(EVENT_TYPES, event_types);
+    }
+    if let Some(active) = active {
+        update.insert(ACTIVE, active);
+    }
+    if update.is_empty() {
+        return Err(InternalError::DbUpdateEmpty);
+    }
+    update.insert(UPDATED_AT, Utc::now().to_rfc3339());
+
+    let res = db
+        .collection::<WebhookEndpoint>(COLLECTION_WEBHOOK_ENDPOINTS)
+        .update_one(doc! { ID: id }, doc! { "$set": update }, None)
+        .await?;
+    Ok(res.modified_count)
+}
+
+pub async fn delete_endpoint(id: &Uuid, db: &Database) -> Result<u64, InternalError> {
+    let res = db
+        .collection::<WebhookEndpoint>(COLLECTION_WEBHOOK_ENDPOINTS)
+        .delete_one(doc! { ID: id }, None)
+        .await?;
+    Ok(res.deleted_count)
+}
+
+/// Delete the endpoints of a tenant, in the transaction of `session` if any.
+pub async fn delete_endpoints_by_tenant(
+    tenant_id: &Uuid,
+    session: Option<&mut ClientSession>,
+    db: &Database,
+) -> Result<u64, InternalError> {
+    let collection = db.collection::<WebhookEndpoint>(COLLECTION_WEBHOOK_ENDPOINTS);
+    let filter = doc! { TENANT_ID: tenant_id };
+    let res = match session {
+        Some(session) => {
+            collection
+                .delete_many_with_session(filter, None, session)
+                .await?
+        }
+        None => collection.delete_many(filter, None).await?,
+    };
+    Ok(res.deleted_count)
+}
+
+/// Delete the deliveries of a tenant not made yet, in the transaction of
+/// `session` if any.
+pub async fn delete_pending_deliveries_by_tenant(
+    tenant_id: &Uuid,
+    session: Option<&mut ClientSession>,
+    db: &Database,
+) -> Result<u64, InternalError> {
+    let collection = db.collection::<WebhookDelivery>(COLLECTION_WEBHOOK_DELIVERIES);
+    let filter = doc! {
+        TENANT_ID: tenant_id,
+        STATUS: { "$in": [DeliveryStatus::Pending, DeliveryStatus::InFlight] },
+    };
+    let res = match session {
+        Some(session) => {
+            collection
+                .delete_many_with_session(filter, None, session)
+                .await?
+        }
+        None => collection.delete_many(filter, None).await?,
+    };
+    Ok(res.deleted_count)
+}
+
+/// Delete the deliveries of a tenant, in the transaction of `session` if any.
+pub async fn delete_deliveries_by_tenant(
+    tenant_id: &Uuid,
+    session: Option<&mut ClientSession>,
+    db: &Database,
+) -> Result<u64, InternalError> {
+    let collection = db.collection::<WebhookDelivery>(COLLECTION_WEBHOOK_DELIVERIES);
+    let filter = doc! { TENANT_ID: tenant_id };
+    let res = match session {
+        Some(session) => {
+            collection
+                .delete_many_with_session(filter, None, session)
+                .await?
+        }
+        None => collection.delete_many(filter, None).await?,
+    };
+    Ok(res.deleted_count)
+}
+
+pub async fn find_delivery_by_id(
+    id: &Uuid,
+    db: &Database,
+) -> Result<Option<WebhookDelivery>, InternalError> {
+    let filter = doc! { ID: id };
+    let delivery = db
+        .collection::<WebhookDelivery>(COLLECTION_WEBHOOK_DELIVERIES)
+        .find_one(filter, None)
+        .await?;
+    Ok(delivery)
+}
+
+/// The delivery log of an endpoint, most recent first.
+pub async fn find_deliveries_paginated(
+    endpoint_id: &Uuid,
+    page_request: &PageRequest,
+    db: &Database,
+) -> Result<PageResponse<WebhookDelivery>, InternalError> {
+    find_page(
+        &db.collection::<WebhookDelivery>(COLLECTION_WEBHOOK_DELIVERIES),
+        doc! { ENDPOINT_ID: endpoint_id },
+        &SortOrder::desc(CREATED_AT),
+        page_request,
+    )
+    .await
+}
+
+pub async fn insert_deliveries(
+    deliveries: &[WebhookDelivery],
+    db: &Database,
+) -> Result<(), InternalError> {
+    if deliveries.is_empty() {
+        return Ok(());
+    }
+    db.collection::<WebhookDelivery>(COLLECTION_WEBHOOK_DELIVERIES)
+        .insert_many(deliveries, None)
+        .await?;
+    Ok(())
+}
+
+/// Atomically claim the next due delivery so that it is attempted by a
+/// single dispatcher even when several replicas are running. The claim is a
+/// lease: a delivery left in flight by a crashed replica is due again once
+/// `lease_until` is reached.
+pub async fn claim_next_due_delivery(
+    now: DateTime<Utc>,
+    lease_until: DateTime<Utc>,
+    db: &Database,
+) -> Result<Option<WebhookDelivery>, InternalError> {
+    let options = FindOneAndUpdateOptions::builder()
+        .sort(doc! { NEXT_ATTEMPT_AT: 1 })
+        .return_document(ReturnDocument::After)
+        .build();
+
+    let delivery = db
+        .collection::<WebhookDelivery>(COLLECTION_WEBHOOK_DELIVERIES)
+        .find_one_and_update(
+            doc! {
+                STATUS: { "$in": [DeliveryStatus::Pending, DeliveryStatus::InFlight] },
+                NEXT_ATTEMPT_AT: { "$lte": bson::DateTime::from_chrono(now) },
+            },
+            doc! {
+                "$set": {
+                    STATUS: DeliveryStatus::InFlight,
+                    NEXT_ATTEMPT_AT: bson::DateTime::from_chrono(lease_until),
+                    UPDATED_AT: now.to_rfc3339(),
+                },
+            },
+            options,
+        )
+        .await?;
+    Ok(delivery)
+}
+
+/// Append `attempt` to the delivery log and move the delivery to `status`.
+pub async fn record_attempt(
+    id: &Uuid,
+    attempt: &DeliveryAttempt,
+    status: DeliveryStatus,
+    next_attempt_at: DateTime<Utc>,
+    db: &Database,
+) -> Result<u64, InternalError> {
+    let res = db
+        .collection::<WebhookDelivery>(COLLECTION_WEBHOOK_DELIVERIES)
+        .update_one(
+            doc! { ID: id },
+            doc! {
+                "$push": { ATTEMPTS: bson::to_bson(attempt)? },
+                "$set": {
+                    STATUS: status,
+                    NEXT_ATTEMPT_AT: bson::DateTime::from_chrono(next_attempt_at),
+                    UPDATED_AT: Utc::now().to_rfc3339(),
+                },
+            },
+            None,
+        )
+        .await?;
+    Ok(res.modified_count)
+}
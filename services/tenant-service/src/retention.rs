@@ -0,0 +1,78 @@
+use crate::{
+    context::AppContext,
+    model::domain::tenant::prelude::CACHE_TAG_PREFIX_TENANT,
+    repository::webhook_repository,
+    secrets,
+};
+use chrono::{DateTime, Utc};
+use common::{
+    client::db_mongo,
+    error::InternalError,
+    model::event::{
+        v1::{
+            tenant::{prelude::SERVICE_TENANT_SUBJECT, TenantDeletedMessage},
+            Event,
+        },
+        EventMessage,
+        EventMetadata,
+    },
+};
+use nats_actor::EventMessage as NatsEventMessage;
+
+/// Permanently delete up to `limit` of the tenants soft deleted before
+/// `deleted_before`, along with their webhooks, emitting an event for every
+/// tenant purged.
+pub async fn purge_deleted_tenants(
+    ctx: &AppContext,
+    deleted_before: DateTime<Utc>,
+    limit: i64,
+) -> Result<u64, InternalError> {
+    let tenants = ctx
+        .tenants()
+        .find_deleted_before(deleted_before, limit)
+        .await?;
+
+    let mut purged = 0;
+    for id in tenants.iter().filter_map(|tenant| tenant.id) {
+        // the signing keys are kept in vault, out of the transaction
+        let endpoints = webhook_repository::find_endpoints_by_tenant(&id, ctx.db()).await?;
+
+        // the tenant may be kept in Postgres, out of the transaction of its
+        // webhooks. Purged first, so that a failure in between only leaves
+        // webhooks of a missing tenant, which are never dispatched, and
+        // skipped when restored since
+        if ctx.tenants().purge_one(&id, deleted_before).await? == 0 {
+            continue;
+        }
+        db_mongo::with_transaction(ctx.mongo_client(), &(ctx.db(), id), |session, (db, id)| {
+            Box::pin(async move {
+                let _: u64 =
+                    webhook_repository::delete_endpoints_by_tenant(id, Some(&mut *session), db)
+                        .await?;
+                let _: u64 =
+                    webhook_repository::delete_deliveries_by_tenant(id, Some(session), db).await?;
+                Ok(())
+            })
+        })
+        .await?;
+        purged += 1;
+
+        for endpoint in &endpoints {
+            secrets::delete_webhook(ctx.vault(), ctx.webhook_secrets_path(), &endpoint.id).await?;
+        }
+        ctx.cache()
+            .invalidate_tag(&format!("{CACHE_TAG_PREFIX_TENANT}_{id}"))
+            .await?;
+
+        let tenant_deleted_event = Event::TenantDeleted(EventMessage {
+            meta: EventMetadata::new(SERVICE_TENANT_SUBJECT.into(), "trace_id"),
+            payload: TenantDeletedMessage {
+                tenant_id: id.to_string(),
+            },
+        });
+        ctx.event_publisher().do_send(NatsEventMessage {
+            event: tenant_deleted_event.try_into()?,
+        });
+    }
+    Ok(purged)
+}
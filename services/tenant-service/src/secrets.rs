@@ -1,15 +1,38 @@
 use crate::settings::Settings;
+use bson::Uuid;
+#[cfg(feature = "postgres")]
+use common::client::{db_postgres::PostgresClientSecrets, DbBackend};
 use common::{
-    client::{cache_redis::RedisClientSecrets, db_mongo::MongoClientSecrets, sm_vault},
+    client::{
+        cache_redis::RedisClientSecrets,
+        db_mongo::MongoClientSecrets,
+        sm_vault::{self, VaultClientConfig, VaultKvPath},
+    },
     error::InternalError,
+    util::access_token::AccessTokenSecrets,
 };
-use serde::Deserialize;
+use secrecy::Secret;
+use serde::{Deserialize, Serialize};
 use vaultrs::client::VaultClient;
 
 #[derive(Debug, Deserialize)]
 pub struct Secrets {
     pub cache: RedisClientSecrets,
     pub db: MongoClientSecrets,
+    #[cfg(feature = "postgres")]
+    pub postgres: Option<PostgresClientSecrets>,
+    pub access_token: AccessTokenSecrets,
+}
+
+/// Signing key of a webhook endpoint, stored under the endpoint id.
+#[derive(Debug, Deserialize)]
+pub struct WebhookSecrets {
+    pub signing_key: Secret<String>,
+}
+
+#[derive(Serialize)]
+struct WebhookSecretsEntry<'a> {
+    signing_key: &'a str,
 }
 
 pub async fn read(settings: &Settings) -> Result<Secrets, InternalError> {
@@ -21,8 +44,63 @@ pub async fn read(settings: &Settings) -> Result<Secrets, InternalError> {
     let db_secrets: MongoClientSecrets =
         sm_vault::get_secret_value(&vault_client, &settings.db_secrets_path).await?;
 
+    #[cfg(feature = "postgres")]
+    let postgres_secrets: Option<PostgresClientSecrets> =
+        match (settings.db_backend, &settings.postgres_secrets_path) {
+            (DbBackend::Postgres, Some(path)) => {
+                Some(sm_vault::get_secret_value(&vault_client, path).await?)
+            }
+            _ => None,
+        };
+
+    let access_token_secrets: AccessTokenSecrets =
+        sm_vault::get_secret_value(&vault_client, &settings.access_token_secrets_path).await?;
+
     Ok(Secrets {
         cache: cache_secrets,
         db: db_secrets,
+        #[cfg(feature = "postgres")]
+        postgres: postgres_secrets,
+        access_token: access_token_secrets,
     })
 }
+
+fn webhook_secrets_path(base: &VaultKvPath, endpoint_id: &Uuid) -> VaultKvPath {
+    VaultKvPath {
+        mount: base.mount.clone(),
+        path: format!("{}/{}", base.path, endpoint_id),
+    }
+}
+
+pub async fn read_webhook(
+    vault: &VaultClientConfig,
+    base: &VaultKvPath,
+    endpoint_id: &Uuid,
+) -> Result<WebhookSecrets, InternalError> {
+    let vault_client: VaultClient = sm_vault::connect(vault)?;
+    sm_vault::get_secret_value(&vault_client, &webhook_secrets_path(base, endpoint_id)).await
+}
+
+pub async fn write_webhook(
+    vault: &VaultClientConfig,
+    base: &VaultKvPath,
+    endpoint_id: &Uuid,
+    signing_key: &str,
+) -> Result<(), InternalError> {
+    let vault_client: VaultClient = sm_vault::connect(vault)?;
+    sm_vault::set_secret_value(
+        &vault_client,
+        &webhook_secrets_path(base, endpoint_id),
+        &WebhookSecretsEntry { signing_key },
+    )
+    .await
+}
+
+pub async fn delete_webhook(
+    vault: &VaultClientConfig,
+    base: &VaultKvPath,
+    endpoint_id: &Uuid,
+) -> Result<(), InternalError> {
+    let vault_client: VaultClient = sm_vault::connect(vault)?;
+    sm_vault::delete_secret_value(&vault_client, &webhook_secrets_path(base, endpoint_id)).await
+}
@@ -1,21 +1,42 @@
+#[cfg(feature = "postgres")]
+use common::client::db_postgres::PostgresClientSettings;
 use common::{
     client::{
         cache_redis::RedisClientSettings,
         db_mongo::MongoClientSettings,
         sm_vault::{VaultClientConfig, VaultKvPath},
+        DbBackend,
     },
-    util::configuration,
+    util::{client_ip::TrustedProxies, configuration, retention::RetentionSettings},
 };
+use nats_actor::NatsClientSettings;
 use serde_aux::field_attributes::deserialize_number_from_string;
 
 #[derive(Debug, serde::Deserialize, Clone)]
 pub struct Settings {
     pub application: ApplicationSettings,
     pub vault: VaultClientConfig,
+    /// The store of the tenants, `mongo` unless set. The webhooks are always
+    /// kept in Mongo.
+    #[serde(default)]
+    pub db_backend: DbBackend,
     pub db: MongoClientSettings,
     pub db_secrets_path: VaultKvPath,
+    #[cfg(feature = "postgres")]
+    pub postgres: Option<PostgresClientSettings>,
+    #[cfg(feature = "postgres")]
+    pub postgres_secrets_path: Option<VaultKvPath>,
     pub cache: RedisClientSettings,
     pub cache_secrets_path: VaultKvPath,
+    pub access_token_secrets_path: VaultKvPath,
+    pub webhook_secrets_path: VaultKvPath,
+    pub nats: NatsClientSettings,
+    pub webhook: WebhookSettings,
+    pub retention: RetentionSettings,
+    /// The proxies trusted to forward the address of the clients, none
+    /// unless set.
+    #[serde(default)]
+    pub trusted_proxies: TrustedProxies,
     pub log: LogSettings,
     pub tracer: Tracer,
 }
@@ -30,6 +51,24 @@ pub struct ApplicationSettings {
     pub workers: usize,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub max_json_payload_size: usize,
+    pub nats_publisher_mailbox_size: usize,
+    pub nats_subscriber_mailbox_size: usize,
+}
+
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct WebhookSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub poll_interval_secs: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub batch_size: usize,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub request_timeout_secs: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_attempts: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub initial_backoff_secs: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_backoff_secs: i64,
 }
 
 #[derive(Debug, serde::Deserialize, Clone)]
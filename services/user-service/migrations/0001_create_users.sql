@@ -0,0 +1,33 @@
+-- The users, a column per field of the entity. Text columns compare byte
+-- wise, as strings do in Mongo, so that both stores list users alike.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE TABLE IF NOT EXISTS users (
+    id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    email       TEXT COLLATE "C",
+    first_name  TEXT COLLATE "C",
+    last_name   TEXT COLLATE "C",
+    phone       TEXT COLLATE "C",
+    status      TEXT,
+    role        TEXT,
+    tenant_id   UUID,
+    created_at  TIMESTAMPTZ,
+    updated_at  TIMESTAMPTZ,
+    deleted_at  TIMESTAMPTZ,
+    version     BIGINT NOT NULL DEFAULT 1
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_email ON users (email);
+CREATE INDEX IF NOT EXISTS users_tenant_id ON users (tenant_id);
+-- the soft deleted users due for purge
+CREATE INDEX IF NOT EXISTS users_deleted_at ON users (deleted_at);
+
+-- the sorted listings, with the id as tie breaker
+CREATE INDEX IF NOT EXISTS users_email_id ON users (email, id);
+CREATE INDEX IF NOT EXISTS users_created_at_id ON users (created_at, id);
+CREATE INDEX IF NOT EXISTS users_updated_at_id ON users (updated_at, id);
+
+-- the search, matching regexes on the search fields
+CREATE INDEX IF NOT EXISTS users_search_email ON users USING gin (email gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS users_search_first_name ON users USING gin (first_name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS users_search_last_name ON users USING gin (last_name gin_trgm_ops);
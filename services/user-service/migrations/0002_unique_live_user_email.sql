@@ -0,0 +1,4 @@
+-- The email of a live user is unique: the soft deleted users no longer hold
+-- theirs, so that it can be used again.
+DROP INDEX IF EXISTS users_email;
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_live ON users (email) WHERE deleted_at IS NULL;
@@ -0,0 +1,6 @@
+-- The email of a live user is unique within its tenant only, the users
+-- without a tenant sharing the nil one.
+DROP INDEX IF EXISTS users_email_live;
+CREATE UNIQUE INDEX IF NOT EXISTS users_tenant_id_email_live
+    ON users (COALESCE(tenant_id, '00000000-0000-0000-0000-000000000000'::uuid), email)
+    WHERE deleted_at IS NULL;
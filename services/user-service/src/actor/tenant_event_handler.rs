@@ -0,0 +1,58 @@
+use crate::context::AppContext;
+use actix::{Actor, Context, Handler};
+use bson::Uuid;
+use common::{
+    error::InternalError,
+    model::event::{v1::Event, EventMessage},
+};
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// Provisions where the users of the tenants are kept as the tenants are
+/// created, and removes it once they are deleted.
+pub struct TenantEventHandler {
+    pub context: Arc<AppContext>,
+}
+
+impl Actor for TenantEventHandler {
+    type Context = Context<Self>;
+}
+
+// Define handler for `Event` message
+impl Handler<Event> for TenantEventHandler {
+    type Result = Result<(), std::io::Error>;
+
+    fn handle(&mut self, event: Event, _: &mut Context<Self>) -> Self::Result {
+        let context = Arc::clone(&self.context);
+
+        actix::spawn(async move {
+            if let Err(err) = apply(&context, event).await {
+                error!("Failed to provision the users of a tenant: {}", err);
+            }
+        });
+
+        Ok(())
+    }
+}
+
+async fn apply(ctx: &AppContext, event: Event) -> Result<(), InternalError> {
+    match event {
+        Event::TenantCreated(EventMessage { payload, .. }) => {
+            info!("Provisioning the users of tenant {}", payload.tenant_id);
+            ctx.users().provision(&tenant_id(&payload.tenant_id)?).await
+        }
+        Event::TenantDeleted(EventMessage { payload, .. }) => {
+            info!("Deprovisioning the users of tenant {}", payload.tenant_id);
+            ctx.users()
+                .deprovision(&tenant_id(&payload.tenant_id)?)
+                .await
+        }
+        _ => Ok(()),
+    }
+}
+
+fn tenant_id(tenant_id: &str) -> Result<Uuid, InternalError> {
+    Uuid::parse_str(tenant_id).map_err(|err| InternalError::InvalidFormatError {
+        cause: err.to_string(),
+    })
+}
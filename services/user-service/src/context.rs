@@ -1,24 +1,88 @@
-use common::client::cache_redis::Cache;
-use mongodb::Database;
+use crate::{
+    model::domain::user::prelude::CACHE_ENTITY_USER,
+    repository::user_repository::UserRepository,
+};
+use actix::Addr;
+use bson::Uuid;
+use common::{
+    client::cache_redis::Cache,
+    error::InternalError,
+    model::event::{
+        v1::{audit::AuditRecord, Event},
+        EventMessage,
+        EventMetadata,
+    },
+    util::tenant_scope::TenantScope,
+};
+use nats_actor::{publisher::NatsPublisher, EventMessage as NatsEventMessage};
 use std::sync::Arc;
 
 /// The AppContext contains all the global data commonly used in the vast
 /// majority of request handlers.
 #[derive(Debug)]
 pub struct AppContext {
-    pub(crate) db: Arc<Database>,
+    pub(crate) users: Arc<dyn UserRepository>,
     pub(crate) cache: Arc<Cache>,
+    pub(crate) event_publisher: Arc<Addr<NatsPublisher>>,
+    pub(crate) audit_publisher: Arc<Addr<NatsPublisher>>,
 }
 
 impl AppContext {
-    /// A MongoDB reference to the underlying database. Used to interract with
-    /// collections, etc.
-    pub fn db(&self) -> &Database {
-        &self.db
+    /// The store of the users, Mongo or Postgres.
+    pub fn users(&self) -> &dyn UserRepository {
+        self.users.as_ref()
     }
 
     /// A Redis cache pool.
     pub fn cache(&self) -> &Cache {
         &self.cache
     }
+
+    /// The cache key of the user `id` as read in `scope`. The reads of a
+    /// tenant only find its own users, so they are cached apart.
+    pub fn user_cache_key(&self, scope: &TenantScope, id: &Uuid) -> String {
+        let key = self.cache.key(CACHE_ENTITY_USER);
+        match scope.tenant_id() {
+            Some(tenant_id) => key.tenant(tenant_id).id(id),
+            None => key.id(id),
+        }
+    }
+
+    /// Invalidate the user `id` of the tenant `tenant_id` in every scope it
+    /// may be cached in.
+    pub async fn invalidate_user(
+        &self,
+        tenant_id: Option<Uuid>,
+        id: &Uuid,
+    ) -> Result<(), InternalError> {
+        let mut keys = vec![self.user_cache_key(&TenantScope::Platform, id)];
+        if let Some(tenant_id) = tenant_id {
+            keys.push(self.user_cache_key(&TenantScope::Tenant(tenant_id.to_uuid_0_8()), id));
+        }
+        let keys = keys.iter().map(String::as_str).collect::<Vec<_>>();
+        self.cache.invalidate(&keys).await
+    }
+
+    /// A NATS publisher for the user events.
+    pub fn event_publisher(&self) -> &Addr<NatsPublisher> {
+        &self.event_publisher
+    }
+
+    /// Publish `record` to the audit log, in the background. A failed publish
+    /// is retried while this instance runs, but the record is lost if it
+    /// stops first: the log is best effort, not written along with the action.
+    pub fn audit(&self, record: AuditRecord) -> Result<(), InternalError> {
+        let meta = EventMetadata::new(
+            record.source.clone(),
+            record.trace_id.as_deref().unwrap_or("trace_id"),
+        );
+        let audit_event = Event::AuditRecorded(EventMessage {
+            meta,
+            payload: record,
+        });
+        self.audit_publisher.do_send(NatsEventMessage {
+            event: audit_event.try_into()?,
+        });
+        Ok(())
+    }
 }
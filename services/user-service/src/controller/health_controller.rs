@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use actix_http::StatusCode;
 use actix_web::{web, HttpResponse, HttpResponseBuilder, Scope};
-use common::{client::db_mongo, error::InternalError};
+use common::error::InternalError;
 use serde::Serialize;
 use serde_json::json;
 
@@ -21,7 +21,8 @@ struct Health {
 
 pub async fn health(ctx: web::Data<AppContext>) -> Result<HttpResponse, InternalError> {
     let mut health = HashMap::<&str, Health>::new();
-    health.insert("mongodb", mongo_health(&ctx).await);
+    let store = ctx.users().store_name();
+    health.insert(store, db_health(&ctx).await);
 
     let status = match health.values().any(|health| !health.healthy) {
         true => StatusCode::SERVICE_UNAVAILABLE,
@@ -30,13 +31,14 @@ pub async fn health(ctx: web::Data<AppContext>) -> Result<HttpResponse, Internal
 
     Ok(HttpResponseBuilder::new(status).json(json!(
         {
-            "MongoDB": health["mongodb"],
+            store: health[store],
+            "cache": ctx.cache().stats(),
         }
     )))
 }
 
-async fn mongo_health(ctx: &web::Data<AppContext>) -> Health {
-    match db_mongo::ping(ctx.db()).await {
+async fn db_health(ctx: &web::Data<AppContext>) -> Health {
+    match ctx.users().ping().await {
         Err(err) => Health {
             healthy: false,
             message: Some(err.to_string()),
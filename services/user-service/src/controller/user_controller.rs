@@ -1,85 +1,147 @@
 use actix_web::{
+    http::header::IfMatch,
     web::{self},
     HttpResponse,
     Scope,
 };
 use bson::Uuid;
-use chrono::Utc;
 use common::{
     error::{ApiResult, InternalError},
-    model::request::page_request::PageRequest,
+    model::{
+        event::v1::{
+            audit::prelude::{
+                AUDIT_ACTION_USER_CREATE,
+                AUDIT_ACTION_USER_DELETE,
+                AUDIT_ACTION_USER_RESTORE,
+                AUDIT_ACTION_USER_UPDATE,
+                AUDIT_TARGET_USER,
+            },
+            user::prelude::SERVICE_USER_SUBJECT,
+        },
+        request::{
+            filter_request::FilterRequest,
+            page_request::PageRequest,
+            search_request::SearchRequest,
+        },
+    },
+    util::{
+        audit::AuditActor,
+        etag::{etag, expected_version},
+        principal::Principal,
+        tenant_scope::TenantScope,
+    },
 };
 use validator::Validate;
 
 use crate::{
     context::AppContext,
-    model::domain::user::{
-        prelude::{CACHE_KEY_PREFIX_USER_ID, CACHE_USER_EXPIRY},
-        User,
-        UserRole,
-        UserStatus,
+    model::{
+        domain::user::{prelude::CACHE_USER_EXPIRY, User, UserRole, UserStatus},
+        request::user_request::{CreateUser, UpdateUser},
     },
-    repository::user_repository,
 };
 
 pub fn router() -> Scope {
-    web::scope("/user").service(
-        web::resource("")
-            //.route(web::get().to(query))
-            .route(web::get().to(query_paginated))
-            .route(web::post().to(create))
-            .route(web::put().to(update_by_id))
-            .route(web::delete().to(delete_by_id)),
-    )
+    web::scope("/user")
+        .service(
+            web::resource("")
+                //.route(web::get().to(query))
+                .route(web::get().to(query_paginated))
+                .route(web::post().to(create))
+                .route(web::put().to(update_by_id))
+                .route(web::delete().to(delete_by_id)),
+        )
+        .service(web::resource("/search").route(web::get().to(search)))
+        .service(web::resource("/restore").route(web::post().to(restore_by_id)))
 }
 
-/// Http handler for querying users.
-#[tracing::instrument(name = "query", skip(user), level = "info")]
-pub async fn query(ctx: web::Data<AppContext>, web::Query(user): web::Query<User>) -> ApiResult {
+/// Http handler for querying users, among the users of the tenant of the
+/// caller.
+#[tracing::instrument(name = "query", skip(user, filter_request), level = "info")]
+pub async fn query(
+    ctx: web::Data<AppContext>,
+    scope: TenantScope,
+    web::Query(user): web::Query<User>,
+    web::Query(filter_request): web::Query<FilterRequest>,
+) -> ApiResult {
     match user.id {
-        Some(id) => get_by_id(ctx, &id).await,
-        None => get_by_condition(ctx, user).await,
+        Some(id) => get_by_id(ctx, &scope, &id).await,
+        None => get_by_condition(ctx, &scope, user, filter_request).await,
     }
 }
 
-/// Http handler for querying users with pagination.
-#[tracing::instrument(name = "query_paginated", skip(user, page_request), level = "info")]
+/// Http handler for querying users with pagination, among the users of the
+/// tenant of the caller.
+#[tracing::instrument(
+    name = "query_paginated",
+    skip(user, filter_request, page_request),
+    level = "info"
+)]
 pub async fn query_paginated(
     ctx: web::Data<AppContext>,
+    scope: TenantScope,
     web::Query(user): web::Query<User>,
+    web::Query(filter_request): web::Query<FilterRequest>,
     web::Query(page_request): web::Query<PageRequest>,
 ) -> ApiResult {
     match user.id {
-        Some(id) => get_by_id(ctx, &id).await,
-        None => get_paginated_by_condition(ctx, user, page_request).await,
+        Some(id) => get_by_id(ctx, &scope, &id).await,
+        None => get_paginated_by_condition(ctx, &scope, user, filter_request, page_request).await,
     }
 }
 
-async fn get_by_id(ctx: web::Data<AppContext>, id: &Uuid) -> ApiResult {
-    let cache_key_user_id = format!("{CACHE_KEY_PREFIX_USER_ID}_{id}");
-
-    match ctx.cache().get::<User>(&cache_key_user_id).await? {
-        Some(user) => Ok(HttpResponse::Ok()
-            .content_type("application/json")
-            .json(user)),
-        None => match user_repository::find_by_id(id, ctx.db()).await? {
-            Some(user) => {
-                ctx.cache()
-                    .set::<User>(&cache_key_user_id, user.clone(), CACHE_USER_EXPIRY)
-                    .await?;
-                Ok(HttpResponse::Ok()
-                    .content_type("application/json")
-                    .json(user))
-            }
-            None => Err(InternalError::UserNotFound {
-                user_id: id.to_uuid_0_8(),
-            }),
-        },
-    }
+/// Http handler for searching users by name or email. Callers acting for a tenant only
+/// find within it.
+#[tracing::instrument(name = "search", skip(search_request, page_request), level = "info")]
+pub async fn search(
+    ctx: web::Data<AppContext>,
+    scope: TenantScope,
+    web::Query(search_request): web::Query<SearchRequest>,
+    web::Query(page_request): web::Query<PageRequest>,
+) -> ApiResult {
+    search_request.validate()?;
+    page_request.validate()?;
+
+    let users = ctx
+        .users()
+        .search(&scope, &search_request, &page_request)
+        .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(users))
 }
 
-async fn get_by_condition(ctx: web::Data<AppContext>, user: User) -> ApiResult {
-    let users = user_repository::find_all_with_query(&user, ctx.db()).await?;
+async fn get_by_id(ctx: web::Data<AppContext>, scope: &TenantScope, id: &Uuid) -> ApiResult {
+    let cache_key_user_id = ctx.user_cache_key(scope, id);
+
+    let user = ctx
+        .cache()
+        .get_or_load(&cache_key_user_id, CACHE_USER_EXPIRY, || {
+            ctx.users().find_by_id(scope, id)
+        })
+        .await?
+        .ok_or(InternalError::UserNotFound {
+            user_id: id.to_uuid_0_8(),
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header(etag(user.version))
+        .json(user))
+}
+
+async fn get_by_condition(
+    ctx: web::Data<AppContext>,
+    scope: &TenantScope,
+    user: User,
+    filter_request: FilterRequest,
+) -> ApiResult {
+    filter_request.validate()?;
+
+    let users = ctx
+        .users()
+        .find_all_with_query(scope, &user, &filter_request)
+        .await?;
     Ok(HttpResponse::Ok()
         .content_type("application/json")
         .json(users))
@@ -87,73 +149,207 @@ async fn get_by_condition(ctx: web::Data<AppContext>, user: User) -> ApiResult {
 
 async fn get_paginated_by_condition(
     ctx: web::Data<AppContext>,
+    scope: &TenantScope,
     user: User,
+    filter_request: FilterRequest,
     page_request: PageRequest,
 ) -> ApiResult {
-    let users = user_repository::find_all_paginated_with_query(
-        &user,
-        page_request.page,
-        page_request.page_size,
-        ctx.db(),
-    )
-    .await?;
+    filter_request.validate()?;
+    page_request.validate()?;
+
+    let users = ctx
+        .users()
+        .find_all_paginated_with_query(scope, &user, &filter_request, &page_request)
+        .await?;
     Ok(HttpResponse::Ok()
         .content_type("application/json")
         .json(users))
 }
 
-/// Http handler for creating an user.
-#[tracing::instrument(name = "create", skip(user), level = "info")]
-pub async fn create(ctx: web::Data<AppContext>, user: web::Json<User>) -> ApiResult {
-    // verify necessary fields
-    if user.email.is_none() {
-        return Err(InternalError::RequestFormatError {
-            reason: "require fields: `EMAIL`".to_string(),
-        });
+/// Http handler for creating an user in the tenant of the caller, or across
+/// the platform in the tenant of the request. Only administrators can choose
+/// the status and the role of the user.
+#[tracing::instrument(name = "create", skip(create_user, actor), level = "info")]
+pub async fn create(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    scope: TenantScope,
+    actor: AuditActor,
+    web::Json(create_user): web::Json<CreateUser>,
+) -> ApiResult {
+    create_user.validate()?;
+    if create_user.requires_admin() {
+        principal.require_admin()?;
     }
+    let tenant_id = scope.owner(
+        create_user
+            .tenant_id
+            .map(|tenant_id| tenant_id.to_uuid_0_8()),
+    )?;
 
-    let now = Some(Utc::now());
+    let user = User::from(create_user);
     let to_create = User {
         id: Some(bson::Uuid::new()),
-        status: Some(UserStatus::Active),
-        role: Some(UserRole::User),
-        created_at: now,
-        updated_at: now,
-        ..user.0
+        tenant_id: Some(Uuid::from_uuid_0_8(tenant_id)),
+        status: Some(user.status.unwrap_or(UserStatus::Active)),
+        role: Some(user.role.unwrap_or(UserRole::User)),
+        ..user
     };
 
-    let user = user_repository::insert_one(&to_create, ctx.db()).await?;
+    let user = ctx
+        .users()
+        .insert_one(&TenantScope::Tenant(tenant_id), &to_create)
+        .await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_USER_SUBJECT,
+                AUDIT_ACTION_USER_CREATE,
+                AUDIT_TARGET_USER,
+                to_create.id.unwrap(),
+            )
+            .with_tenant(user.tenant_id)
+            .with_changes(None, Some(&user))?,
+    )?;
 
     Ok(HttpResponse::Ok()
         .content_type("application/json")
+        .insert_header(etag(user.version))
         .json(user))
 }
 
-/// Http handler for updating an user.
-#[tracing::instrument(name = "update_by_id", skip(user), level = "info")]
-pub async fn update_by_id(ctx: web::Data<AppContext>, user: web::Json<User>) -> ApiResult {
-    // verify necessary fields
-    if user.id.is_none() {
-        return Err(InternalError::RequestFormatError {
-            reason: "require fields: `_id`".to_string(),
-        });
+/// Http handler for updating an user. Users can only update themselves, and
+/// only administrators can change the status and the role of a user. With an `If-Match` header, the
+/// user is only updated if still at the version of its entity tag.
+#[tracing::instrument(
+    name = "update_by_id",
+    skip(actor, update_user, if_match),
+    level = "info"
+)]
+pub async fn update_by_id(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    scope: TenantScope,
+    actor: AuditActor,
+    web::Json(update_user): web::Json<UpdateUser>,
+    if_match: Option<web::Header<IfMatch>>,
+) -> ApiResult {
+    update_user.validate()?;
+    let id = update_user.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })?;
+    if update_user.requires_admin() || id.to_uuid_0_8() != principal.user_id {
+        principal.require_admin()?;
     }
+    let expected_version = expected_version(if_match.as_deref())?;
 
-    let _: u64 = user_repository::update_by_id(&user, ctx.db()).await?;
+    let user = User::from(update_user);
 
-    Ok(HttpResponse::Ok().finish())
+    let previous = ctx.users().find_by_id(&scope, &id).await?;
+    let updated = ctx
+        .users()
+        .update_by_id(&scope, &user, expected_version)
+        .await?
+        .ok_or(InternalError::UserNotFound {
+            user_id: id.to_uuid_0_8(),
+        })?;
+    ctx.invalidate_user(updated.tenant_id, &id).await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_USER_SUBJECT,
+                AUDIT_ACTION_USER_UPDATE,
+                AUDIT_TARGET_USER,
+                id,
+            )
+            .with_tenant(updated.tenant_id)
+            .with_changes(previous.as_ref(), Some(&updated))?,
+    )?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(updated.version))
+        .finish())
 }
 
-/// Http handler for deleting an user.
-#[tracing::instrument(name = "delete_by_id", skip(user), level = "info")]
+/// Http handler for deleting an user, conditionally on `If-Match` as for the
+/// updates. The user is only marked deleted, and purged once the retention
+/// period is over. Users can only delete themselves, administrators any user of
+/// their tenant.
+#[tracing::instrument(name = "delete_by_id", skip(actor, user, if_match), level = "info")]
 pub async fn delete_by_id(
     ctx: web::Data<AppContext>,
+    principal: Principal,
+    scope: TenantScope,
+    actor: AuditActor,
     web::Query(user): web::Query<User>,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> ApiResult {
     let id = user.id.ok_or(InternalError::RequestFormatError {
         reason: "require fields: `_id`".to_string(),
     })?;
+    if id.to_uuid_0_8() != principal.user_id {
+        principal.require_admin()?;
+    }
+    let expected_version = expected_version(if_match.as_deref())?;
 
-    let _: u64 = user_repository::delete_one(&id, ctx.db()).await?;
+    let previous = ctx.users().find_by_id(&scope, &id).await?;
+    if !ctx
+        .users()
+        .delete_one(&scope, &id, expected_version)
+        .await?
+    {
+        return Err(InternalError::UserNotFound {
+            user_id: id.to_uuid_0_8(),
+        });
+    }
+    let tenant_id = previous.as_ref().and_then(|user| user.tenant_id);
+    ctx.invalidate_user(tenant_id, &id).await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_USER_SUBJECT,
+                AUDIT_ACTION_USER_DELETE,
+                AUDIT_TARGET_USER,
+                id,
+            )
+            .with_tenant(tenant_id)
+            .with_changes(previous.as_ref(), None)?,
+    )?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Http handler for restoring a deleted user, before it is purged. Only
+/// administrators can restore users.
+#[tracing::instrument(name = "restore_by_id", skip(actor, user), level = "info")]
+pub async fn restore_by_id(
+    ctx: web::Data<AppContext>,
+    principal: Principal,
+    scope: TenantScope,
+    actor: AuditActor,
+    web::Query(user): web::Query<User>,
+) -> ApiResult {
+    principal.require_admin()?;
+    let id = user.id.ok_or(InternalError::RequestFormatError {
+        reason: "require fields: `_id`".to_string(),
+    })?;
+
+    if !ctx.users().restore_one(&scope, &id).await? {
+        return Err(InternalError::UserNotFound {
+            user_id: id.to_uuid_0_8(),
+        });
+    }
+    let restored = ctx.users().find_by_id(&scope, &id).await?;
+    let tenant_id = restored.and_then(|user| user.tenant_id);
+    ctx.invalidate_user(tenant_id, &id).await?;
+    ctx.audit(
+        actor
+            .record(
+                SERVICE_USER_SUBJECT,
+                AUDIT_ACTION_USER_RESTORE,
+                AUDIT_TARGET_USER,
+                id,
+            )
+            .with_tenant(tenant_id),
+    )?;
     Ok(HttpResponse::Ok().finish())
 }
@@ -1,25 +1,56 @@
+mod actor;
 mod context;
 mod controller;
 mod model;
 mod repository;
+mod retention;
 mod secrets;
 mod settings;
 
-use crate::{context::AppContext, settings::Settings};
+use crate::{
+    actor::tenant_event_handler::TenantEventHandler,
+    context::AppContext,
+    repository::{mongo_user_repository::MongoUserRepository, user_repository::UserRepository},
+    settings::Settings,
+};
+use actix::{Actor, Addr};
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 use actix_web_opentelemetry::RequestTracing;
 use common::{
     client::{
         cache_redis::{self, Cache, CachePool},
         db_mongo,
+        DbBackend,
+    },
+    error::{InternalError, REDACTED_ERRORS},
+    model::event::v1::{
+        audit::prelude::SERVICE_AUDIT_SUBJECT,
+        tenant::prelude::SERVICE_TENANT_SUBJECT,
+        user::prelude::SERVICE_USER_SUBJECT,
+        Event,
+    },
+    util::{
+        access_token::AccessTokenKey,
+        actix_json_config::json_extractor_config,
+        leader_election::LeaderElection,
+        retention::RetentionPurger,
+        telemetry,
     },
-    error::REDACTED_ERRORS,
-    util::{actix_json_config::json_extractor_config, telemetry},
+};
+use futures::FutureExt;
+use nats_actor::{
+    publisher::{NatsPublisher, NatsPublisherConfig},
+    subscriber::{subscribe_to_nats, NatsStreamMessage, NatsSubscriberConfig},
 };
 use secrets::Secrets;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info, warn};
 use tracing_actix_web::TracingLogger;
 
+// Lock electing the replica purging the deleted users
+const RETENTION_LEADER_LOCK: &str = "retention_leader";
+const RETENTION_LEADER_LEASE: Duration = Duration::from_secs(30);
+
 pub async fn server() -> Result<(), std::io::Error> {
     // configure tracing subscriber
     let app_name = concat!(env!("CARGO_PKG_NAME"), "-", env!("CARGO_PKG_VERSION")).to_string();
@@ -52,25 +83,101 @@ pub async fn start_web_service(
     );
 
     let secrets: Secrets = secrets::read(&configuration).await?;
+    // Verifies the access tokens asserting the principal of the requests
+    let access_token_key = AccessTokenKey::new(secrets.access_token.signing_key.clone());
+    let trusted_proxies = configuration.trusted_proxies.clone();
 
-    let db_client = db_mongo::connect(app_name, &configuration.db, &secrets.db)
+    let users = connect_user_repository(app_name, &configuration, &secrets)
         .await
-        .expect("db client connection failure");
+        .expect("db setup failure");
+
+    let cache_pool: CachePool = cache_redis::connect(&configuration.cache, &secrets.cache).await?;
+    let cache_client: Cache = cache_redis::Cache::new(cache_pool, env!("CARGO_PKG_NAME"))
+        .with_local_tier(configuration.cache.local.as_ref());
+    let pubsub_client = cache_redis::connect_pubsub(&configuration.cache, &secrets.cache).await?;
+
+    // Evict the values invalidated by any replica from the in-process tier
+    let local_cache = cache_client.clone();
+    actix_web::rt::spawn(async move {
+        if let Err(err) = local_cache.follow_invalidations(&pubsub_client).await {
+            error!("Failed to follow cache invalidations: {}", err);
+        }
+    });
+
+    // Start the NATS publisher actor.
+    let publisher = NatsPublisher::start_new(NatsPublisherConfig {
+        client_settings: configuration.nats.clone(),
+        subject: SERVICE_USER_SUBJECT.into(),
+        mailbox_size: configuration.application.nats_publisher_mailbox_size,
+    })
+    .await
+    .expect("nats connection setup failure");
+
+    // Start the NATS publisher actor of the audit records.
+    let audit_publisher = NatsPublisher::start_new(NatsPublisherConfig {
+        client_settings: configuration.nats.clone(),
+        subject: SERVICE_AUDIT_SUBJECT.into(),
+        mailbox_size: configuration.application.nats_publisher_mailbox_size,
+    })
+    .await
+    .expect("nats connection setup failure");
 
-    let cache_pool: CachePool = cache_redis::connect(&configuration.cache, &secrets.cache)?;
-    let cache_client: Cache = cache_redis::Cache::new(cache_pool);
+    let election_cache = cache_client.clone();
 
     // Instantiate the application context. This application state will be
     // cloned for each Actix thread but the Arc of the DbContext will be
     // reused in each Actix thread.
     let app_context = web::Data::new(AppContext {
-        db: Arc::new(db_client),
+        users,
         cache: Arc::new(cache_client),
+        event_publisher: Arc::new(publisher),
+        audit_publisher: Arc::new(audit_publisher),
+    });
+
+    // Purge the users deleted for longer than the retention period, from the
+    // leading replica only
+    let context = Arc::clone(&app_context);
+    let retention_purger = RetentionPurger::new(
+        configuration.retention.clone(),
+        Box::new(move |deleted_before, limit| {
+            let context = Arc::clone(&context);
+            async move { retention::purge_deleted_users(&context, deleted_before, limit).await }
+                .boxed_local()
+        }),
+    )
+    .start();
+    LeaderElection::new(
+        election_cache,
+        RETENTION_LEADER_LOCK,
+        RETENTION_LEADER_LEASE,
+    )
+    .with_subscriber(retention_purger.recipient())
+    .start();
+
+    // Provision the users of the tenants as they are created
+    let tenant_event_handler = TenantEventHandler {
+        context: Arc::clone(&app_context),
+    }
+    .start();
+    let config = NatsSubscriberConfig {
+        client_settings: configuration.nats.clone(),
+        subject: SERVICE_TENANT_SUBJECT.into(),
+        mailbox_size: configuration.application.nats_subscriber_mailbox_size,
+    };
+    actix::spawn(async move {
+        subscribe_to_nats(config, move |msg: NatsStreamMessage| {
+            forward_event(&tenant_event_handler, msg);
+            Ok(())
+        })
+        .await
+        .expect("nats connection/subscriber setup failure");
     });
 
     let server = HttpServer::new(move || {
         App::new()
             .app_data(app_context.clone())
+            .app_data(access_token_key.clone())
+            .app_data(trusted_proxies.clone())
             .app_data(json_extractor_config(4096))
             .wrap(TracingLogger::default())
             .wrap(RequestTracing::new())
@@ -87,6 +194,79 @@ pub async fn start_web_service(
     Ok(())
 }
 
+/// The store of the users configured by `db_backend`, connected and brought
+/// to the current schema.
+async fn connect_user_repository(
+    app_name: &str,
+    configuration: &Settings,
+    secrets: &Secrets,
+) -> Result<Arc<dyn UserRepository>, InternalError> {
+    let missing = |what: &str| InternalError::DbError {
+        cause: format!(
+            "missing {what} of the `{:?}` db backend",
+            configuration.db_backend
+        ),
+    };
+
+    match configuration.db_backend {
+        DbBackend::Mongo => {
+            let db_secrets = secrets.db.as_ref().ok_or_else(|| missing("secrets"))?;
+            let mongo_client =
+                db_mongo::connect_client(app_name, &configuration.db, db_secrets).await?;
+            let db_client = mongo_client.database(&configuration.db.database_name);
+            db_mongo::migrate(&db_client, &repository::migrations::SCHEMA).await?;
+
+            let users = MongoUserRepository::new(
+                mongo_client,
+                &configuration.db.database_name,
+                configuration.tenant_isolation,
+            );
+            users.create_indexes().await?;
+            Ok(Arc::new(users))
+        }
+        #[cfg(feature = "postgres")]
+        DbBackend::Postgres => {
+            use crate::repository::postgres_user_repository::{self, PostgresUserRepository};
+            use common::client::{db_postgres, tenant_isolation::TenantIsolation};
+
+            if configuration.tenant_isolation != TenantIsolation::SharedCollection {
+                return Err(InternalError::DbError {
+                    cause: "the `postgres` db backend only supports the `shared_collection` \
+                            tenant isolation"
+                        .to_string(),
+                });
+            }
+            let db_settings = configuration
+                .postgres
+                .as_ref()
+                .ok_or_else(|| missing("settings"))?;
+            let db_secrets = secrets
+                .postgres
+                .as_ref()
+                .ok_or_else(|| missing("secrets"))?;
+            let pool = db_postgres::connect(db_settings, db_secrets).await?;
+            db_postgres::migrate(&pool, &postgres_user_repository::MIGRATOR).await?;
+            Ok(Arc::new(PostgresUserRepository::new(pool)))
+        }
+        #[cfg(not(feature = "postgres"))]
+        DbBackend::Postgres => Err(InternalError::DbError {
+            cause: "the `postgres` db backend needs a build with the `postgres` feature"
+                .to_string(),
+        }),
+    }
+}
+
+fn forward_event(handler: &Addr<TenantEventHandler>, msg: NatsStreamMessage) {
+    info!("Received event {:?}", msg);
+    let event: Result<Event, _> = serde_json::from_slice::<cloudevents::Event>(&msg.msg.data)
+        .map_err(InternalError::from)
+        .and_then(|event| event.try_into());
+    match event {
+        Ok(event) => handler.do_send(event),
+        Err(err) => warn!("Skipping unreadable event: {}", err),
+    }
+}
+
 pub async fn not_found() -> impl Responder {
     HttpResponse::NotFound().body("the requested resource does not exist")
 }
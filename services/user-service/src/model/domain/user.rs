@@ -1,13 +1,23 @@
-use mongodb::bson::{self, doc, Bson};
-use redis::{FromRedisValue, RedisResult, RedisWrite, ToRedisArgs, Value};
+#[cfg(feature = "postgres")]
+use common::client::postgres_repository::{Column, ColumnType, PostgresEntity};
+use common::client::{
+    mongo_filter::{FilterField, FilterFieldType},
+    mongo_repository::MongoEntity,
+    mongo_search::SearchField,
+};
+use mongodb::bson::{self, Bson};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::{fmt, fmt::Formatter};
 use strum::{Display, EnumString};
 
 pub mod prelude {
+    use common::client::cache_key::CacheEntity;
+
     // Collection name
     pub const COLLECTION_USERS: &str = "users";
+    // Table name, with the `postgres` db backend
+    pub const TABLE_USERS: &str = "users";
 
     // User fields.
     pub const ID: &str = "_id";
@@ -17,11 +27,13 @@ pub mod prelude {
     pub const PHONE: &str = "PHONE";
     pub const STATUS: &str = "STATUS";
     pub const ROLE: &str = "ROLE";
+    pub const TENANT_ID: &str = "TENANT_ID";
     pub const CREATED_AT: &str = "CREATED_AT";
     pub const UPDATED_AT: &str = "UPDATED_AT";
+    pub use common::client::mongo_repository::{DELETED_AT, VERSION};
 
     // Cache keys
-    pub const CACHE_KEY_PREFIX_USER_ID: &str = "_id";
+    pub const CACHE_ENTITY_USER: CacheEntity = CacheEntity::new("user", 1);
     pub const CACHE_USER_EXPIRY: usize = 600;
 }
 
@@ -75,37 +87,80 @@ pub struct User {
     pub status: Option<UserStatus>,
     #[serde(rename = "ROLE")]
     pub role: Option<UserRole>,
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: Option<bson::Uuid>,
     // #[serde_as(as = "Option<bson::DateTime>")]
     #[serde(rename = "CREATED_AT")]
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
     // #[serde_as(as = "Option<bson::DateTime>")]
     #[serde(rename = "UPDATED_AT")]
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "DELETED_AT")]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "VERSION")]
+    pub version: Option<i64>,
 }
 
-impl ToRedisArgs for User {
-    fn write_redis_args<W>(&self, output: &mut W)
-    where
-        W: ?Sized + RedisWrite,
-    {
-        output.write_arg_fmt(serde_json::to_string(self).unwrap());
+impl MongoEntity for User {
+    type Id = bson::Uuid;
+
+    const COLLECTION: &'static str = prelude::COLLECTION_USERS;
+    const SORT_FIELDS: &'static [&'static str] =
+        &[prelude::EMAIL, prelude::CREATED_AT, prelude::UPDATED_AT];
+    const FILTER_FIELDS: &'static [FilterField] = &[
+        FilterField::new(prelude::ID, FilterFieldType::Uuid),
+        FilterField::new(prelude::EMAIL, FilterFieldType::String),
+        FilterField::new(prelude::FIRST_NAME, FilterFieldType::String),
+        FilterField::new(prelude::LAST_NAME, FilterFieldType::String),
+        FilterField::new(prelude::PHONE, FilterFieldType::String),
+        FilterField::new(
+            prelude::STATUS,
+            FilterFieldType::Enum(&["Active", "Inactive"]),
+        ),
+        FilterField::new(prelude::ROLE, FilterFieldType::Enum(&["Admin", "User"])),
+        FilterField::new(prelude::TENANT_ID, FilterFieldType::Uuid),
+        FilterField::new(prelude::CREATED_AT, FilterFieldType::DateTime),
+        FilterField::new(prelude::UPDATED_AT, FilterFieldType::DateTime),
+    ];
+    const SEARCH_FIELDS: &'static [SearchField] = &[
+        SearchField::new(prelude::EMAIL, 3),
+        SearchField::new(prelude::FIRST_NAME, 2),
+        SearchField::new(prelude::LAST_NAME, 2),
+    ];
+    const SOFT_DELETE: bool = true;
+    const VERSIONED: bool = true;
+    const TIMESTAMPED: bool = true;
+
+    fn id(&self) -> Option<&bson::Uuid> {
+        self.id.as_ref()
     }
-}
 
-impl FromRedisValue for User {
-    fn from_redis_value(value: &Value) -> RedisResult<Self> {
-        match *value {
-            redis::Value::Data(ref value_slice) => match serde_json::from_slice(value_slice) {
-                Err(_) => Err((redis::ErrorKind::TypeError, "Can't serialize value").into()),
-                Ok(user) => Ok(user),
-            },
-            _ => Err((
-                redis::ErrorKind::ResponseError,
-                "Response type not Profile compatible.",
-            )
-                .into()),
-        }
+    fn set_id(&mut self, id: bson::Uuid) {
+        self.id = Some(id);
     }
+
+    fn version(&self) -> Option<i64> {
+        self.version
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl PostgresEntity for User {
+    const TABLE: &'static str = prelude::TABLE_USERS;
+    const COLUMNS: &'static [Column] = &[
+        Column::new(prelude::ID, "id", ColumnType::Uuid),
+        Column::new(prelude::EMAIL, "email", ColumnType::Text),
+        Column::new(prelude::FIRST_NAME, "first_name", ColumnType::Text),
+        Column::new(prelude::LAST_NAME, "last_name", ColumnType::Text),
+        Column::new(prelude::PHONE, "phone", ColumnType::Text),
+        Column::new(prelude::STATUS, "status", ColumnType::Text),
+        Column::new(prelude::ROLE, "role", ColumnType::Text),
+        Column::new(prelude::TENANT_ID, "tenant_id", ColumnType::Uuid),
+        Column::new(prelude::CREATED_AT, "created_at", ColumnType::TimestampTz),
+        Column::new(prelude::UPDATED_AT, "updated_at", ColumnType::TimestampTz),
+        Column::new(prelude::DELETED_AT, "deleted_at", ColumnType::TimestampTz),
+        Column::new(prelude::VERSION, "version", ColumnType::BigInt),
+    ];
 }
 
 impl fmt::Display for User {
@@ -0,0 +1,105 @@
+use crate::model::domain::user::{User, UserRole, UserStatus};
+use bson::Uuid;
+use serde::Deserialize;
+use validator::Validate;
+
+/// The fields a user is created with. The id and the timestamps are set by
+/// the service, the status and the role by administrators only.
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct CreateUser {
+    #[validate(required, email)]
+    #[serde(rename = "EMAIL")]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "FIRST_NAME")]
+    pub first_name: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "LAST_NAME")]
+    pub last_name: Option<String>,
+    #[validate(length(min = 1, max = 32))]
+    #[serde(rename = "PHONE")]
+    pub phone_number: Option<String>,
+    #[serde(rename = "STATUS")]
+    pub status: Option<UserStatus>,
+    #[serde(rename = "ROLE")]
+    pub role: Option<UserRole>,
+    #[serde(rename = "TENANT_ID")]
+    pub tenant_id: Option<Uuid>,
+}
+
+impl CreateUser {
+    /// Whether the request sets fields only administrators can set.
+    pub fn requires_admin(&self) -> bool {
+        self.status.is_some() || self.role.is_some()
+    }
+}
+
+impl From<CreateUser> for User {
+    fn from(create: CreateUser) -> Self {
+        User {
+            id: None,
+            email: create.email,
+            first_name: create.first_name,
+            last_name: create.last_name,
+            phone_number: create.phone_number,
+            status: create.status,
+            role: create.role,
+            tenant_id: create.tenant_id,
+            created_at: None,
+            updated_at: None,
+            deleted_at: None,
+            version: None,
+        }
+    }
+}
+
+/// The fields of a user that can be changed, the status and the role by
+/// administrators only. The tenant of a user never changes.
+#[derive(Debug, Deserialize, Clone, Validate)]
+pub struct UpdateUser {
+    #[validate(required)]
+    #[serde(rename = "_id")]
+    pub id: Option<Uuid>,
+    #[validate(email)]
+    #[serde(rename = "EMAIL")]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "FIRST_NAME")]
+    pub first_name: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    #[serde(rename = "LAST_NAME")]
+    pub last_name: Option<String>,
+    #[validate(length(min = 1, max = 32))]
+    #[serde(rename = "PHONE")]
+    pub phone_number: Option<String>,
+    #[serde(rename = "STATUS")]
+    pub status: Option<UserStatus>,
+    #[serde(rename = "ROLE")]
+    pub role: Option<UserRole>,
+}
+
+impl UpdateUser {
+    /// Whether the request changes fields only administrators can change.
+    pub fn requires_admin(&self) -> bool {
+        self.status.is_some() || self.role.is_some()
+    }
+}
+
+impl From<UpdateUser> for User {
+    fn from(update: UpdateUser) -> Self {
+        User {
+            id: update.id,
+            email: update.email,
+            first_name: update.first_name,
+            last_name: update.last_name,
+            phone_number: update.phone_number,
+            status: update.status,
+            role: update.role,
+            tenant_id: None,
+            created_at: None,
+            updated_at: None,
+            deleted_at: None,
+            version: None,
+        }
+    }
+}
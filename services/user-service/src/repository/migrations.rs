@@ -0,0 +1,56 @@
+use crate::model::domain::user::prelude::*;
+use common::{
+    client::db_mongo::{self, IndexDefinition, Migration, Schema},
+    error::InternalError,
+};
+use futures::future::BoxFuture;
+use mongodb::{
+    bson::{doc, Document},
+    Database,
+};
+
+pub const SCHEMA: Schema = Schema {
+    migrations: &[
+        Migration::new(1, "create collections", create_collections),
+        Migration::new(2, "version users", version_users),
+    ],
+    indexes: &[
+        IndexDefinition::new(COLLECTION_USERS, "users_email", &[(EMAIL, 1)]).obsolete(),
+        IndexDefinition::new(
+            COLLECTION_USERS,
+            "users_email_live",
+            &[(EMAIL, 1), (DELETED_AT, 1)],
+        )
+        .obsolete(),
+        // the email of a live user is unique within its tenant. Mongo cannot
+        // restrict a partial index to the documents missing a field, so the
+        // deletion date is part of the key instead: it is null for every live
+        // user, and the soft deleted ones no longer hold their email.
+        IndexDefinition::new(
+            COLLECTION_USERS,
+            "users_tenant_id_email_live",
+            &[(TENANT_ID, 1), (EMAIL, 1), (DELETED_AT, 1)],
+        )
+        .unique(),
+        IndexDefinition::new(COLLECTION_USERS, "users_tenant_id", &[(TENANT_ID, 1)]),
+        // the soft deleted users due for purge
+        IndexDefinition::new(COLLECTION_USERS, "users_deleted_at", &[(DELETED_AT, 1)]),
+    ],
+};
+
+fn create_collections(db: &Database) -> BoxFuture<'_, Result<(), InternalError>> {
+    Box::pin(db_mongo::create_collections(db, &[COLLECTION_USERS]))
+}
+
+fn version_users(db: &Database) -> BoxFuture<'_, Result<(), InternalError>> {
+    Box::pin(async move {
+        db.collection::<Document>(COLLECTION_USERS)
+            .update_many(
+                doc! { VERSION: { "$exists": false } },
+                doc! { "$set": { VERSION: 1_i64 } },
+                None,
+            )
+            .await?;
+        Ok(())
+    })
+}
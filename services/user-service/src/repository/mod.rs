@@ -1 +1,5 @@
+pub mod migrations;
+pub mod mongo_user_repository;
+#[cfg(feature = "postgres")]
+pub mod postgres_user_repository;
 pub mod user_repository;
@@ -0,0 +1,182 @@
+use super::{migrations, user_repository::UserRepository};
+use crate::model::domain::user::User;
+use async_trait::async_trait;
+use bson::Uuid;
+use chrono::{DateTime, Utc};
+use common::{
+    client::{
+        db_mongo,
+        mongo_repository::MongoRepository,
+        tenant_isolation::{TenantIsolation, TenantRouter},
+    },
+    error::InternalError,
+    model::{
+        request::{
+            filter_request::FilterRequest,
+            page_request::PageRequest,
+            search_request::SearchRequest,
+        },
+        response::{page_response::PageResponse, search_hit::SearchHit},
+    },
+    util::tenant_scope::TenantScope,
+};
+use mongodb::{bson::doc, Client};
+
+type Users = MongoRepository<User>;
+
+/// The users stored in the `users` collection, or in the collections or the
+/// databases of their tenants.
+#[derive(Debug, Clone)]
+pub struct MongoUserRepository {
+    tenants: TenantRouter,
+}
+
+impl MongoUserRepository {
+    pub fn new(client: Client, database_name: &str, isolation: TenantIsolation) -> Self {
+        MongoUserRepository {
+            tenants: TenantRouter::new(client, database_name, isolation)
+                .with_indexes(migrations::SCHEMA.indexes),
+        }
+    }
+
+    /// Create the indexes the listings and the search of users rely on, in
+    /// the shared collection. Those of the tenants are created when they are
+    /// provisioned, the tenants provisioned on first use being registered.
+    pub async fn create_indexes(&self) -> Result<(), InternalError> {
+        let repository = Users::new(self.tenants.database());
+        repository.create_sort_indexes().await?;
+        repository.create_search_index().await?;
+        self.tenants.register_existing::<User>().await
+    }
+
+    async fn users(&self, scope: &TenantScope) -> Result<Users, InternalError> {
+        self.tenants.repository(scope).await
+    }
+}
+
+#[async_trait]
+impl UserRepository for MongoUserRepository {
+    fn store_name(&self) -> &'static str {
+        "MongoDB"
+    }
+
+    async fn ping(&self) -> Result<(), InternalError> {
+        db_mongo::ping(self.tenants.database()).await.map(|_| ())
+    }
+
+    async fn scopes(&self) -> Result<Vec<TenantScope>, InternalError> {
+        self.tenants.scopes::<User>().await
+    }
+
+    async fn provision(&self, tenant_id: &Uuid) -> Result<(), InternalError> {
+        self.tenants
+            .provision::<User>(tenant_id.to_uuid_0_8())
+            .await
+    }
+
+    async fn deprovision(&self, tenant_id: &Uuid) -> Result<(), InternalError> {
+        self.tenants
+            .deprovision::<User>(tenant_id.to_uuid_0_8())
+            .await
+    }
+
+    async fn find_by_id(
+        &self,
+        scope: &TenantScope,
+        id: &Uuid,
+    ) -> Result<Option<User>, InternalError> {
+        self.users(scope).await?.find_by_id(id).await
+    }
+
+    async fn find_all_with_query(
+        &self,
+        scope: &TenantScope,
+        cond: &User,
+        filter_request: &FilterRequest,
+    ) -> Result<Vec<User>, InternalError> {
+        let filter = Users::filter_with_expression(cond, filter_request)?;
+        self.users(scope).await?.find_all_matching(filter).await
+    }
+
+    async fn find_all_paginated_with_query(
+        &self,
+        scope: &TenantScope,
+        cond: &User,
+        filter_request: &FilterRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<User>, InternalError> {
+        let filter = Users::filter_with_expression(cond, filter_request)?;
+        self.users(scope)
+            .await?
+            .find_paginated_matching(filter, page_request)
+            .await
+    }
+
+    async fn search(
+        &self,
+        scope: &TenantScope,
+        search_request: &SearchRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<SearchHit<User>>, InternalError> {
+        self.users(scope)
+            .await?
+            .search(doc! {}, search_request, page_request)
+            .await
+    }
+
+    async fn insert_one(&self, scope: &TenantScope, user: &User) -> Result<User, InternalError> {
+        self.users(scope).await?.insert_one(user).await
+    }
+
+    async fn update_by_id(
+        &self,
+        scope: &TenantScope,
+        user: &User,
+        expected_version: Option<i64>,
+    ) -> Result<Option<User>, InternalError> {
+        self.users(scope)
+            .await?
+            .update_by_id_checked(user, expected_version)
+            .await
+    }
+
+    async fn delete_one(
+        &self,
+        scope: &TenantScope,
+        id: &Uuid,
+        expected_version: Option<i64>,
+    ) -> Result<bool, InternalError> {
+        self.users(scope)
+            .await?
+            .delete_by_id_checked(id, expected_version)
+            .await
+    }
+
+    async fn restore_one(&self, scope: &TenantScope, id: &Uuid) -> Result<bool, InternalError> {
+        self.users(scope).await?.restore_by_id(id).await
+    }
+
+    async fn find_deleted_before(
+        &self,
+        scope: &TenantScope,
+        deleted_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<User>, InternalError> {
+        self.users(scope)
+            .await?
+            .find_deleted_before(deleted_before, limit)
+            .await
+    }
+
+    async fn purge_one(
+        &self,
+        scope: &TenantScope,
+        id: &Uuid,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<u64, InternalError> {
+        self.users(scope)
+            .await?
+            .purge_by_id_with_session(id, deleted_before, None)
+            .await
+    }
+}
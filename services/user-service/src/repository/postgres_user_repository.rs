@@ -0,0 +1,168 @@
+use super::user_repository::UserRepository;
+use crate::model::domain::user::{prelude::*, User};
+use async_trait::async_trait;
+use bson::Uuid;
+use chrono::{DateTime, Utc};
+use common::{
+    client::{
+        db_postgres::{self, DatabasePool},
+        postgres_repository::PostgresRepository,
+    },
+    error::InternalError,
+    model::{
+        request::{
+            filter_request::FilterRequest,
+            page_request::PageRequest,
+            search_request::SearchRequest,
+        },
+        response::{page_response::PageResponse, search_hit::SearchHit},
+    },
+    util::tenant_scope::TenantScope,
+};
+use sqlx::migrate::Migrator;
+
+/// The schema of the `users` table, see `migrations`.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+type Users = PostgresRepository<User>;
+
+/// The users stored in the `users` table, shared by the tenants: the only
+/// isolation supported in Postgres.
+#[derive(Debug, Clone)]
+pub struct PostgresUserRepository {
+    pool: DatabasePool,
+}
+
+impl PostgresUserRepository {
+    pub fn new(pool: DatabasePool) -> Self {
+        PostgresUserRepository { pool }
+    }
+
+    // The users of `scope`, told apart by their tenant id
+    fn users(&self, scope: &TenantScope) -> Result<Users, InternalError> {
+        let users = Users::new(&self.pool);
+        match scope.tenant_id() {
+            Some(tenant_id) => users.with_discriminator(
+                TENANT_ID,
+                &serde_json::to_value(Uuid::from_uuid_0_8(tenant_id))?,
+            ),
+            None => Ok(users),
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepository for PostgresUserRepository {
+    fn store_name(&self) -> &'static str {
+        "PostgreSQL"
+    }
+
+    async fn ping(&self) -> Result<(), InternalError> {
+        db_postgres::ping(&self.pool).await
+    }
+
+    async fn scopes(&self) -> Result<Vec<TenantScope>, InternalError> {
+        Ok(vec![TenantScope::Platform])
+    }
+
+    // the tenants share the `users` table
+    async fn provision(&self, _tenant_id: &Uuid) -> Result<(), InternalError> {
+        Ok(())
+    }
+
+    async fn deprovision(&self, _tenant_id: &Uuid) -> Result<(), InternalError> {
+        Ok(())
+    }
+
+    async fn find_by_id(
+        &self,
+        scope: &TenantScope,
+        id: &Uuid,
+    ) -> Result<Option<User>, InternalError> {
+        self.users(scope)?.find_by_id(id).await
+    }
+
+    async fn find_all_with_query(
+        &self,
+        scope: &TenantScope,
+        cond: &User,
+        filter_request: &FilterRequest,
+    ) -> Result<Vec<User>, InternalError> {
+        let filter = Users::filter_with_expression(cond, filter_request)?;
+        self.users(scope)?.find_all_matching(filter).await
+    }
+
+    async fn find_all_paginated_with_query(
+        &self,
+        scope: &TenantScope,
+        cond: &User,
+        filter_request: &FilterRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<User>, InternalError> {
+        let filter = Users::filter_with_expression(cond, filter_request)?;
+        self.users(scope)?
+            .find_paginated_matching(filter, page_request)
+            .await
+    }
+
+    async fn search(
+        &self,
+        scope: &TenantScope,
+        search_request: &SearchRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<SearchHit<User>>, InternalError> {
+        self.users(scope)?
+            .search(Default::default(), search_request, page_request)
+            .await
+    }
+
+    async fn insert_one(&self, scope: &TenantScope, user: &User) -> Result<User, InternalError> {
+        self.users(scope)?.insert_one(user).await
+    }
+
+    async fn update_by_id(
+        &self,
+        scope: &TenantScope,
+        user: &User,
+        expected_version: Option<i64>,
+    ) -> Result<Option<User>, InternalError> {
+        self.users(scope)?
+            .update_by_id_checked(user, expected_version)
+            .await
+    }
+
+    async fn delete_one(
+        &self,
+        scope: &TenantScope,
+        id: &Uuid,
+        expected_version: Option<i64>,
+    ) -> Result<bool, InternalError> {
+        self.users(scope)?
+            .delete_by_id_checked(id, expected_version)
+            .await
+    }
+
+    async fn restore_one(&self, scope: &TenantScope, id: &Uuid) -> Result<bool, InternalError> {
+        self.users(scope)?.restore_by_id(id).await
+    }
+
+    async fn find_deleted_before(
+        &self,
+        scope: &TenantScope,
+        deleted_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<User>, InternalError> {
+        self.users(scope)?
+            .find_deleted_before(deleted_before, limit)
+            .await
+    }
+
+    async fn purge_one(
+        &self,
+        scope: &TenantScope,
+        id: &Uuid,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<u64, InternalError> {
+        self.users(scope)?.purge_by_id(id, deleted_before).await
+    }
+}
@@ -1,246 +1,112 @@
-use crate::model::domain::user::{prelude::*, User};
-use bson::{from_bson, Uuid};
+use crate::model::domain::user::User;
+use async_trait::async_trait;
+use bson::Uuid;
+use chrono::{DateTime, Utc};
 use common::{
     error::InternalError,
-    model::{domain::pagination::Pagination, response::page_response::PageResponse},
+    model::{
+        request::{
+            filter_request::FilterRequest,
+            page_request::PageRequest,
+            search_request::SearchRequest,
+        },
+        response::{page_response::PageResponse, search_hit::SearchHit},
+    },
+    util::tenant_scope::TenantScope,
 };
-use futures::TryStreamExt;
-use mongodb::{
-    bson::{doc, Document},
-    options::FindOptions,
-    Database,
-};
-
-pub async fn find_by_id(id: &Uuid, db: &Database) -> Result<Option<User>, InternalError> {
-    let filter = doc! { ID: id };
-    let user = db
-        .collection::<User>(COLLECTION_USERS)
-        .find_one(filter, None)
-        .await?;
-    Ok(user)
-}
-
-pub async fn find_by_email(email: &str, db: &Database) -> Result<Option<User>, InternalError> {
-    let filter = doc! { EMAIL: email };
-    let user = db
-        .collection::<User>(COLLECTION_USERS)
-        .find_one(filter, None)
-        .await?;
-    Ok(user)
-}
-
-pub async fn find_all(db: &Database) -> Result<Vec<User>, InternalError> {
-    let cursor = db
-        .collection::<User>(COLLECTION_USERS)
-        .find(doc! {}, None)
-        .await?;
-    Ok(cursor.try_collect().await?)
-}
-
-pub async fn find_all_paginated(
-    page: u64,
-    page_size: u64,
-    db: &Database,
-) -> Result<PageResponse<User>, InternalError> {
-    let total = db
-        .collection::<User>(COLLECTION_USERS)
-        .count_documents(doc! {}, None)
-        .await?;
-
-    let mut options = FindOptions::default();
-    options.skip = Some((page - 1) * page_size);
-    options.limit = Some(i64::try_from(page_size)?);
-
-    let cursor = db
-        .collection::<User>(COLLECTION_USERS)
-        .find(doc! {}, options)
-        .await?;
-    let users: Vec<User> = cursor.try_collect().await?;
-
-    let total_pages = (total as f64 / page_size as f64).ceil() as u32;
-
-    let page_info = Pagination {
-        number_of_elements: users.len(),
-        page: page as u32,
-        page_size: page_size as u32,
-        total_pages,
-        total_elements: total as usize,
-    };
-
-    Ok(PageResponse {
-        data: users,
-        page_info,
-    })
-}
-
-pub async fn find_all_with_query(cond: &User, db: &Database) -> Result<Vec<User>, InternalError> {
-    let find_opts = FindOptions::builder().sort(doc! {ID: 1}).build();
-
-    let mut doc = Document::new();
-    if let Some(id) = cond.id {
-        doc.insert(ID, id);
-    }
-    if let Some(email) = &cond.email {
-        doc.insert(EMAIL, &email);
-    }
-    if let Some(first_name) = &cond.first_name {
-        doc.insert(FIRST_NAME, &first_name);
-    }
-    if let Some(last_name) = &cond.last_name {
-        doc.insert(LAST_NAME, &last_name);
-    }
-    if let Some(phone_number) = &cond.phone_number {
-        doc.insert(PHONE, &phone_number);
-    }
-    if let Some(status) = cond.status {
-        doc.insert(STATUS, status);
-    }
-    if let Some(role) = cond.role {
-        doc.insert(ROLE, role);
-    }
-    if let Some(created_at) = cond.created_at {
-        doc.insert(CREATED_AT, created_at);
-    }
-    if let Some(updated_at) = cond.updated_at {
-        doc.insert(UPDATED_AT, updated_at);
-    }
-
-    let cursor = db
-        .collection::<User>(COLLECTION_USERS)
-        .find(doc, find_opts)
-        .await?;
-    Ok(cursor.try_collect().await?)
-}
-
-pub async fn find_all_paginated_with_query(
-    cond: &User,
-    page: u64,
-    page_size: u64,
-    db: &Database,
-) -> Result<PageResponse<User>, InternalError> {
-    let find_opts = FindOptions::builder().sort(doc! {ID: 1}).build();
-
-    let mut doc = Document::new();
-    if let Some(id) = cond.id {
-        doc.insert(ID, id);
-    }
-    if let Some(email) = &cond.email {
-        doc.insert(EMAIL, &email);
-    }
-    if let Some(first_name) = &cond.first_name {
-        doc.insert(FIRST_NAME, &first_name);
-    }
-    if let Some(last_name) = &cond.last_name {
-        doc.insert(LAST_NAME, &last_name);
-    }
-    if let Some(phone_number) = &cond.phone_number {
-        doc.insert(PHONE, &phone_number);
-    }
-    if let Some(status) = cond.status {
-        doc.insert(STATUS, status);
-    }
-    if let Some(role) = cond.role {
-        doc.insert(ROLE, role);
-    }
-    if let Some(created_at) = cond.created_at {
-        doc.insert(CREATED_AT, created_at);
-    }
-    if let Some(updated_at) = cond.updated_at {
-        doc.insert(UPDATED_AT, updated_at);
-    }
-
-    let cursor = db
-        .collection::<User>(COLLECTION_USERS)
-        .find(doc, find_opts)
-        .await?;
-    let users: Vec<User> = cursor.try_collect().await?;
-    let total = users.len();
-
-    let total_pages = (total as f64 / page_size as f64).ceil() as u32;
-
-    let page_info = Pagination {
-        number_of_elements: total,
-        page: page as u32,
-        page_size: page_size as u32,
-        total_pages,
-        total_elements: total as usize,
-    };
-
-    Ok(PageResponse {
-        data: users,
-        page_info,
-    })
-}
-
-pub async fn insert_one(user: &User, db: &Database) -> Result<User, InternalError> {
-    let mut ret = user.clone();
-
-    let res = db
-        .collection::<User>(COLLECTION_USERS)
-        .insert_one(user, None)
-        .await?;
-
-    Ok(from_bson(res.inserted_id).map(|id: Uuid| {
-        ret.id = Some(id);
-        ret
-    })?)
-}
-
-pub async fn update_by_id(user: &User, db: &Database) -> Result<u64, InternalError> {
-    let id = user.id.ok_or(InternalError::RequestFormatError {
-        reason: "require fields: `_id`".to_string(),
-    })?;
-
-    let query = doc! { ID: id };
-
-    let mut update = Document::new();
-    if let Some(email) = &user.email {
-        update.insert(EMAIL, &email);
-    }
-    if let Some(first_name) = &user.first_name {
-        update.insert(FIRST_NAME, &first_name);
-    }
-    if let Some(last_name) = &user.last_name {
-        update.insert(LAST_NAME, &last_name);
-    }
-    if let Some(phone_number) = &user.phone_number {
-        update.insert(PHONE, &phone_number);
-    }
-    if let Some(status) = &user.status {
-        update.insert(STATUS, &status);
-    }
-    if let Some(role) = &user.role {
-        update.insert(ROLE, &role);
-    }
-    if let Some(created_at) = &user.created_at {
-        update.insert(CREATED_AT, &created_at);
-    }
-    if let Some(updated_at) = &user.updated_at {
-        update.insert(UPDATED_AT, &updated_at);
-    }
-
-    let res = db
-        .collection::<User>(COLLECTION_USERS)
-        .update_one(
-            query,
-            doc! {
-                "$set": update,
-            },
-            None,
-        )
-        .await?;
-    Ok(res.modified_count)
-}
-
-pub async fn delete_one(id: &Uuid, db: &Database) -> Result<u64, InternalError> {
-    let res = db
-        .collection::<User>(COLLECTION_USERS)
-        .delete_one(
-            doc! {
-                ID : id
-            },
-            None,
-        )
-        .await?;
-    Ok(res.deleted_count)
+use std::fmt::Debug;
+
+/// The store of the users, Mongo or Postgres as configured by `db_backend`.
+///
+/// Every operation only reaches the users of a tenant scope, isolated as
+/// configured by `tenant_isolation`.
+#[async_trait]
+pub trait UserRepository: Debug + Send + Sync {
+    /// The name of the store, as reported by the health check.
+    fn store_name(&self) -> &'static str;
+
+    /// Check that the store is reachable.
+    async fn ping(&self) -> Result<(), InternalError>;
+
+    /// The scopes holding users, for the jobs across the tenants.
+    async fn scopes(&self) -> Result<Vec<TenantScope>, InternalError>;
+
+    /// Set up where the users of the new tenant `tenant_id` are kept. The
+    /// users of the tenants not provisioned are out of reach.
+    async fn provision(&self, tenant_id: &Uuid) -> Result<(), InternalError>;
+
+    /// Remove where the users of the deleted tenant `tenant_id` were kept.
+    async fn deprovision(&self, tenant_id: &Uuid) -> Result<(), InternalError>;
+
+    async fn find_by_id(
+        &self,
+        scope: &TenantScope,
+        id: &Uuid,
+    ) -> Result<Option<User>, InternalError>;
+
+    /// The users matching the fields set in `cond` and the filter expression
+    /// of `filter_request`.
+    async fn find_all_with_query(
+        &self,
+        scope: &TenantScope,
+        cond: &User,
+        filter_request: &FilterRequest,
+    ) -> Result<Vec<User>, InternalError>;
+
+    async fn find_all_paginated_with_query(
+        &self,
+        scope: &TenantScope,
+        cond: &User,
+        filter_request: &FilterRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<User>, InternalError>;
+
+    async fn search(
+        &self,
+        scope: &TenantScope,
+        search_request: &SearchRequest,
+        page_request: &PageRequest,
+    ) -> Result<PageResponse<SearchHit<User>>, InternalError>;
+
+    /// Insert the user, owned by the tenant of `scope`.
+    async fn insert_one(&self, scope: &TenantScope, user: &User) -> Result<User, InternalError>;
+
+    /// Update the user, provided it is still at `expected_version` when given,
+    /// returning it updated, or `None` when there is no such user.
+    async fn update_by_id(
+        &self,
+        scope: &TenantScope,
+        user: &User,
+        expected_version: Option<i64>,
+    ) -> Result<Option<User>, InternalError>;
+
+    /// Soft delete the user, provided it is still at `expected_version` when
+    /// given, returning whether there was such a user. It is kept until
+    /// purged after the retention period.
+    async fn delete_one(
+        &self,
+        scope: &TenantScope,
+        id: &Uuid,
+        expected_version: Option<i64>,
+    ) -> Result<bool, InternalError>;
+
+    /// Restore the soft deleted user, returning whether there was one.
+    async fn restore_one(&self, scope: &TenantScope, id: &Uuid) -> Result<bool, InternalError>;
+
+    /// Up to `limit` of the users soft deleted before `deleted_before`.
+    async fn find_deleted_before(
+        &self,
+        scope: &TenantScope,
+        deleted_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<User>, InternalError>;
+
+    /// Permanently delete the user if still soft deleted before
+    /// `deleted_before`.
+    async fn purge_one(
+        &self,
+        scope: &TenantScope,
+        id: &Uuid,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<u64, InternalError>;
 }
@@ -0,0 +1,59 @@
+use crate::context::AppContext;
+use chrono::{DateTime, Utc};
+use common::{
+    error::InternalError,
+    model::event::{
+        v1::{
+            user::{prelude::SERVICE_USER_SUBJECT, UserDeletedMessage},
+            Event,
+        },
+        EventMessage,
+        EventMetadata,
+    },
+};
+use nats_actor::EventMessage as NatsEventMessage;
+
+/// Permanently delete up to `limit` of the users soft deleted before
+/// `deleted_before`, tenant after tenant when isolated apart, emitting an
+/// event for every user purged.
+pub async fn purge_deleted_users(
+    ctx: &AppContext,
+    deleted_before: DateTime<Utc>,
+    limit: i64,
+) -> Result<u64, InternalError> {
+    let mut purged = 0;
+    for scope in ctx.users().scopes().await? {
+        let remaining = limit - i64::try_from(purged)?;
+        if remaining <= 0 {
+            break;
+        }
+        let users = ctx
+            .users()
+            .find_deleted_before(&scope, deleted_before, remaining)
+            .await?;
+
+        for (id, tenant_id) in users
+            .iter()
+            .filter_map(|user| Some((user.id?, user.tenant_id)))
+        {
+            // a user restored since is kept
+            if ctx.users().purge_one(&scope, &id, deleted_before).await? == 0 {
+                continue;
+            }
+            purged += 1;
+            ctx.invalidate_user(tenant_id, &id).await?;
+
+            let user_deleted_event = Event::UserDeleted(EventMessage {
+                meta: EventMetadata::new(SERVICE_USER_SUBJECT.into(), "trace_id"),
+                payload: UserDeletedMessage {
+                    user_id: id.to_string(),
+                    tenant_id: tenant_id.map(|tenant_id| tenant_id.to_string()),
+                },
+            });
+            ctx.event_publisher().do_send(NatsEventMessage {
+                event: user_deleted_event.try_into()?,
+            });
+        }
+    }
+    Ok(purged)
+}
@@ -1,7 +1,10 @@
 use crate::settings::Settings;
+#[cfg(feature = "postgres")]
+use common::client::db_postgres::PostgresClientSecrets;
 use common::{
-    client::{cache_redis::RedisClientSecrets, db_mongo::MongoClientSecrets, sm_vault},
+    client::{cache_redis::RedisClientSecrets, db_mongo::MongoClientSecrets, sm_vault, DbBackend},
     error::InternalError,
+    util::access_token::AccessTokenSecrets,
 };
 use serde::Deserialize;
 use vaultrs::client::VaultClient;
@@ -9,7 +12,11 @@ use vaultrs::client::VaultClient;
 #[derive(Debug, Deserialize)]
 pub struct Secrets {
     pub cache: RedisClientSecrets,
-    pub db: MongoClientSecrets,
+    /// The credentials of the configured db backend only.
+    pub db: Option<MongoClientSecrets>,
+    #[cfg(feature = "postgres")]
+    pub postgres: Option<PostgresClientSecrets>,
+    pub access_token: AccessTokenSecrets,
 }
 
 pub async fn read(settings: &Settings) -> Result<Secrets, InternalError> {
@@ -18,11 +25,30 @@ pub async fn read(settings: &Settings) -> Result<Secrets, InternalError> {
     let cache_secrets: RedisClientSecrets =
         sm_vault::get_secret_value(&vault_client, &settings.cache_secrets_path).await?;
 
-    let db_secrets: MongoClientSecrets =
-        sm_vault::get_secret_value(&vault_client, &settings.db_secrets_path).await?;
+    let db_secrets: Option<MongoClientSecrets> = match settings.db_backend {
+        DbBackend::Mongo => {
+            Some(sm_vault::get_secret_value(&vault_client, &settings.db_secrets_path).await?)
+        }
+        DbBackend::Postgres => None,
+    };
+
+    #[cfg(feature = "postgres")]
+    let postgres_secrets: Option<PostgresClientSecrets> =
+        match (settings.db_backend, &settings.postgres_secrets_path) {
+            (DbBackend::Postgres, Some(path)) => {
+                Some(sm_vault::get_secret_value(&vault_client, path).await?)
+            }
+            _ => None,
+        };
+
+    let access_token_secrets: AccessTokenSecrets =
+        sm_vault::get_secret_value(&vault_client, &settings.access_token_secrets_path).await?;
 
     Ok(Secrets {
         cache: cache_secrets,
         db: db_secrets,
+        #[cfg(feature = "postgres")]
+        postgres: postgres_secrets,
+        access_token: access_token_secrets,
     })
 }
@@ -1,21 +1,44 @@
+#[cfg(feature = "postgres")]
+use common::client::db_postgres::PostgresClientSettings;
 use common::{
     client::{
         cache_redis::RedisClientSettings,
         db_mongo::MongoClientSettings,
         sm_vault::{VaultClientConfig, VaultKvPath},
+        tenant_isolation::TenantIsolation,
+        DbBackend,
     },
-    util::configuration,
+    util::{client_ip::TrustedProxies, configuration, retention::RetentionSettings},
 };
+use nats_actor::NatsClientSettings;
 use serde_aux::field_attributes::deserialize_number_from_string;
 
 #[derive(Debug, serde::Deserialize, Clone)]
 pub struct Settings {
     pub application: ApplicationSettings,
     pub vault: VaultClientConfig,
+    /// The store of the users, `mongo` unless set.
+    #[serde(default)]
+    pub db_backend: DbBackend,
+    /// How the users of the tenants are kept apart, in a shared collection
+    /// unless set.
+    #[serde(default)]
+    pub tenant_isolation: TenantIsolation,
     pub db: MongoClientSettings,
     pub db_secrets_path: VaultKvPath,
+    #[cfg(feature = "postgres")]
+    pub postgres: Option<PostgresClientSettings>,
+    #[cfg(feature = "postgres")]
+    pub postgres_secrets_path: Option<VaultKvPath>,
     pub cache: RedisClientSettings,
     pub cache_secrets_path: VaultKvPath,
+    pub access_token_secrets_path: VaultKvPath,
+    pub nats: NatsClientSettings,
+    pub retention: RetentionSettings,
+    /// The proxies trusted to forward the address of the clients, none
+    /// unless set.
+    #[serde(default)]
+    pub trusted_proxies: TrustedProxies,
     pub log: LogSettings,
     pub tracer: Tracer,
 }
@@ -30,6 +53,8 @@ pub struct ApplicationSettings {
     pub workers: usize,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub max_json_payload_size: usize,
+    pub nats_publisher_mailbox_size: usize,
+    pub nats_subscriber_mailbox_size: usize,
 }
 
 #[derive(Debug, serde::Deserialize, Clone)]